@@ -0,0 +1,214 @@
+//! Benchmarks for working with a large file, which should stay responsive no matter its size.
+//! The number of lines can be changed with `HECTO_BENCH_LINES`, e.g. to try a multi-hundred-megabyte file,
+//! and the length of the single long line with `HECTO_BENCH_LINE_LENGTH`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use hecto::bench::{DisplayOptions, Edit, Move, SearchOptions, Size, UIComponent, View};
+
+use std::{
+  env,
+  fmt::Write as _,
+  fs::{remove_file, write},
+  hint::black_box,
+  path::PathBuf,
+};
+
+/// About 80 MB of text
+const DEFAULT_LINES: usize = 1_000_000;
+/// Words in the long line, about 5 MB of text, as in a minified file
+const DEFAULT_LINE_LENGTH: usize = 500_000;
+/// Only appears once, in the middle of the file
+const NEEDLE: &str = "needle";
+
+struct LargeFile {
+  path: PathBuf,
+}
+
+impl LargeFile {
+  fn create() -> Self {
+    let lines = env_or("HECTO_BENCH_LINES", DEFAULT_LINES);
+
+    let mut text = String::new();
+    for idx in 0..lines {
+      if idx == lines / 2 {
+        text.push_str(NEEDLE);
+      }
+      let _ = writeln!(
+        text,
+        "{idx:>8} INFO request handled in {} ms — état: ok ✓",
+        idx % 997
+      );
+    }
+    Self::write("log", &text)
+  }
+
+  /// A file of a single line, with the needle in the middle of it
+  fn with_long_line() -> Self {
+    let words = env_or("HECTO_BENCH_LINE_LENGTH", DEFAULT_LINE_LENGTH);
+
+    let mut text = String::new();
+    for idx in 0..words {
+      if idx == words / 2 {
+        text.push_str(NEEDLE);
+      }
+      let _ = write!(text, "{} état: ok ✓\t", idx % 997);
+    }
+    text.push('\n');
+    Self::write("min.js", &text)
+  }
+
+  fn write(extension: &str, text: &str) -> Self {
+    let path = env::temp_dir().join(format!("hecto-bench-{}.{extension}", std::process::id()));
+    write(&path, text).expect("Could not write the benchmark file");
+    Self { path }
+  }
+
+  fn path(&self) -> &str {
+    self
+      .path
+      .to_str()
+      .expect("Temporary path is not valid UTF-8")
+  }
+
+  /// A view of the file, with the caret in the middle of it
+  fn open_in_middle(&self) -> View {
+    let mut view = View::default();
+    view.resize(Size {
+      height: 50,
+      width: 120,
+    });
+    view
+      .load(self.path())
+      .expect("Could not load the benchmark file");
+    view.enter_search();
+    view
+      .search(NEEDLE, SearchOptions::default())
+      .expect("Needle is a valid query");
+    view.exit_search();
+    view
+  }
+}
+
+impl Drop for LargeFile {
+  fn drop(&mut self) {
+    let _ = remove_file(&self.path);
+  }
+}
+
+fn large_file(criterion: &mut Criterion) {
+  let file = LargeFile::create();
+  let mut group = criterion.benchmark_group("large file");
+  group.sample_size(10);
+
+  group.bench_function("load", |bencher| {
+    bencher.iter(|| {
+      let mut view = View::default();
+      view
+        .load(file.path())
+        .expect("Could not load the benchmark file");
+      view
+    });
+  });
+
+  group.bench_function("search whole file", |bencher| {
+    let mut view = file.open_in_middle();
+    bencher.iter(|| {
+      view.enter_search();
+      view
+        .search(black_box(NEEDLE), SearchOptions::default())
+        .expect("Needle is a valid query");
+      view.exit_search();
+    });
+  });
+
+  group.bench_function("count matches in whole file", |bencher| {
+    let mut view = file.open_in_middle();
+    bencher.iter(|| {
+      view.enter_search();
+      view
+        .search(black_box("ms"), SearchOptions::default())
+        .expect("Query is valid");
+      view.count_matches();
+      view.exit_search();
+    });
+  });
+
+  group.bench_function("save", |bencher| {
+    let mut view = file.open_in_middle();
+    bencher.iter(|| view.save().expect("Could not save the benchmark file"));
+  });
+
+  group.finish();
+
+  let mut view = file.open_in_middle();
+  criterion.bench_function("page down and up in the middle", |bencher| {
+    bencher.iter(|| {
+      view.handle_move_command(Move::PageDown);
+      view.handle_move_command(Move::PageUp);
+    });
+  });
+
+  criterion.bench_function("type and delete a char in the middle", |bencher| {
+    bencher.iter(|| {
+      view.handle_edit_command(Edit::Insert('x'));
+      view.handle_edit_command(Edit::DeleteBackward);
+    });
+  });
+
+  criterion.bench_function("insert and undo a line in the middle", |bencher| {
+    bencher.iter(|| {
+      view.handle_edit_command(Edit::InsertNewline);
+      view.handle_edit_command(Edit::Undo);
+    });
+  });
+}
+
+fn long_line(criterion: &mut Criterion) {
+  let file = LargeFile::with_long_line();
+  let mut view = file.open_in_middle();
+  view.set_display_options(DisplayOptions {
+    soft_wrap: true,
+    ..DisplayOptions::default()
+  });
+  let mut group = criterion.benchmark_group("long line");
+
+  group.bench_function("move right and left in the middle", |bencher| {
+    bencher.iter(|| {
+      view.handle_move_command(Move::Right);
+      view.handle_move_command(Move::Left);
+    });
+  });
+
+  group.bench_function("move down and up a row in the middle", |bencher| {
+    bencher.iter(|| {
+      view.handle_move_command(Move::Down);
+      view.handle_move_command(Move::Up);
+    });
+  });
+
+  group.bench_function("move to the end and the start", |bencher| {
+    bencher.iter(|| {
+      view.handle_move_command(Move::EndOfLine);
+      view.handle_move_command(Move::StartOfLine);
+    });
+  });
+
+  group.bench_function("type and delete a char", |bencher| {
+    bencher.iter(|| {
+      view.handle_edit_command(Edit::Insert('x'));
+      view.handle_edit_command(Edit::DeleteBackward);
+    });
+  });
+
+  group.finish();
+}
+
+fn env_or(name: &str, default: usize) -> usize {
+  env::var(name)
+    .ok()
+    .and_then(|value| value.parse().ok())
+    .unwrap_or(default)
+}
+
+criterion_group!(benches, large_file, long_line);
+criterion_main!(benches);
@@ -41,6 +41,13 @@ impl AnnotatedString {
     });
   }
 
+  /// Appends annotated text, e.g. a cell standing in for the line break after the text of a line
+  pub fn push_annotated(&mut self, string: &str, annotation_type: AnnotationType) {
+    let start = self.string.len();
+    self.string.push_str(string);
+    self.add_annotation(annotation_type, start, self.string.len());
+  }
+
   pub fn replace(&mut self, start: ByteIdx, end: ByteIdx, new_string: &str) {
     let end = min(end, self.string.len());
 
@@ -116,14 +123,6 @@ impl AnnotatedString {
       annotation.start < annotation.end && annotation.start < self.string.len()
     });
   }
-
-  pub fn truncate_left_until(&mut self, until: ByteIdx) {
-    self.replace(0, until, "");
-  }
-
-  pub fn truncate_right_from(&mut self, from: ByteIdx) {
-    self.replace(from, self.string.len(), "");
-  }
 }
 
 impl Display for AnnotatedString {
@@ -1,9 +1,17 @@
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+use serde::Deserialize;
+
+/// Named in kebab-case in the theme of the configuration file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum AnnotationType {
   /// A regular match
   Match,
   /// A match that is currently selected
   SelectedMatch,
+  /// Text selected by the user
+  Selection,
+  /// Line numbers in the gutter, except the one of the caret line
+  LineNumber,
   Number,
   Keyword,
   Type,
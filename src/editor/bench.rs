@@ -0,0 +1,11 @@
+//! What the benchmarks in `benches/` need from the editor, which is otherwise private to the crate
+
+pub use crate::{
+  editor::{
+    command::{Edit, Move},
+    displayoptions::DisplayOptions,
+    searchoptions::SearchOptions,
+    uicomponents::{UIComponent, View},
+  },
+  prelude::Size,
+};
@@ -0,0 +1,107 @@
+use crate::editor::{document::Document, uicomponents::View};
+
+use std::{fs::canonicalize, io::Error, path::Path};
+
+/// All documents opened in the editor, one of which is shown
+pub struct BufferList {
+  /// Never empty
+  documents: Vec<Document>,
+  current: usize,
+}
+
+impl Default for BufferList {
+  fn default() -> Self {
+    Self {
+      documents: vec![Document::default()],
+      current: 0,
+    }
+  }
+}
+
+impl BufferList {
+  pub fn current(&self) -> &Document {
+    &self.documents[self.current]
+  }
+
+  pub fn current_mut(&mut self) -> &mut Document {
+    &mut self.documents[self.current]
+  }
+
+  pub const fn current_idx(&self) -> usize {
+    self.current
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Document> {
+    self.documents.iter()
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Document> {
+    self.documents.iter_mut()
+  }
+
+  /// Opens the file and makes it the current document, or switches to it if it's already open.
+  /// The untouched, unnamed buffer the editor starts with is replaced.
+  /// Returns false if the file was already open.
+  pub fn open(&mut self, file_name: &str) -> Result<bool, Error> {
+    if let Some(idx) = self.documents.iter().position(|document| {
+      document
+        .view
+        .file_path()
+        .is_some_and(|path| is_same_file(&path, Path::new(file_name)))
+    }) {
+      self.current = idx;
+      return Ok(false);
+    }
+
+    let mut view = View::default();
+    view.load(file_name)?;
+    let document = Document::from(view);
+
+    if self.current().is_untouched() {
+      *self.current_mut() = document;
+    } else {
+      self.documents.push(document);
+      self.current = self.documents.len().saturating_sub(1);
+    }
+    Ok(true)
+  }
+
+  pub fn select(&mut self, idx: usize) {
+    if idx < self.documents.len() {
+      self.current = idx;
+    }
+  }
+
+  pub fn select_next(&mut self) {
+    self.current = self
+      .current
+      .saturating_add(1)
+      .checked_rem(self.documents.len())
+      .unwrap_or(0);
+  }
+
+  pub fn select_previous(&mut self) {
+    self.current = self
+      .current
+      .checked_sub(1)
+      .unwrap_or_else(|| self.documents.len().saturating_sub(1));
+  }
+
+  /// Removes the current document and returns it.
+  /// Closing the last document leaves an empty one behind.
+  pub fn close_current(&mut self) -> Document {
+    let document = self.documents.remove(self.current);
+    if self.documents.is_empty() {
+      self.documents.push(Document::default());
+    }
+    self.current = self.current.min(self.documents.len().saturating_sub(1));
+    document
+  }
+}
+
+fn is_same_file(left: &Path, right: &Path) -> bool {
+  match (canonicalize(left), canonicalize(right)) {
+    (Ok(left), Ok(right)) => left == right,
+    _ => left == right,
+  }
+}
@@ -1,30 +1,16 @@
-use crossterm::event::{
-  KeyCode::{Backspace, Char, Delete, Enter, Tab},
-  KeyEvent, KeyModifiers,
-};
-
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Edit {
   Insert(char),
+  /// Insert a (possibly multi line) text in one go, e.g. when pasted into the terminal
+  InsertText(String),
   InsertNewline,
   Delete,
   DeleteBackward,
-}
-
-impl TryFrom<KeyEvent> for Edit {
-  type Error = String;
-
-  fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-    match (event.code, event.modifiers) {
-      (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => Ok(Self::Insert(character)),
-      (Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
-      (Enter, KeyModifiers::NONE) => Ok(Self::InsertNewline),
-      (Backspace, KeyModifiers::NONE) => Ok(Self::DeleteBackward),
-      (Delete, KeyModifiers::NONE) => Ok(Self::Delete),
-      _ => Err(format!(
-        "Unsupported key code {:?} with modifiers {:?}",
-        event.code, event.modifiers
-      )),
-    }
-  }
+  /// Delete from the start of the previous word up to the caret
+  DeleteWordBackward,
+  Undo,
+  Redo,
+  Cut,
+  Copy,
+  Paste,
 }
@@ -9,13 +9,16 @@ pub use crate::{
 
 use crossterm::event::Event;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Command {
   Move(Move),
+  /// Movement while holding Shift, which extends the selection
+  Select(Move),
   Edit(Edit),
   System(System),
 }
 
+/// Commands of events other than key presses, which are looked up in the keymap
 // clippy::as_conversions: Will run into problems for rare edge case systems where usize < u16
 #[allow(clippy::as_conversions)]
 impl TryFrom<Event> for Command {
@@ -23,15 +26,10 @@ impl TryFrom<Event> for Command {
 
   fn try_from(event: Event) -> Result<Self, Self::Error> {
     match event {
-      Event::Key(key_event) => Edit::try_from(key_event)
-        // Try to convert key_event into an Edit event
-        .map(Command::Edit)
-        // If fail, try to convert key_event into a Move event
-        .or_else(|_| Move::try_from(key_event).map(Command::Move))
-        // If fail, try to convert key_event into a System event
-        .or_else(|_| System::try_from(key_event).map(Command::System))
-        // If fail, return an error
-        .map_err(|_err| format!("Event not supported: {key_event:?}")),
+      // Terminals may send any kind of line break, we only use `\n` internally
+      Event::Paste(text) => Ok(Self::Edit(Edit::InsertText(
+        text.replace("\r\n", "\n").replace('\r', "\n"),
+      ))),
       Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
         height: height_u16 as usize,
         width: width_u16 as usize,
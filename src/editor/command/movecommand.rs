@@ -1,7 +1,3 @@
-use crossterm::event::{
-  KeyCode::{Down, End, Home, Left, PageDown, PageUp, Right, Up},
-  KeyEvent, KeyModifiers,
-};
 #[derive(Clone, Copy)]
 pub enum Move {
   PageUp,
@@ -13,30 +9,3 @@ pub enum Move {
   Left,
   Right,
 }
-
-impl TryFrom<KeyEvent> for Move {
-  type Error = String;
-  fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-    let KeyEvent {
-      code, modifiers, ..
-    } = event;
-
-    if modifiers == KeyModifiers::NONE {
-      match code {
-        Up => Ok(Self::Up),
-        Down => Ok(Self::Down),
-        Left => Ok(Self::Left),
-        Right => Ok(Self::Right),
-        PageUp => Ok(Self::PageUp),
-        PageDown => Ok(Self::PageDown),
-        Home => Ok(Self::StartOfLine),
-        End => Ok(Self::EndOfLine),
-        _ => Err(format!("Unsupported code: {code:?}")),
-      }
-    } else {
-      Err(format!(
-        "Unsupported key code {code:?} or modifier {modifiers:?}"
-      ))
-    }
-  }
-}
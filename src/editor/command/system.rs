@@ -1,8 +1,9 @@
-use crate::prelude::*;
-
-use crossterm::event::{
-  KeyCode::{Char, Esc},
-  KeyEvent, KeyModifiers,
+use crate::{
+  editor::{
+    layout::{Direction, SplitDirection},
+    searchoptions::SearchOption,
+  },
+  prelude::*,
 };
 
 #[derive(Clone, Copy)]
@@ -12,28 +13,41 @@ pub enum System {
   Quit,
   Dismiss,
   Search,
-}
-
-impl TryFrom<KeyEvent> for System {
-  type Error = String;
-  fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-    let KeyEvent {
-      code, modifiers, ..
-    } = event;
-
-    if modifiers == KeyModifiers::CONTROL {
-      match code {
-        Char('q') => Ok(Self::Quit),
-        Char('s') => Ok(Self::Save),
-        Char('f') => Ok(Self::Search),
-        _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
-      }
-    } else if modifiers == KeyModifiers::NONE && matches!(code, Esc) {
-      Ok(Self::Dismiss)
-    } else {
-      Err(format!(
-        "Unsupported key code {code:?} or modifier {modifiers:?}"
-      ))
-    }
-  }
+  Replace,
+  /// Change one of the options of the current search
+  ToggleSearchOption(SearchOption),
+  /// Switch the line endings used when saving between LF and CRLF
+  ToggleLineEnding,
+  /// Switch to the next kind of line numbers shown left of the text
+  CycleLineNumbers,
+  /// Switch between wrapping long lines and scrolling horizontally
+  ToggleSoftWrap,
+  /// Switch between inserting spaces and a tab character when Tab is pressed
+  ToggleInsertSpaces,
+  /// Read the file again, decoding it with an encoding the user picks
+  ReopenWithEncoding,
+  /// Pick the encoding used when saving
+  SaveWithEncoding,
+  /// Switch to the next open buffer
+  NextBuffer,
+  /// Switch to the previous open buffer
+  PreviousBuffer,
+  /// Pick the buffer to switch to from a list
+  PickBuffer,
+  /// Pick a file below the current directory to open
+  OpenFile,
+  /// Close the current buffer
+  CloseBuffer,
+  /// Split the focused window in two, both showing its buffer
+  SplitWindow(SplitDirection),
+  /// Close the focused window, the buffer stays open
+  CloseWindow,
+  /// Make the focused window larger, at the expense of the one next to it
+  GrowWindow,
+  /// Make the focused window smaller, in favor of the one next to it
+  ShrinkWindow,
+  /// Move the focus to the window next to the focused one
+  FocusWindow(Direction),
+  /// List the key bindings, to look one up or run its command
+  ListBindings,
 }
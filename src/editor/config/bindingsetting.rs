@@ -0,0 +1,27 @@
+use crate::editor::keymap::command_name;
+
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// What keys are bound to in the configuration file: the name of a command, or `none` to unbind them
+#[derive(Debug, Clone, Copy)]
+pub struct BindingSetting(pub Option<&'static str>);
+
+impl TryFrom<String> for BindingSetting {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    if value == "none" {
+      return Ok(Self(None));
+    }
+    command_name(&value)
+      .map(|name| Self(Some(name)))
+      .ok_or_else(|| format!("unknown command `{value}`"))
+  }
+}
+
+// The derived implementation can't hand out names which live longer than the file's contents
+impl<'de> Deserialize<'de> for BindingSetting {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Self::try_from(String::deserialize(deserializer)?).map_err(D::Error::custom)
+  }
+}
@@ -0,0 +1,34 @@
+use crossterm::style::Color;
+
+use serde::Deserialize;
+
+/// A colour in the configuration file: `#rrggbb`, or `none` for the terminal's own colour
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ColorSetting(pub Option<Color>);
+
+impl TryFrom<String> for ColorSetting {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    if value == "none" {
+      return Ok(Self(None));
+    }
+    let invalid = || format!("invalid colour `{value}`, expected `#rrggbb` or `none`");
+    let hex = value
+      .strip_prefix('#')
+      .filter(|hex| hex.len() == 6 && hex.is_ascii())
+      .ok_or_else(invalid)?;
+    let channel = |range| {
+      hex
+        .get(range)
+        .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        .ok_or_else(invalid)
+    };
+    Ok(Self(Some(Color::Rgb {
+      r: channel(0..2)?,
+      g: channel(2..4)?,
+      b: channel(4..6)?,
+    })))
+  }
+}
@@ -0,0 +1,145 @@
+mod bindingsetting;
+mod colorsetting;
+mod settings;
+
+use crate::{
+  editor::{
+    config::settings::{IndentationSettings, Settings},
+    displayoptions::DisplayOptions,
+    filetype::FileType,
+    indentation::Indentation,
+    keymap::Keymap,
+    uicomponents::DEFAULT_MESSAGE_DURATION,
+  },
+  prelude::NAME,
+};
+
+use std::{
+  collections::HashMap,
+  env,
+  fs::read_to_string,
+  io::ErrorKind,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+const DEFAULT_QUIT_TIMES: u8 = 3;
+/// Name of the user's configuration file within the configuration directory
+const CONFIG_FILE_NAME: &str = "config.toml";
+/// Name of the configuration file of a project, which overrides the user's
+const PROJECT_FILE_NAME: &str = ".hecto.toml";
+
+/// Settings from the configuration files, with the built-in defaults for whatever they leave out
+pub struct Config {
+  /// How the windows show their text at startup
+  pub display: DisplayOptions,
+  /// How long a message stays in the message bar
+  pub message_duration: Duration,
+  /// How often Ctrl-Q has to be pressed to quit while there are unsaved changes
+  pub quit_times: u8,
+  pub keymap: Keymap,
+  /// Indentation set for every file type
+  indentation: IndentationSettings,
+  file_types: HashMap<FileType, IndentationSettings>,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self::from(Settings::default())
+  }
+}
+
+impl From<Settings> for Config {
+  fn from(settings: Settings) -> Self {
+    let display = DisplayOptions {
+      line_numbers: settings.line_numbers().unwrap_or_default(),
+      soft_wrap: settings.soft_wrap().unwrap_or_default(),
+      theme: settings.theme(),
+    };
+    Self {
+      display,
+      message_duration: settings
+        .message_timeout()
+        .map_or(DEFAULT_MESSAGE_DURATION, Duration::from_secs),
+      quit_times: settings.quit_times().unwrap_or(DEFAULT_QUIT_TIMES),
+      keymap: settings.keymap(),
+      indentation: settings.indentation(),
+      file_types: settings.into_file_types(),
+    }
+  }
+}
+
+impl Config {
+  /// Loads the user's configuration file, then the project's, which overrides it.
+  /// A file which can't be read or is invalid is left out, its error is returned along with the config.
+  pub fn load() -> (Self, Vec<String>) {
+    let mut settings = Settings::default();
+    let mut errors = Vec::new();
+    for path in [user_config_path(), project_config_path()]
+      .into_iter()
+      .flatten()
+    {
+      match load_file(&path) {
+        Ok(Some(file_settings)) => settings = settings.merge(file_settings),
+        Ok(None) => {}
+        Err(err) => errors.push(format!("ERR: {}: {err}", path.display())),
+      }
+    }
+    (Self::from(settings), errors)
+  }
+
+  /// Indentation of files of the type: their usual one, changed by what is set for every file type,
+  /// and then by what is set for this one
+  pub fn indentation(&self, file_type: FileType) -> Indentation {
+    let indentation = self
+      .indentation
+      .apply_to(Indentation::for_file_type(file_type));
+    self
+      .file_types
+      .get(&file_type)
+      .map_or(indentation, |settings| settings.apply_to(indentation))
+  }
+}
+
+/// Reads a configuration file. Returns `None` if there is none.
+fn load_file(path: &Path) -> Result<Option<Settings>, String> {
+  let contents = match read_to_string(path) {
+    Ok(contents) => contents,
+    Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+    Err(err) => return Err(err.to_string()),
+  };
+  let settings: Settings = toml::from_str(&contents).map_err(|err| {
+    // The error's own rendering spans several lines, which doesn't fit into the message bar
+    let message = err.message().trim_end();
+    err.span().map_or_else(
+      || message.to_string(),
+      |span| {
+        let line = contents
+          .get(..span.start)
+          .map_or(0, |before| before.matches('\n').count())
+          .saturating_add(1);
+        format!("line {line}: {message}")
+      },
+    )
+  })?;
+  settings.validate()?;
+  Ok(Some(settings))
+}
+
+/// `$XDG_CONFIG_HOME/hecto/config.toml`, falling back to `~/.config/hecto/config.toml`
+fn user_config_path() -> Option<PathBuf> {
+  let config_home = env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .filter(|path| path.is_absolute())
+    .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+  Some(config_home.join(NAME).join(CONFIG_FILE_NAME))
+}
+
+/// The project's configuration file, in the current directory or the closest one above it
+fn project_config_path() -> Option<PathBuf> {
+  env::current_dir()
+    .ok()?
+    .ancestors()
+    .map(|dir| dir.join(PROJECT_FILE_NAME))
+    .find(|path| path.is_file())
+}
@@ -0,0 +1,278 @@
+use crate::editor::{
+  annotationtype::AnnotationType,
+  config::{bindingsetting::BindingSetting, colorsetting::ColorSetting},
+  filetype::FileType,
+  indentation::Indentation,
+  keymap::{KeySequence, Keymap},
+  linenumbers::LineNumbers,
+  terminal::{Attribute, Theme},
+};
+
+use serde::Deserialize;
+
+use std::collections::HashMap;
+
+/// Widest tab width accepted, anything wider is most likely a typo
+const MAX_TAB_WIDTH: usize = 16;
+
+/// What one configuration file sets. Everything can be left out, to keep the value from elsewhere.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+  tab_width: Option<usize>,
+  insert_spaces: Option<bool>,
+  /// Seconds a message stays in the message bar
+  message_timeout: Option<u64>,
+  /// How often Ctrl-Q has to be pressed to quit while there are unsaved changes
+  quit_times: Option<u8>,
+  line_numbers: Option<LineNumbers>,
+  soft_wrap: Option<bool>,
+  theme: HashMap<AnnotationType, AttributeSettings>,
+  /// Overrides of the indentation, by file type
+  #[serde(rename = "filetype")]
+  file_types: HashMap<FileType, IndentationSettings>,
+  /// Changes of the default key bindings
+  keys: HashMap<KeySequence, BindingSetting>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct IndentationSettings {
+  tab_width: Option<usize>,
+  insert_spaces: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttributeSettings {
+  foreground: Option<ColorSetting>,
+  background: Option<ColorSetting>,
+}
+
+impl Settings {
+  /// Combines the settings with those of a later file, whose values win
+  pub fn merge(mut self, overrides: Self) -> Self {
+    for (annotation_type, attribute) in overrides.theme {
+      let merged = self
+        .theme
+        .get(&annotation_type)
+        .copied()
+        .unwrap_or_default()
+        .merge(attribute);
+      self.theme.insert(annotation_type, merged);
+    }
+    for (file_type, indentation) in overrides.file_types {
+      let merged = self
+        .file_types
+        .get(&file_type)
+        .copied()
+        .unwrap_or_default()
+        .merge(indentation);
+      self.file_types.insert(file_type, merged);
+    }
+    self.keys.extend(overrides.keys);
+    Self {
+      tab_width: overrides.tab_width.or(self.tab_width),
+      insert_spaces: overrides.insert_spaces.or(self.insert_spaces),
+      message_timeout: overrides.message_timeout.or(self.message_timeout),
+      quit_times: overrides.quit_times.or(self.quit_times),
+      line_numbers: overrides.line_numbers.or(self.line_numbers),
+      soft_wrap: overrides.soft_wrap.or(self.soft_wrap),
+      ..self
+    }
+  }
+
+  /// Checks the values the file format can't rule out
+  pub fn validate(&self) -> Result<(), String> {
+    let tab_widths = self
+      .file_types
+      .values()
+      .map(|indentation| indentation.tab_width)
+      .chain([self.tab_width]);
+    for tab_width in tab_widths.flatten() {
+      if !(1..=MAX_TAB_WIDTH).contains(&tab_width) {
+        return Err(format!(
+          "tab-width must be between 1 and {MAX_TAB_WIDTH}, not {tab_width}"
+        ));
+      }
+    }
+    if self.quit_times == Some(0) {
+      return Err("quit-times must be at least 1".to_string());
+    }
+    Ok(())
+  }
+
+  pub const fn message_timeout(&self) -> Option<u64> {
+    self.message_timeout
+  }
+
+  pub const fn quit_times(&self) -> Option<u8> {
+    self.quit_times
+  }
+
+  pub const fn line_numbers(&self) -> Option<LineNumbers> {
+    self.line_numbers
+  }
+
+  pub const fn soft_wrap(&self) -> Option<bool> {
+    self.soft_wrap
+  }
+
+  /// The default theme with the colours of the file applied
+  pub fn theme(&self) -> Theme {
+    let mut theme = Theme::default();
+    for (annotation_type, attribute) in &self.theme {
+      theme.set_attribute(
+        *annotation_type,
+        attribute.apply_to(theme.attribute(*annotation_type)),
+      );
+    }
+    theme
+  }
+
+  /// The default key bindings with the changes of the file applied
+  pub fn keymap(&self) -> Keymap {
+    let mut keymap = Keymap::default();
+    // Sorted, so that new bindings are always listed in the same order
+    let mut changes: Vec<_> = self.keys.iter().collect();
+    changes.sort_by_key(|(keys, _)| keys.to_string());
+    for (keys, binding) in changes {
+      keymap.bind(keys.clone(), binding.0);
+    }
+    keymap
+  }
+
+  /// Indentation set for every file type
+  pub const fn indentation(&self) -> IndentationSettings {
+    IndentationSettings {
+      tab_width: self.tab_width,
+      insert_spaces: self.insert_spaces,
+    }
+  }
+
+  pub fn into_file_types(self) -> HashMap<FileType, IndentationSettings> {
+    self.file_types
+  }
+}
+
+impl IndentationSettings {
+  fn merge(self, overrides: Self) -> Self {
+    Self {
+      tab_width: overrides.tab_width.or(self.tab_width),
+      insert_spaces: overrides.insert_spaces.or(self.insert_spaces),
+    }
+  }
+
+  pub fn apply_to(self, indentation: Indentation) -> Indentation {
+    Indentation {
+      tab_width: self.tab_width.unwrap_or(indentation.tab_width),
+      insert_spaces: self.insert_spaces.unwrap_or(indentation.insert_spaces),
+    }
+  }
+}
+
+impl AttributeSettings {
+  fn merge(self, overrides: Self) -> Self {
+    Self {
+      foreground: overrides.foreground.or(self.foreground),
+      background: overrides.background.or(self.background),
+    }
+  }
+
+  fn apply_to(self, attribute: Attribute) -> Attribute {
+    Attribute {
+      foreground: self
+        .foreground
+        .map_or(attribute.foreground, |color| color.0),
+      background: self
+        .background
+        .map_or(attribute.background, |color| color.0),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crossterm::style::Color;
+
+  fn parse(toml: &str) -> Settings {
+    toml::from_str(toml).expect("settings are valid TOML")
+  }
+
+  #[test]
+  fn later_values_win() {
+    let merged = parse("tab-width = 2\nsoft-wrap = true").merge(parse("tab-width = 8"));
+    assert_eq!(merged.tab_width, Some(8));
+    assert_eq!(merged.soft_wrap(), Some(true));
+  }
+
+  #[test]
+  fn file_types_are_merged_by_setting() {
+    let merged = parse("[filetype.rust]\ntab-width = 2")
+      .merge(parse("[filetype.rust]\ninsert-spaces = false"));
+    let rust = merged
+      .into_file_types()
+      .get(&FileType::Rust)
+      .copied()
+      .expect("rust settings are kept");
+    let indentation = Indentation {
+      tab_width: 4,
+      insert_spaces: true,
+    };
+    assert_eq!(
+      rust.apply_to(indentation),
+      Indentation {
+        tab_width: 2,
+        insert_spaces: false
+      }
+    );
+  }
+
+  #[test]
+  fn theme_is_merged_by_colour() {
+    let merged = parse("[theme.keyword]\nforeground = \"#ff0000\"")
+      .merge(parse("[theme.keyword]\nbackground = \"none\""));
+    let keyword = merged.theme().attribute(AnnotationType::Keyword);
+    assert_eq!(keyword.foreground, Some(Color::Rgb { r: 255, g: 0, b: 0 }));
+    assert_eq!(keyword.background, None);
+  }
+
+  #[test]
+  fn later_key_bindings_win() {
+    let merged = parse("[keys]\nctrl-t = \"save\"\nctrl-g = \"quit\"")
+      .merge(parse("[keys]\nctrl-t = \"none\""));
+    let bindings: Vec<_> = merged
+      .keys
+      .iter()
+      .map(|(keys, binding)| (keys.to_string(), binding.0))
+      .collect();
+    assert_eq!(bindings.len(), 2);
+    assert!(bindings.contains(&(String::from("Ctrl-T"), None)));
+    assert!(bindings.contains(&(String::from("Ctrl-G"), Some("quit"))));
+  }
+
+  #[test]
+  fn default_settings_are_valid() {
+    assert!(Settings::default().validate().is_ok());
+    assert!(parse("tab-width = 16\nquit-times = 1").validate().is_ok());
+  }
+
+  #[test]
+  fn tab_width_out_of_range_is_invalid() {
+    assert!(parse("tab-width = 0").validate().is_err());
+    assert!(parse("tab-width = 17").validate().is_err());
+    assert!(parse("[filetype.go]\ntab-width = 0").validate().is_err());
+  }
+
+  #[test]
+  fn quitting_without_pressing_is_invalid() {
+    assert!(parse("quit-times = 0").validate().is_err());
+  }
+
+  #[test]
+  fn unknown_settings_are_rejected() {
+    assert!(toml::from_str::<Settings>("tab-size = 4").is_err());
+  }
+}
@@ -0,0 +1,10 @@
+use crate::editor::{linenumbers::LineNumbers, terminal::Theme};
+
+/// How the views show their text, the same in every window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayOptions {
+  pub line_numbers: LineNumbers,
+  /// Wrap long lines onto further rows instead of scrolling horizontally
+  pub soft_wrap: bool,
+  pub theme: Theme,
+}
@@ -0,0 +1,124 @@
+use crate::editor::{
+  swapfile::{SwapFile, SwapState, watch},
+  uicomponents::View,
+};
+
+use std::{cell::RefCell, rc::Rc};
+
+/// A buffer opened in the editor, with its view and swap file
+#[derive(Default)]
+pub struct Document {
+  pub view: View,
+  /// Shared with the panic hook
+  swap_file: Option<Rc<RefCell<SwapFile>>>,
+  /// Text of a swap file left behind by an earlier session, until the user decides what to do with it
+  stale_swap: Option<String>,
+}
+
+impl Document {
+  pub fn from(view: View) -> Self {
+    Self {
+      view,
+      ..Self::default()
+    }
+  }
+
+  /// Name shown to the user, along with whether there are unsaved changes
+  pub fn name(&self) -> String {
+    let status = self.view.get_status();
+    format!(
+      "{} {}",
+      status.file_name,
+      status.modified_indicator_to_string()
+    )
+    .trim_end()
+    .to_string()
+  }
+
+  pub fn is_modified(&self) -> bool {
+    self.view.get_status().is_modified
+  }
+
+  /// Whether this is the empty, unnamed buffer the editor starts with, which nobody touched
+  pub fn is_untouched(&self) -> bool {
+    let status = self.view.get_status();
+    !self.view.is_file_loaded() && !status.is_modified && status.total_lines == 0
+  }
+
+  // region: swap file
+
+  /// Looks for a swap file left behind by an earlier session.
+  /// Returns the id of the process which owns the swap file, if it belongs to another running session.
+  pub fn check_swap_file(&mut self) -> Option<u32> {
+    let mut swap_file = SwapFile::new(self.view.file_path().as_deref(), self.view.swap_source())?;
+    let pid = match swap_file.inspect() {
+      SwapState::Missing => None,
+      SwapState::Stale(text) => {
+        self.stale_swap = Some(text);
+        None
+      }
+      SwapState::InUse(pid) => {
+        swap_file.disable();
+        Some(pid)
+      }
+    };
+    self.swap_file = Some(watch(swap_file));
+    pid
+  }
+
+  pub fn stale_swap(&self) -> Option<&str> {
+    self.stale_swap.as_deref()
+  }
+
+  /// Replaces the text with the one from the stale swap file
+  pub fn recover_swap(&mut self) {
+    if let Some(text) = self.stale_swap.take() {
+      self.view.replace_contents(&text);
+    }
+    self.update_swap_file(true);
+  }
+
+  pub fn discard_swap(&mut self) {
+    self.stale_swap = None;
+    self.remove_swap_file();
+  }
+
+  /// Keeps the swap file in line with the buffer: written while there are unsaved changes, removed otherwise.
+  /// Unless forced, the swap file is written at most once per `SWAP_INTERVAL`.
+  pub fn update_swap_file(&mut self, force: bool) {
+    // The swap file of an earlier session must stay untouched until the user decided what to do with it
+    if self.stale_swap.is_some() {
+      return;
+    }
+
+    let file_path = self.view.file_path();
+    if !self
+      .swap_file
+      .as_ref()
+      .is_some_and(|swap_file| swap_file.borrow().belongs_to(file_path.as_deref()))
+    {
+      self.remove_swap_file();
+      self.swap_file = SwapFile::new(file_path.as_deref(), self.view.swap_source()).map(watch);
+    }
+
+    let Some(swap_file) = &self.swap_file else {
+      return;
+    };
+    let mut swap_file = swap_file.borrow_mut();
+    let revision = self.view.revision();
+    if !self.view.get_status().is_modified {
+      swap_file.remove();
+    } else if swap_file.is_due(revision) || (force && swap_file.is_outdated(revision)) {
+      // The swap file is a safety net, failing to write it should not interrupt editing
+      let _ = swap_file.write(&self.view.contents(), revision);
+    }
+  }
+
+  pub fn remove_swap_file(&mut self) {
+    if let Some(swap_file) = &self.swap_file {
+      swap_file.borrow_mut().remove();
+    }
+  }
+
+  // endregion
+}
@@ -1,4 +1,7 @@
-use crate::{editor::filetype::FileType, prelude::LineIdx};
+use crate::{
+  editor::{encoding::Encoding, filetype::FileType, lineending::LineEnding},
+  prelude::LineIdx,
+};
 
 #[derive(Default, Eq, PartialEq, Debug)]
 pub struct DocumentStatus {
@@ -7,6 +10,15 @@ pub struct DocumentStatus {
   pub is_modified: bool,
   pub file_name: String,
   pub file_type: FileType,
+  pub encoding: Encoding,
+  pub line_ending: LineEnding,
+  /// Index of the match at the caret, starting at 1, if there is one
+  pub current_match: Option<usize>,
+  /// Number of matches in the document, `None` if not searching or not counted yet
+  pub total_matches: Option<usize>,
+  /// Set while the matches of the search are yet to be counted
+  pub counting_matches: bool,
+  pub search_wrapped: bool,
 }
 
 impl DocumentStatus {
@@ -30,7 +42,35 @@ impl DocumentStatus {
     )
   }
 
+  /// Returns `None` if there's no search in progress
+  pub fn search_indicator_to_string(&self) -> Option<String> {
+    let matches = if self.counting_matches {
+      "counting matches".to_string()
+    } else {
+      let total = self.total_matches?;
+      match (self.current_match, total) {
+        (_, 0) => "no matches".to_string(),
+        (Some(current), _) => format!("match {current}/{total}"),
+        (None, 1) => "1 match".to_string(),
+        (None, _) => format!("{total} matches"),
+      }
+    };
+    Some(if self.search_wrapped {
+      format!("{matches}, search wrapped")
+    } else {
+      matches
+    })
+  }
+
   pub fn file_type_to_string(&self) -> String {
     self.file_type.to_string()
   }
+
+  pub fn encoding_to_string(&self) -> String {
+    self.encoding.to_string()
+  }
+
+  pub fn line_ending_to_string(&self) -> String {
+    self.line_ending.to_string()
+  }
 }
@@ -0,0 +1,240 @@
+use std::{
+  fmt::{self, Display, Formatter},
+  io::{Error, ErrorKind},
+};
+
+/// Characters of Windows-1252 in the range 0x80..=0x9F, where it differs from Latin-1.
+/// Bytes that Windows-1252 leaves undefined map to the control characters Latin-1 has there.
+const WINDOWS_1252_HIGH: [char; 32] = [
+  '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+  '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}', '\u{90}',
+  '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{2dc}',
+  '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+/// Character encodings files can be read and written in
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+pub enum Encoding {
+  #[default]
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  /// Superset of Latin-1, used for files which are not valid in any other encoding
+  Windows1252,
+}
+
+impl Encoding {
+  /// Looks up an encoding by one of its common names, ignoring case
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "utf-8" | "utf8" => Some(Self::Utf8),
+      "utf-16le" | "utf16le" => Some(Self::Utf16Le),
+      "utf-16be" | "utf16be" => Some(Self::Utf16Be),
+      "windows-1252" | "cp1252" | "latin-1" | "latin1" | "iso-8859-1" => Some(Self::Windows1252),
+      _ => None,
+    }
+  }
+
+  /// Detects the encoding of the given bytes and decodes them.
+  /// Returns the text, its encoding and whether it started with a byte order mark.
+  pub fn decode_detected(bytes: &[u8]) -> (String, Self, bool) {
+    for encoding in [Self::Utf8, Self::Utf16Le, Self::Utf16Be] {
+      if let Some(rest) = bytes.strip_prefix(encoding.bom())
+        && let Ok(text) = encoding.decode(rest)
+      {
+        return (text, encoding, true);
+      }
+    }
+    if let Ok(text) = Self::Utf8.decode(bytes) {
+      return (text, Self::Utf8, false);
+    }
+    // Every byte is a valid Windows-1252 character
+    let text = Self::Windows1252.decode(bytes).unwrap_or_default();
+    (text, Self::Windows1252, false)
+  }
+
+  /// Decodes the bytes, skipping a byte order mark at their start if there is one
+  pub fn decode_with_bom(self, bytes: &[u8]) -> Result<(String, bool), Error> {
+    let bom = self.bom();
+    match bytes.strip_prefix(bom) {
+      Some(rest) if !bom.is_empty() => Ok((self.decode(rest)?, true)),
+      _ => Ok((self.decode(bytes)?, false)),
+    }
+  }
+
+  /// Byte order mark of the encoding, empty if it has none
+  pub const fn bom(self) -> &'static [u8] {
+    match self {
+      Self::Utf8 => &[0xEF, 0xBB, 0xBF],
+      Self::Utf16Le => &[0xFF, 0xFE],
+      Self::Utf16Be => &[0xFE, 0xFF],
+      Self::Windows1252 => &[],
+    }
+  }
+
+  /// Whether files in this encoding need a byte order mark to be detected when they are read again
+  pub const fn requires_bom(self) -> bool {
+    matches!(self, Self::Utf16Le | Self::Utf16Be)
+  }
+
+  fn decode(self, bytes: &[u8]) -> Result<String, Error> {
+    match self {
+      Self::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|err| invalid_data(&err.to_string())),
+      Self::Utf16Le | Self::Utf16Be => {
+        let chunks = bytes.chunks_exact(2);
+        if !chunks.remainder().is_empty() {
+          return Err(invalid_data(&format!("Odd number of bytes for {self}")));
+        }
+        let units: Vec<u16> = chunks
+          .map(|chunk| {
+            let pair = [chunk[0], chunk[1]];
+            if self == Self::Utf16Le {
+              u16::from_le_bytes(pair)
+            } else {
+              u16::from_be_bytes(pair)
+            }
+          })
+          .collect();
+        String::from_utf16(&units).map_err(|err| invalid_data(&err.to_string()))
+      }
+      Self::Windows1252 => Ok(
+        bytes
+          .iter()
+          .map(|&byte| match byte {
+            0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte.saturating_sub(0x80))],
+            _ => char::from(byte),
+          })
+          .collect(),
+      ),
+    }
+  }
+
+  /// Encodes the text, failing if it contains a character the encoding cannot represent
+  pub fn encode(self, text: &str) -> Result<Vec<u8>, Error> {
+    match self {
+      Self::Utf8 => Ok(text.as_bytes().to_vec()),
+      Self::Utf16Le => Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+      Self::Utf16Be => Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+      Self::Windows1252 => text
+        .chars()
+        .map(|character| {
+          let high = WINDOWS_1252_HIGH
+            .iter()
+            .position(|&candidate| candidate == character)
+            .and_then(|idx| u8::try_from(idx).ok())
+            .map(|idx| idx.saturating_add(0x80));
+          let low = u8::try_from(character)
+            .ok()
+            .filter(|byte| !(0x80..=0x9F).contains(byte));
+          high
+            .or(low)
+            .ok_or_else(|| invalid_data(&format!("{character:?} cannot be encoded in {self}")))
+        })
+        .collect(),
+    }
+  }
+}
+
+fn invalid_data(message: &str) -> Error {
+  Error::new(ErrorKind::InvalidData, message)
+}
+
+impl Display for Encoding {
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Utf8 => write!(formatter, "UTF-8"),
+      Self::Utf16Le => write!(formatter, "UTF-16LE"),
+      Self::Utf16Be => write!(formatter, "UTF-16BE"),
+      Self::Windows1252 => write!(formatter, "Windows-1252"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detects_utf8() {
+    assert_eq!(
+      Encoding::decode_detected("état".as_bytes()),
+      (String::from("état"), Encoding::Utf8, false)
+    );
+  }
+
+  #[test]
+  fn detects_utf8_bom() {
+    assert_eq!(
+      Encoding::decode_detected(b"\xEF\xBB\xBFab"),
+      (String::from("ab"), Encoding::Utf8, true)
+    );
+  }
+
+  #[test]
+  fn detects_utf16_by_bom() {
+    assert_eq!(
+      Encoding::decode_detected(b"\xFF\xFEa\0b\0"),
+      (String::from("ab"), Encoding::Utf16Le, true)
+    );
+    assert_eq!(
+      Encoding::decode_detected(b"\xFE\xFF\0a\0b"),
+      (String::from("ab"), Encoding::Utf16Be, true)
+    );
+  }
+
+  #[test]
+  fn falls_back_to_windows_1252() {
+    assert_eq!(
+      Encoding::decode_detected(b"\x80 caf\xE9"),
+      (String::from("€ café"), Encoding::Windows1252, false)
+    );
+  }
+
+  #[test]
+  fn invalid_utf16_after_bom_falls_back_to_windows_1252() {
+    let (text, encoding, bom) = Encoding::decode_detected(b"\xFF\xFEa");
+    assert_eq!(text, "ÿþa");
+    assert_eq!(encoding, Encoding::Windows1252);
+    assert!(!bom);
+  }
+
+  #[test]
+  fn decodes_given_encoding_with_and_without_bom() {
+    assert_eq!(
+      Encoding::Utf16Le.decode_with_bom(b"\xFF\xFEa\0").ok(),
+      Some((String::from("a"), true))
+    );
+    assert_eq!(
+      Encoding::Utf16Le.decode_with_bom(b"a\0").ok(),
+      Some((String::from("a"), false))
+    );
+    assert!(Encoding::Utf8.decode_with_bom(b"\xFF").is_err());
+  }
+
+  #[test]
+  fn encoding_round_trips() {
+    let text = "€ café ✓";
+    for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+      let bytes = encoding
+        .encode(text)
+        .expect("every character can be encoded");
+      assert_eq!(encoding.decode(&bytes).ok().as_deref(), Some(text));
+    }
+  }
+
+  #[test]
+  fn windows_1252_rejects_characters_it_lacks() {
+    assert_eq!(
+      Encoding::Windows1252.encode("€ café").ok(),
+      Some(b"\x80 caf\xE9".to_vec())
+    );
+    assert!(Encoding::Windows1252.encode("✓").is_err());
+  }
+
+  #[test]
+  fn looks_up_names() {
+    assert_eq!(Encoding::from_name(" UTF8 "), Some(Encoding::Utf8));
+    assert_eq!(Encoding::from_name("latin1"), Some(Encoding::Windows1252));
+    assert_eq!(Encoding::from_name("ebcdic"), None);
+  }
+}
@@ -1,16 +1,51 @@
-use std::fmt::{Display, Formatter, Result};
+use serde::Deserialize;
 
-#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+use std::{
+  fmt::{Display, Formatter, Result},
+  path::Path,
+};
+
+/// Named in lowercase in the configuration file
+#[derive(Debug, PartialEq, Eq, Hash, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FileType {
   Rust,
+  Go,
+  Makefile,
   #[default]
   Text,
 }
 
+impl FileType {
+  /// Tells the type from the file's extension, or its name for Makefiles
+  pub fn detect(path: &Path) -> Self {
+    let has_extension = |extension: &str| {
+      path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    };
+    let file_name = path.file_name().and_then(|name| name.to_str());
+
+    if has_extension("rs") {
+      Self::Rust
+    } else if has_extension("go") {
+      Self::Go
+    } else if has_extension("mk")
+      || matches!(file_name, Some("Makefile" | "makefile" | "GNUmakefile"))
+    {
+      Self::Makefile
+    } else {
+      Self::Text
+    }
+  }
+}
+
 impl Display for FileType {
   fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
     match self {
       Self::Rust => write!(formatter, "Rust"),
+      Self::Go => write!(formatter, "Go"),
+      Self::Makefile => write!(formatter, "Makefile"),
       Self::Text => write!(formatter, "Text"),
     }
   }
@@ -0,0 +1,133 @@
+/// Every matched character scores this
+const MATCH_SCORE: usize = 2;
+/// Bonus for a match at the start of the text, of a path component or of a word
+const WORD_START_BONUS: usize = 8;
+/// Bonus for a match right after the previous one
+const CONSECUTIVE_BONUS: usize = 6;
+/// Bonus for a match in the last path component, which is usually what the user types
+const FILE_NAME_BONUS: usize = 4;
+/// Cost of skipping characters between two matches
+const GAP_PENALTY: usize = 3;
+
+/// Scores how well the query matches the text, higher is better.
+/// `None` if the text doesn't contain every character of the query in order, ignoring case.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+  let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+  let Some(first) = query.first() else {
+    return Some(0);
+  };
+  let text: Vec<char> = text.chars().collect();
+  // Lowercased the same way as the query, as some characters lowercase to several ones,
+  // each together with the index of the character in the text it comes from
+  let lowercase: Vec<(char, usize)> = text
+    .iter()
+    .enumerate()
+    .flat_map(|(idx, character)| character.to_lowercase().map(move |lower| (lower, idx)))
+    .collect();
+  let file_name_start = text
+    .iter()
+    .rposition(|character| *character == '/')
+    .map_or(0, |idx| idx.saturating_add(1));
+
+  // Matching greedily from the first possible start can miss a better match later on,
+  // e.g. at the start of the file name, so every start is tried
+  lowercase
+    .iter()
+    .enumerate()
+    .filter(|(_, (character, _))| character == first)
+    .filter_map(|(start, _)| score_from(&query, &text, &lowercase, start, file_name_start))
+    .max()
+}
+
+fn score_from(
+  query: &[char],
+  text: &[char],
+  lowercase: &[(char, usize)],
+  start: usize,
+  file_name_start: usize,
+) -> Option<usize> {
+  let mut score: usize = 0;
+  // Position in `lowercase` of the previous match
+  let mut previous: Option<usize> = None;
+  let mut from = start;
+  for query_char in query {
+    let (pos, idx) = lowercase
+      .get(from..)?
+      .iter()
+      .zip(from..)
+      .find_map(|((character, idx), pos)| (character == query_char).then_some((pos, *idx)))?;
+    score = score.saturating_add(MATCH_SCORE);
+    if is_word_start(text, idx) {
+      score = score.saturating_add(WORD_START_BONUS);
+    }
+    if idx >= file_name_start {
+      score = score.saturating_add(FILE_NAME_BONUS);
+    }
+    match previous {
+      Some(previous) if previous.saturating_add(1) == pos => {
+        score = score.saturating_add(CONSECUTIVE_BONUS);
+      }
+      Some(_) => score = score.saturating_sub(GAP_PENALTY),
+      None => {}
+    }
+    previous = Some(pos);
+    from = pos.saturating_add(1);
+  }
+  Some(score)
+}
+
+fn is_word_start(text: &[char], idx: usize) -> bool {
+  let Some(before) = idx.checked_sub(1).and_then(|before| text.get(before)) else {
+    return true;
+  };
+  matches!(before, '/' | '\\' | '_' | '-' | '.' | ' ')
+    || (before.is_lowercase()
+      && text
+        .get(idx)
+        .is_some_and(|character| character.is_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn score(query: &str, text: &str) -> usize {
+    fuzzy_score(query, text).expect("query matches")
+  }
+
+  #[test]
+  fn needs_every_character_in_order() {
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+    assert!(fuzzy_score("abc", "a/b/c.rs").is_some());
+    assert!(fuzzy_score("cba", "a/b/c.rs").is_none());
+    assert!(fuzzy_score("aa", "a.rs").is_none());
+  }
+
+  #[test]
+  fn ignores_case() {
+    assert_eq!(score("READ", "readme.md"), score("read", "README.md"));
+    assert!(fuzzy_score("istanbul", "İstanbul.txt").is_some());
+  }
+
+  #[test]
+  fn prefers_consecutive_matches() {
+    assert!(score("main", "src/main.rs") > score("main", "src/my_airplane.rs"));
+  }
+
+  #[test]
+  fn prefers_matches_in_the_file_name() {
+    assert!(score("edit", "src/edit/mod.rs") < score("edit", "src/mod/edit.rs"));
+  }
+
+  #[test]
+  fn prefers_word_starts() {
+    assert!(score("fm", "src/fuzzyMatch.rs") > score("fm", "src/fumble.rs"));
+    assert!(score("fm", "src/fuzzy_match.rs") > score("fm", "src/fumble.rs"));
+  }
+
+  #[test]
+  fn finds_the_best_match_after_a_worse_one() {
+    // Matching greedily would start at the `m` of `moon`
+    assert_eq!(score("main", "moon/main.rs"), score("main", "main.rs"));
+  }
+}
@@ -0,0 +1,38 @@
+use crate::editor::filetype::FileType;
+
+/// How wide tabs are shown, and what pressing Tab inserts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indentation {
+  /// Columns from one tab stop to the next
+  pub tab_width: usize,
+  /// Insert spaces up to the next tab stop instead of a tab character
+  pub insert_spaces: bool,
+}
+
+impl Default for Indentation {
+  fn default() -> Self {
+    Self::for_file_type(FileType::default())
+  }
+}
+
+impl Indentation {
+  /// The usual indentation of files of the type
+  pub const fn for_file_type(file_type: FileType) -> Self {
+    match file_type {
+      FileType::Rust => Self {
+        tab_width: 4,
+        insert_spaces: true,
+      },
+      // make requires a tab in front of every recipe line
+      FileType::Makefile => Self {
+        tab_width: 8,
+        insert_spaces: false,
+      },
+      // gofmt indents with tabs
+      FileType::Go | FileType::Text => Self {
+        tab_width: 4,
+        insert_spaces: false,
+      },
+    }
+  }
+}
@@ -0,0 +1,194 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use std::{
+  fmt::{Display, Formatter, Result},
+  str::FromStr,
+};
+
+/// A key pressed together with modifiers, as bound in the keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+  pub code: KeyCode,
+  pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+  fn from(event: KeyEvent) -> Self {
+    let modifiers =
+      event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+    match event.code {
+      // A character typed with Shift already is the shifted one
+      KeyCode::Char(character) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+        code: KeyCode::Char(character.to_ascii_uppercase()),
+        modifiers: modifiers.difference(KeyModifiers::SHIFT),
+      },
+      code => Self { code, modifiers },
+    }
+  }
+}
+
+impl Key {
+  /// The same key without Shift, if it was held
+  pub fn without_shift(self) -> Option<Self> {
+    self.modifiers.contains(KeyModifiers::SHIFT).then(|| Self {
+      modifiers: self.modifiers.difference(KeyModifiers::SHIFT),
+      ..self
+    })
+  }
+}
+
+/// Parses keys like `ctrl-k`, `alt-shift-up` or `f1`. Letters are case-insensitive, Shift has to be named.
+impl FromStr for Key {
+  type Err = String;
+
+  fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = text;
+    // The last part is the key itself, which may be `-`
+    while let Some((prefix, rest)) = name.split_once('-')
+      && !rest.is_empty()
+    {
+      modifiers |= match prefix.to_ascii_lowercase().as_str() {
+        "ctrl" => KeyModifiers::CONTROL,
+        "alt" => KeyModifiers::ALT,
+        "shift" => KeyModifiers::SHIFT,
+        _ => return Err(format!("unknown modifier `{prefix}` in `{text}`")),
+      };
+      name = rest;
+    }
+    let code = key_code(name).ok_or_else(|| format!("unknown key `{name}` in `{text}`"))?;
+    Ok(Self::from(KeyEvent::new(code, modifiers)))
+  }
+}
+
+fn key_code(name: &str) -> Option<KeyCode> {
+  let mut chars = name.chars();
+  if let (Some(character), None) = (chars.next(), chars.next()) {
+    return Some(KeyCode::Char(character.to_ascii_lowercase()));
+  }
+  let code = match name.to_ascii_lowercase().as_str() {
+    "up" => KeyCode::Up,
+    "down" => KeyCode::Down,
+    "left" => KeyCode::Left,
+    "right" => KeyCode::Right,
+    "pageup" => KeyCode::PageUp,
+    "pagedown" => KeyCode::PageDown,
+    "home" => KeyCode::Home,
+    "end" => KeyCode::End,
+    "enter" => KeyCode::Enter,
+    "tab" => KeyCode::Tab,
+    "backtab" => KeyCode::BackTab,
+    "backspace" => KeyCode::Backspace,
+    "delete" => KeyCode::Delete,
+    "insert" => KeyCode::Insert,
+    "esc" => KeyCode::Esc,
+    "space" => KeyCode::Char(' '),
+    function_key => {
+      let number = function_key.strip_prefix('f')?.parse().ok()?;
+      return (1..=12).contains(&number).then_some(KeyCode::F(number));
+    }
+  };
+  Some(code)
+}
+
+impl Display for Key {
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+    if self.modifiers.contains(KeyModifiers::CONTROL) {
+      write!(formatter, "Ctrl-")?;
+    }
+    if self.modifiers.contains(KeyModifiers::ALT) {
+      write!(formatter, "Alt-")?;
+    }
+    let is_shifted_char =
+      matches!(self.code, KeyCode::Char(character) if character.is_ascii_uppercase());
+    if self.modifiers.contains(KeyModifiers::SHIFT) || is_shifted_char {
+      write!(formatter, "Shift-")?;
+    }
+    match self.code {
+      KeyCode::Char(' ') => write!(formatter, "Space"),
+      KeyCode::Char(character) => write!(formatter, "{}", character.to_ascii_uppercase()),
+      KeyCode::PageUp => write!(formatter, "PageUp"),
+      KeyCode::PageDown => write!(formatter, "PageDown"),
+      KeyCode::F(number) => write!(formatter, "F{number}"),
+      code => write!(formatter, "{code:?}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(text: &str) -> Key {
+    text.parse().expect("key is valid")
+  }
+
+  #[test]
+  fn parses_modifiers() {
+    assert_eq!(
+      parse("ctrl-k"),
+      Key {
+        code: KeyCode::Char('k'),
+        modifiers: KeyModifiers::CONTROL
+      }
+    );
+    assert_eq!(
+      parse("Alt-Shift-Up"),
+      Key {
+        code: KeyCode::Up,
+        modifiers: KeyModifiers::ALT | KeyModifiers::SHIFT
+      }
+    );
+  }
+
+  #[test]
+  fn letters_are_case_insensitive() {
+    assert_eq!(parse("ctrl-K"), parse("ctrl-k"));
+    assert_eq!(parse("A"), parse("a"));
+  }
+
+  #[test]
+  fn shift_with_a_letter_is_the_upper_case_letter() {
+    assert_eq!(
+      parse("shift-a"),
+      Key {
+        code: KeyCode::Char('A'),
+        modifiers: KeyModifiers::NONE
+      }
+    );
+  }
+
+  #[test]
+  fn minus_is_a_key() {
+    assert_eq!(
+      parse("alt--"),
+      Key {
+        code: KeyCode::Char('-'),
+        modifiers: KeyModifiers::ALT
+      }
+    );
+    assert_eq!(parse("-").code, KeyCode::Char('-'));
+  }
+
+  #[test]
+  fn parses_named_keys() {
+    assert_eq!(parse("space").code, KeyCode::Char(' '));
+    assert_eq!(parse("PageDown").code, KeyCode::PageDown);
+    assert_eq!(parse("f12").code, KeyCode::F(12));
+  }
+
+  #[test]
+  fn rejects_unknown_keys_and_modifiers() {
+    assert!("f13".parse::<Key>().is_err());
+    assert!("hyper-a".parse::<Key>().is_err());
+    assert!("ctrl-foo".parse::<Key>().is_err());
+  }
+
+  #[test]
+  fn shows_keys_the_way_they_are_written() {
+    assert_eq!(parse("ctrl-alt-x").to_string(), "Ctrl-Alt-X");
+    assert_eq!(parse("shift-a").to_string(), "Shift-A");
+    assert_eq!(parse("shift-pageup").to_string(), "Shift-PageUp");
+    assert_eq!(parse("space").to_string(), "Space");
+  }
+}
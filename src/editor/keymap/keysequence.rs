@@ -0,0 +1,55 @@
+use crate::editor::keymap::key::Key;
+
+use serde::Deserialize;
+
+use std::fmt::{Display, Formatter, Result};
+
+/// Keys pressed one after the other to run a command, like `ctrl-k ctrl-c`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeySequence(pub Vec<Key>);
+
+impl TryFrom<String> for KeySequence {
+  type Error = String;
+
+  fn try_from(text: String) -> std::result::Result<Self, Self::Error> {
+    let keys = text
+      .split_whitespace()
+      .map(str::parse)
+      .collect::<std::result::Result<Vec<Key>, _>>()?;
+    if keys.is_empty() {
+      return Err("a key binding needs at least one key".to_string());
+    }
+    Ok(Self(keys))
+  }
+}
+
+impl Display for KeySequence {
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+    for (idx, key) in self.0.iter().enumerate() {
+      if idx > 0 {
+        write!(formatter, " ")?;
+      }
+      write!(formatter, "{key}")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_keys_separated_by_whitespace() {
+    let keys = KeySequence::try_from(String::from("ctrl-k  ctrl-c")).expect("keys are valid");
+    assert_eq!(keys.0.len(), 2);
+    assert_eq!(keys.to_string(), "Ctrl-K Ctrl-C");
+  }
+
+  #[test]
+  fn rejects_empty_and_invalid_sequences() {
+    assert!(KeySequence::try_from(String::from(" ")).is_err());
+    assert!(KeySequence::try_from(String::from("ctrl-k nokey")).is_err());
+  }
+}
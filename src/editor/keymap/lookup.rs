@@ -0,0 +1,9 @@
+use crate::editor::command::Command;
+
+/// What the keys typed so far mean
+pub enum Lookup {
+  Command(Command),
+  /// The keys start a longer chord, the next key decides
+  Prefix,
+  Unbound,
+}
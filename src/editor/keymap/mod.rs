@@ -0,0 +1,247 @@
+mod key;
+mod keysequence;
+mod lookup;
+mod namedcommand;
+
+pub use key::Key;
+pub use keysequence::KeySequence;
+pub use lookup::Lookup;
+pub use namedcommand::command_name;
+
+use crate::editor::{
+  command::{Command, Edit},
+  keymap::namedcommand::named_command,
+};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Bindings the editor starts with, before the configuration files change them
+const DEFAULT_BINDINGS: [(&str, &str); 47] = [
+  ("tab", "insert-tab"),
+  ("enter", "insert-newline"),
+  ("delete", "delete"),
+  ("backspace", "delete-backward"),
+  ("ctrl-w", "delete-word-backward"),
+  ("ctrl-z", "undo"),
+  ("ctrl-y", "redo"),
+  ("ctrl-x", "cut"),
+  ("ctrl-c", "copy"),
+  ("ctrl-v", "paste"),
+  ("up", "move-up"),
+  ("down", "move-down"),
+  ("left", "move-left"),
+  ("right", "move-right"),
+  ("pageup", "page-up"),
+  ("pagedown", "page-down"),
+  ("home", "start-of-line"),
+  ("end", "end-of-line"),
+  ("ctrl-s", "save"),
+  ("ctrl-q", "quit"),
+  ("esc", "dismiss"),
+  ("ctrl-f", "search"),
+  ("ctrl-r", "replace"),
+  ("alt-r", "toggle-regex"),
+  ("alt-c", "toggle-case-sensitivity"),
+  ("alt-w", "toggle-whole-word"),
+  ("alt-l", "toggle-line-ending"),
+  ("alt-n", "cycle-line-numbers"),
+  ("alt-z", "toggle-soft-wrap"),
+  ("alt-t", "toggle-insert-spaces"),
+  ("alt-o", "reopen-with-encoding"),
+  ("alt-e", "save-with-encoding"),
+  ("alt-.", "next-buffer"),
+  ("alt-,", "previous-buffer"),
+  ("ctrl-b", "pick-buffer"),
+  ("ctrl-o", "open-file"),
+  ("alt-q", "close-buffer"),
+  ("alt-s", "split-horizontal"),
+  ("alt-v", "split-vertical"),
+  ("alt-x", "close-window"),
+  ("alt-=", "grow-window"),
+  ("alt--", "shrink-window"),
+  ("alt-up", "focus-up"),
+  ("alt-down", "focus-down"),
+  ("alt-left", "focus-left"),
+  ("alt-right", "focus-right"),
+  ("alt-k", "list-bindings"),
+];
+
+/// Which keys run which command.
+/// Keys which aren't bound type their character, and Shift with a movement key extends the selection.
+#[derive(Clone)]
+pub struct Keymap {
+  /// Command names by the keys bound to them, in the order they are listed
+  bindings: Vec<(KeySequence, &'static str)>,
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    let mut keymap = Self {
+      bindings: Vec::new(),
+    };
+    for (keys, name) in DEFAULT_BINDINGS {
+      keymap.bind(
+        KeySequence::try_from(keys.to_string()).expect("Default bindings are valid"),
+        command_name(name),
+      );
+    }
+    keymap
+  }
+}
+
+impl Keymap {
+  /// Binds the keys to the command, replacing what they were bound to. `None` unbinds them.
+  pub fn bind(&mut self, keys: KeySequence, name: Option<&'static str>) {
+    let existing = self.bindings.iter().position(|(bound, _)| *bound == keys);
+    match (existing, name) {
+      (Some(idx), Some(name)) => self.bindings[idx].1 = name,
+      (Some(idx), None) => {
+        self.bindings.remove(idx);
+      }
+      (None, Some(name)) => self.bindings.push((keys, name)),
+      (None, None) => {}
+    }
+  }
+
+  /// What the keys typed so far run. A chord wins over a binding of its first keys, which then can't be used.
+  pub fn lookup(&self, keys: &[Key]) -> Lookup {
+    let is_prefix = self
+      .bindings
+      .iter()
+      .any(|(bound, _)| bound.0.len() > keys.len() && bound.0.starts_with(keys));
+    if is_prefix {
+      return Lookup::Prefix;
+    }
+    if let Some(command) = self
+      .bindings
+      .iter()
+      .find(|(bound, _)| bound.0 == keys)
+      .and_then(|(_, name)| named_command(name))
+    {
+      return Lookup::Command(command);
+    }
+
+    let [key] = keys else {
+      return Lookup::Unbound;
+    };
+    match (key.code, key.modifiers) {
+      (KeyCode::Char(character), KeyModifiers::NONE) => {
+        Lookup::Command(Command::Edit(Edit::Insert(character)))
+      }
+      _ => match key.without_shift().map(|key| self.lookup(&[key])) {
+        Some(Lookup::Command(Command::Move(move_command))) => {
+          Lookup::Command(Command::Select(move_command))
+        }
+        _ => Lookup::Unbound,
+      },
+    }
+  }
+
+  /// Every binding, as the keys and the name of the command
+  pub fn bindings(&self) -> impl Iterator<Item = (&KeySequence, &'static str)> {
+    self.bindings.iter().map(|(keys, name)| (keys, *name))
+  }
+
+  /// Command of the binding at the index, in the order of `bindings`
+  pub fn command_at(&self, idx: usize) -> Option<Command> {
+    self
+      .bindings
+      .get(idx)
+      .and_then(|(_, name)| named_command(name))
+  }
+
+  /// The keys which run the command, for telling the user. The name of the command if it isn't bound.
+  pub fn describe(&self, name: &str) -> String {
+    self
+      .bindings
+      .iter()
+      .find(|(_, bound)| *bound == name)
+      .map_or_else(|| name.to_string(), |(keys, _)| keys.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::editor::command::{Move, System};
+
+  fn keys(text: &str) -> KeySequence {
+    KeySequence::try_from(text.to_string()).expect("keys are valid")
+  }
+
+  fn lookup(keymap: &Keymap, text: &str) -> Lookup {
+    keymap.lookup(&keys(text).0)
+  }
+
+  #[test]
+  fn looks_up_default_bindings() {
+    let keymap = Keymap::default();
+    assert!(matches!(
+      lookup(&keymap, "ctrl-s"),
+      Lookup::Command(Command::System(System::Save))
+    ));
+    assert!(matches!(
+      lookup(&keymap, "up"),
+      Lookup::Command(Command::Move(Move::Up))
+    ));
+  }
+
+  #[test]
+  fn unbound_characters_are_typed() {
+    let keymap = Keymap::default();
+    assert!(matches!(
+      lookup(&keymap, "x"),
+      Lookup::Command(Command::Edit(Edit::Insert('x')))
+    ));
+    assert!(matches!(lookup(&keymap, "ctrl-e"), Lookup::Unbound));
+  }
+
+  #[test]
+  fn shift_with_a_movement_selects() {
+    let keymap = Keymap::default();
+    assert!(matches!(
+      lookup(&keymap, "shift-left"),
+      Lookup::Command(Command::Select(Move::Left))
+    ));
+    assert!(matches!(lookup(&keymap, "shift-delete"), Lookup::Unbound));
+  }
+
+  #[test]
+  fn chords_are_looked_up_key_by_key() {
+    let mut keymap = Keymap::default();
+    keymap.bind(keys("ctrl-k ctrl-s"), command_name("save"));
+    assert!(matches!(lookup(&keymap, "ctrl-k"), Lookup::Prefix));
+    assert!(matches!(
+      lookup(&keymap, "ctrl-k ctrl-s"),
+      Lookup::Command(Command::System(System::Save))
+    ));
+    assert!(matches!(lookup(&keymap, "ctrl-k ctrl-x"), Lookup::Unbound));
+  }
+
+  #[test]
+  fn chord_wins_over_a_binding_of_its_first_key() {
+    let mut keymap = Keymap::default();
+    keymap.bind(keys("ctrl-s ctrl-s"), command_name("quit"));
+    assert!(matches!(lookup(&keymap, "ctrl-s"), Lookup::Prefix));
+  }
+
+  #[test]
+  fn binding_none_unbinds() {
+    let mut keymap = Keymap::default();
+    keymap.bind(keys("ctrl-s"), None);
+    assert!(matches!(lookup(&keymap, "ctrl-s"), Lookup::Unbound));
+    assert_eq!(keymap.describe("save"), "save");
+  }
+
+  #[test]
+  fn rebinding_replaces_the_command() {
+    let mut keymap = Keymap::default();
+    keymap.bind(keys("ctrl-s"), command_name("quit"));
+    assert!(matches!(
+      lookup(&keymap, "ctrl-s"),
+      Lookup::Command(Command::System(System::Quit))
+    ));
+    assert_eq!(keymap.describe("quit"), "Ctrl-S");
+  }
+}
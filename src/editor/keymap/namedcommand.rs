@@ -0,0 +1,117 @@
+use crate::editor::{
+  command::{Command, Edit, Move, System},
+  layout::{Direction, SplitDirection},
+  searchoptions::SearchOption,
+};
+
+/// Commands which can be bound to keys, by the name they have in the configuration file
+const NAMED_COMMANDS: [(&str, Command); 47] = [
+  ("insert-tab", Command::Edit(Edit::Insert('\t'))),
+  ("insert-newline", Command::Edit(Edit::InsertNewline)),
+  ("delete", Command::Edit(Edit::Delete)),
+  ("delete-backward", Command::Edit(Edit::DeleteBackward)),
+  (
+    "delete-word-backward",
+    Command::Edit(Edit::DeleteWordBackward),
+  ),
+  ("undo", Command::Edit(Edit::Undo)),
+  ("redo", Command::Edit(Edit::Redo)),
+  ("cut", Command::Edit(Edit::Cut)),
+  ("copy", Command::Edit(Edit::Copy)),
+  ("paste", Command::Edit(Edit::Paste)),
+  ("move-up", Command::Move(Move::Up)),
+  ("move-down", Command::Move(Move::Down)),
+  ("move-left", Command::Move(Move::Left)),
+  ("move-right", Command::Move(Move::Right)),
+  ("page-up", Command::Move(Move::PageUp)),
+  ("page-down", Command::Move(Move::PageDown)),
+  ("start-of-line", Command::Move(Move::StartOfLine)),
+  ("end-of-line", Command::Move(Move::EndOfLine)),
+  ("save", Command::System(System::Save)),
+  ("quit", Command::System(System::Quit)),
+  ("dismiss", Command::System(System::Dismiss)),
+  ("search", Command::System(System::Search)),
+  ("replace", Command::System(System::Replace)),
+  (
+    "toggle-regex",
+    Command::System(System::ToggleSearchOption(SearchOption::Regex)),
+  ),
+  (
+    "toggle-case-sensitivity",
+    Command::System(System::ToggleSearchOption(SearchOption::CaseSensitivity)),
+  ),
+  (
+    "toggle-whole-word",
+    Command::System(System::ToggleSearchOption(SearchOption::WholeWord)),
+  ),
+  (
+    "toggle-line-ending",
+    Command::System(System::ToggleLineEnding),
+  ),
+  (
+    "cycle-line-numbers",
+    Command::System(System::CycleLineNumbers),
+  ),
+  ("toggle-soft-wrap", Command::System(System::ToggleSoftWrap)),
+  (
+    "toggle-insert-spaces",
+    Command::System(System::ToggleInsertSpaces),
+  ),
+  (
+    "reopen-with-encoding",
+    Command::System(System::ReopenWithEncoding),
+  ),
+  (
+    "save-with-encoding",
+    Command::System(System::SaveWithEncoding),
+  ),
+  ("next-buffer", Command::System(System::NextBuffer)),
+  ("previous-buffer", Command::System(System::PreviousBuffer)),
+  ("pick-buffer", Command::System(System::PickBuffer)),
+  ("open-file", Command::System(System::OpenFile)),
+  ("close-buffer", Command::System(System::CloseBuffer)),
+  (
+    "split-horizontal",
+    Command::System(System::SplitWindow(SplitDirection::Horizontal)),
+  ),
+  (
+    "split-vertical",
+    Command::System(System::SplitWindow(SplitDirection::Vertical)),
+  ),
+  ("close-window", Command::System(System::CloseWindow)),
+  ("grow-window", Command::System(System::GrowWindow)),
+  ("shrink-window", Command::System(System::ShrinkWindow)),
+  (
+    "focus-up",
+    Command::System(System::FocusWindow(Direction::Up)),
+  ),
+  (
+    "focus-down",
+    Command::System(System::FocusWindow(Direction::Down)),
+  ),
+  (
+    "focus-left",
+    Command::System(System::FocusWindow(Direction::Left)),
+  ),
+  (
+    "focus-right",
+    Command::System(System::FocusWindow(Direction::Right)),
+  ),
+  ("list-bindings", Command::System(System::ListBindings)),
+];
+
+/// The command of the given name
+pub fn named_command(name: &str) -> Option<Command> {
+  NAMED_COMMANDS
+    .into_iter()
+    .find(|(command_name, _)| *command_name == name)
+    .map(|(_, command)| command)
+}
+
+/// The name as it is kept in the keymap, `None` if there is no command of that name
+pub fn command_name(name: &str) -> Option<&'static str> {
+  NAMED_COMMANDS
+    .into_iter()
+    .find(|(command_name, _)| *command_name == name)
+    .map(|(command_name, _)| command_name)
+}
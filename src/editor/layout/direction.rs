@@ -0,0 +1,8 @@
+/// Where to move the focus to, relative to the focused window
+#[derive(Clone, Copy)]
+pub enum Direction {
+  Up,
+  Down,
+  Left,
+  Right,
+}
@@ -0,0 +1,321 @@
+mod direction;
+mod node;
+mod splitdirection;
+
+pub use direction::Direction;
+pub use splitdirection::SplitDirection;
+
+use crate::{
+  editor::{
+    layout::node::Node,
+    terminal::Terminal,
+    uicomponents::{UIComponent, Window},
+  },
+  prelude::{Position, Size},
+};
+
+use std::io::Error;
+
+/// Windows need a row for their text and one for their status bar
+const MIN_WINDOW_HEIGHT: usize = 2;
+const MIN_WINDOW_WIDTH: usize = 1;
+const SEPARATOR: &str = "│";
+
+/// The windows the screen is split into, one of which has the focus
+#[derive(Default)]
+pub struct Layout {
+  root: Node,
+  /// Index of the focused window, counting from the top left
+  focused: usize,
+  size: Size,
+  /// Top left corner and size of each window, relative to the layout, in the same order as the windows
+  areas: Vec<(Position, Size)>,
+  /// Top end and height of the columns between windows side by side
+  separators: Vec<(Position, usize)>,
+  needs_redraw: bool,
+}
+
+impl Layout {
+  pub fn focused(&self) -> &Window {
+    self
+      .root
+      .window(self.focused)
+      .expect("Focused window always exists")
+  }
+
+  pub fn focused_mut(&mut self) -> &mut Window {
+    self
+      .root
+      .window_mut(self.focused)
+      .expect("Focused window always exists")
+  }
+
+  pub fn windows(&self) -> impl Iterator<Item = &Window> {
+    let mut windows = Vec::new();
+    self.root.collect_windows(&mut windows);
+    windows.into_iter()
+  }
+
+  pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut Window> {
+    let mut windows = Vec::new();
+    self.root.collect_windows_mut(&mut windows);
+    windows.into_iter()
+  }
+
+  /// Splits the focused window in two, both showing its buffer. The new window gets the focus.
+  /// Returns false if there is not enough room for another window.
+  pub fn split(&mut self, direction: SplitDirection) -> bool {
+    let Size { height, width } = self.focused_area().1;
+    let has_room = match direction {
+      SplitDirection::Horizontal => height >= MIN_WINDOW_HEIGHT.saturating_mul(2),
+      SplitDirection::Vertical => width > MIN_WINDOW_WIDTH.saturating_mul(2),
+    };
+    if !has_room {
+      return false;
+    }
+    let window = Window::new(self.focused().view.clone());
+    self.root.split(self.focused, direction, window);
+    self.focused = self.focused.saturating_add(1);
+    self.arrange();
+    true
+  }
+
+  /// Closes the focused window and returns it, the window taking its place gets the focus.
+  /// The last window can't be closed.
+  pub fn close_focused(&mut self) -> Option<Window> {
+    let (origin, _) = self.focused_area();
+    let window = self.root.close(self.focused)?;
+    self.arrange();
+    self.focused = self.window_at(origin).unwrap_or_default();
+    Some(window)
+  }
+
+  /// Grows or shrinks the focused window within its split.
+  /// Returns false if it can't change its size any further.
+  pub fn resize_focused(&mut self, grow: bool) -> bool {
+    let changed = self.root.resize(self.focused, grow);
+    if changed {
+      self.arrange();
+    }
+    changed
+  }
+
+  /// Moves the focus to the window next to the focused one, lined up with the caret if there are several.
+  /// Returns false if there is no window in that direction.
+  pub fn focus(&mut self, direction: Direction) -> bool {
+    let (origin, size) = self.focused_area();
+    let caret = self.caret_position();
+    let target = match direction {
+      Direction::Up => origin
+        .row
+        .checked_sub(1)
+        .map(|row| Position { row, ..caret }),
+      Direction::Down => Some(Position {
+        row: origin.row.saturating_add(size.height),
+        ..caret
+      }),
+      // Skip the separator
+      Direction::Left => origin
+        .col
+        .checked_sub(2)
+        .map(|col| Position { col, ..caret }),
+      Direction::Right => Some(Position {
+        col: origin.col.saturating_add(size.width).saturating_add(1),
+        ..caret
+      }),
+    };
+    let Some(idx) = target.and_then(|target| self.window_at(target)) else {
+      return false;
+    };
+    self.focused = idx;
+    true
+  }
+
+  /// Caret position of the focused window, relative to the layout
+  pub fn caret_position(&self) -> Position {
+    let (origin, _) = self.focused_area();
+    let caret = self.focused().caret_position();
+    Position {
+      row: origin.row.saturating_add(caret.row),
+      col: origin.col.saturating_add(caret.col),
+    }
+  }
+
+  fn focused_area(&self) -> (Position, Size) {
+    self
+      .areas
+      .get(self.focused)
+      .copied()
+      .unwrap_or((Position::default(), self.size))
+  }
+
+  fn window_at(&self, position: Position) -> Option<usize> {
+    self.areas.iter().position(|(origin, size)| {
+      (origin.row..origin.row.saturating_add(size.height)).contains(&position.row)
+        && (origin.col..origin.col.saturating_add(size.width)).contains(&position.col)
+    })
+  }
+
+  /// Sizes the windows after the layout or its size changed
+  fn arrange(&mut self) {
+    self.areas.clear();
+    self.separators.clear();
+    self.root.arrange(
+      Position::default(),
+      self.size,
+      &mut self.areas,
+      &mut self.separators,
+    );
+    self.set_needs_redraw(true);
+  }
+}
+
+impl UIComponent for Layout {
+  fn set_needs_redraw(&mut self, value: bool) {
+    self.needs_redraw = value;
+    for window in self.windows_mut() {
+      window.set_needs_redraw(value);
+    }
+  }
+
+  fn needs_redraw(&self) -> bool {
+    self.needs_redraw || self.windows().any(UIComponent::needs_redraw)
+  }
+
+  fn set_size(&mut self, size: Size) {
+    self.size = size;
+    self.arrange();
+  }
+
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    let mut windows = Vec::new();
+    self.root.collect_windows_mut(&mut windows);
+    for (window, (window_origin, _)) in windows.into_iter().zip(&self.areas) {
+      window.render(Position {
+        row: origin.row.saturating_add(window_origin.row),
+        col: origin.col.saturating_add(window_origin.col),
+      });
+    }
+
+    if self.needs_redraw {
+      for (top, height) in &self.separators {
+        for row in 0..*height {
+          Terminal::print_at(
+            Position {
+              row: origin.row.saturating_add(top.row).saturating_add(row),
+              col: origin.col.saturating_add(top.col),
+            },
+            1,
+            SEPARATOR,
+          )?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::editor::command::Edit;
+
+  fn layout() -> Layout {
+    let mut layout = Layout::default();
+    layout.resize(Size {
+      height: 20,
+      width: 41,
+    });
+    layout
+  }
+
+  const fn area(row: usize, col: usize, height: usize, width: usize) -> (Position, Size) {
+    (Position { col, row }, Size { height, width })
+  }
+
+  #[test]
+  fn splits_the_focused_window() {
+    let mut layout = layout();
+    assert!(layout.split(SplitDirection::Horizontal));
+    assert_eq!(layout.areas, vec![area(0, 0, 10, 41), area(10, 0, 10, 41)]);
+    assert_eq!(layout.focused, 1);
+
+    assert!(layout.split(SplitDirection::Vertical));
+    assert_eq!(
+      layout.areas,
+      vec![
+        area(0, 0, 10, 41),
+        area(10, 0, 10, 20),
+        area(10, 21, 10, 20)
+      ]
+    );
+    assert_eq!(layout.separators, vec![(Position { row: 10, col: 20 }, 10)]);
+    assert_eq!(layout.focused, 2);
+  }
+
+  #[test]
+  fn split_windows_show_the_same_buffer() {
+    let mut layout = layout();
+    layout.split(SplitDirection::Vertical);
+    layout
+      .focused_mut()
+      .view
+      .handle_edit_command(Edit::Insert('a'));
+    let windows: Vec<&Window> = layout.windows().collect();
+    assert!(windows[0].view.shows_same_buffer(&windows[1].view));
+    assert_eq!(windows[0].view.contents(), "a");
+  }
+
+  #[test]
+  fn needs_room_to_split() {
+    let mut layout = Layout::default();
+    layout.resize(Size {
+      height: 3,
+      width: 2,
+    });
+    assert!(!layout.split(SplitDirection::Horizontal));
+    assert!(!layout.split(SplitDirection::Vertical));
+    assert_eq!(layout.windows().count(), 1);
+  }
+
+  #[test]
+  fn moves_the_focus_to_the_neighbouring_window() {
+    let mut layout = layout();
+    layout.split(SplitDirection::Horizontal);
+    layout.split(SplitDirection::Vertical);
+    assert!(layout.focus(Direction::Left));
+    assert_eq!(layout.focused, 1);
+    assert!(!layout.focus(Direction::Left));
+    assert!(!layout.focus(Direction::Down));
+    assert!(layout.focus(Direction::Up));
+    assert_eq!(layout.focused, 0);
+    assert!(layout.focus(Direction::Down));
+    assert_eq!(layout.focused, 1);
+    assert!(layout.focus(Direction::Right));
+    assert_eq!(layout.focused, 2);
+  }
+
+  #[test]
+  fn closing_focuses_the_window_taking_its_place() {
+    let mut layout = layout();
+    assert!(layout.close_focused().is_none());
+    layout.split(SplitDirection::Horizontal);
+    layout.split(SplitDirection::Vertical);
+    layout.focus(Direction::Left);
+    assert!(layout.close_focused().is_some());
+    assert_eq!(layout.areas, vec![area(0, 0, 10, 41), area(10, 0, 10, 41)]);
+    assert_eq!(layout.focused, 1);
+    assert!(layout.separators.is_empty());
+  }
+
+  #[test]
+  fn resizing_stops_at_the_limits() {
+    let mut layout = layout();
+    layout.split(SplitDirection::Horizontal);
+    assert!(layout.resize_focused(true));
+    assert_eq!(layout.areas, vec![area(0, 0, 9, 41), area(9, 0, 11, 41)]);
+    while layout.resize_focused(false) {}
+    assert_eq!(layout.areas, vec![area(0, 0, 18, 41), area(18, 0, 2, 41)]);
+  }
+}
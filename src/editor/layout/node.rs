@@ -0,0 +1,270 @@
+use crate::{
+  editor::{
+    layout::splitdirection::SplitDirection,
+    uicomponents::{UIComponent, Window},
+  },
+  prelude::{Position, Size},
+};
+
+use std::{cmp::min, mem};
+
+/// Share of a split given to its first part when splitting, in percent
+const EVEN_RATIO: usize = 50;
+/// Change of the ratio when growing or shrinking a window, in percent
+const RATIO_STEP: usize = 5;
+const MIN_RATIO: usize = 10;
+const MAX_RATIO: usize = 90;
+
+/// A part of the layout tree: either a window, or an area split in two.
+/// Windows are counted from the top left, first parts before second ones.
+pub enum Node {
+  Window(Box<Window>),
+  Split {
+    direction: SplitDirection,
+    /// Share of the first part, in percent
+    ratio: usize,
+    first: Box<Node>,
+    second: Box<Node>,
+  },
+}
+
+impl Default for Node {
+  fn default() -> Self {
+    Self::Window(Box::default())
+  }
+}
+
+impl Node {
+  pub fn window_count(&self) -> usize {
+    match self {
+      Self::Window(_) => 1,
+      Self::Split { first, second, .. } => {
+        first.window_count().saturating_add(second.window_count())
+      }
+    }
+  }
+
+  pub fn window(&self, idx: usize) -> Option<&Window> {
+    match self {
+      Self::Window(window) => (idx == 0).then_some(&**window),
+      Self::Split { first, second, .. } => {
+        let first_count = first.window_count();
+        if idx < first_count {
+          first.window(idx)
+        } else {
+          second.window(idx.saturating_sub(first_count))
+        }
+      }
+    }
+  }
+
+  pub fn window_mut(&mut self, idx: usize) -> Option<&mut Window> {
+    match self {
+      Self::Window(window) => (idx == 0).then_some(&mut **window),
+      Self::Split { first, second, .. } => {
+        let first_count = first.window_count();
+        if idx < first_count {
+          first.window_mut(idx)
+        } else {
+          second.window_mut(idx.saturating_sub(first_count))
+        }
+      }
+    }
+  }
+
+  pub fn collect_windows<'a>(&'a self, windows: &mut Vec<&'a Window>) {
+    match self {
+      Self::Window(window) => windows.push(&**window),
+      Self::Split { first, second, .. } => {
+        first.collect_windows(windows);
+        second.collect_windows(windows);
+      }
+    }
+  }
+
+  pub fn collect_windows_mut<'a>(&'a mut self, windows: &mut Vec<&'a mut Window>) {
+    match self {
+      Self::Window(window) => windows.push(&mut **window),
+      Self::Split { first, second, .. } => {
+        first.collect_windows_mut(windows);
+        second.collect_windows_mut(windows);
+      }
+    }
+  }
+
+  /// Splits the window at the index, the new window becoming the second part
+  pub fn split(&mut self, idx: usize, direction: SplitDirection, window: Window) {
+    match self {
+      Self::Window(_) => {
+        let existing = mem::take(self);
+        *self = Self::Split {
+          direction,
+          ratio: EVEN_RATIO,
+          first: Box::new(existing),
+          second: Box::new(Self::Window(Box::new(window))),
+        };
+      }
+      Self::Split { first, second, .. } => {
+        let first_count = first.window_count();
+        if idx < first_count {
+          first.split(idx, direction, window);
+        } else {
+          second.split(idx.saturating_sub(first_count), direction, window);
+        }
+      }
+    }
+  }
+
+  /// Removes the window at the index, the other part of its split takes its place.
+  /// A window which isn't part of a split can't be closed.
+  pub fn close(&mut self, idx: usize) -> Option<Window> {
+    let Self::Split { first, second, .. } = self else {
+      return None;
+    };
+    let first_count = first.window_count();
+    let (target, other, target_idx) = if idx < first_count {
+      (first, second, idx)
+    } else {
+      (second, first, idx.saturating_sub(first_count))
+    };
+
+    if matches!(**target, Self::Split { .. }) {
+      return target.close(target_idx);
+    }
+    let closed = mem::take(&mut **target);
+    *self = mem::take(&mut **other);
+    match closed {
+      Self::Window(window) => Some(*window),
+      Self::Split { .. } => None,
+    }
+  }
+
+  /// Grows or shrinks the window at the index, at the expense of the other part of its split.
+  /// Returns false if the window can't change its size any further.
+  pub fn resize(&mut self, idx: usize, grow: bool) -> bool {
+    let Self::Split {
+      ratio,
+      first,
+      second,
+      ..
+    } = self
+    else {
+      return false;
+    };
+    let first_count = first.window_count();
+    let (target, target_idx, is_first) = if idx < first_count {
+      (first, idx, true)
+    } else {
+      (second, idx.saturating_sub(first_count), false)
+    };
+
+    if matches!(**target, Self::Split { .. }) {
+      return target.resize(target_idx, grow);
+    }
+    let new_ratio = if grow == is_first {
+      min(ratio.saturating_add(RATIO_STEP), MAX_RATIO)
+    } else {
+      ratio.saturating_sub(RATIO_STEP).max(MIN_RATIO)
+    };
+    let changed = new_ratio != *ratio;
+    *ratio = new_ratio;
+    changed
+  }
+
+  /// Sizes every window to its part of the area.
+  /// Collects the area of each window, and the separators between windows side by side.
+  pub fn arrange(
+    &mut self,
+    origin: Position,
+    size: Size,
+    areas: &mut Vec<(Position, Size)>,
+    separators: &mut Vec<(Position, usize)>,
+  ) {
+    match self {
+      Self::Window(window) => {
+        window.resize(size);
+        areas.push((origin, size));
+      }
+      Self::Split {
+        direction: SplitDirection::Horizontal,
+        ratio,
+        first,
+        second,
+      } => {
+        let first_height = share(size.height, *ratio);
+        first.arrange(
+          origin,
+          Size {
+            height: first_height,
+            width: size.width,
+          },
+          areas,
+          separators,
+        );
+        second.arrange(
+          Position {
+            row: origin.row.saturating_add(first_height),
+            col: origin.col,
+          },
+          Size {
+            height: size.height.saturating_sub(first_height),
+            width: size.width,
+          },
+          areas,
+          separators,
+        );
+      }
+      Self::Split {
+        direction: SplitDirection::Vertical,
+        ratio,
+        first,
+        second,
+      } => {
+        // One column is taken by the separator
+        let available = size.width.saturating_sub(1);
+        let first_width = share(available, *ratio);
+        first.arrange(
+          origin,
+          Size {
+            height: size.height,
+            width: first_width,
+          },
+          areas,
+          separators,
+        );
+        let separator_col = origin.col.saturating_add(first_width);
+        separators.push((
+          Position {
+            row: origin.row,
+            col: separator_col,
+          },
+          size.height,
+        ));
+        second.arrange(
+          Position {
+            row: origin.row,
+            col: separator_col.saturating_add(1),
+          },
+          Size {
+            height: size.height,
+            width: available.saturating_sub(first_width),
+          },
+          areas,
+          separators,
+        );
+      }
+    }
+  }
+}
+
+/// Size of the first part of a split, leaving at least one row or column for each part if possible
+fn share(total: usize, ratio: usize) -> usize {
+  if total < 2 {
+    return total;
+  }
+  total
+    .saturating_mul(ratio)
+    .checked_div(100)
+    .unwrap_or_default()
+    .clamp(1, total.saturating_sub(1))
+}
@@ -0,0 +1,8 @@
+/// How a window is split in two
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+  /// One window above the other
+  Horizontal,
+  /// The windows side by side, with a separator between them
+  Vertical,
+}
@@ -0,0 +1,226 @@
+use crate::{
+  editor::line::{Line, graphemewidth::GraphemeWidth, textfragment::TextFragment},
+  prelude::*,
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Bytes after which a chunk ends, at the next grapheme boundary
+const CHUNK_LEN: ByteIdx = 1024;
+
+/// A run of graphemes of a line. Chunks are measured on their own,
+/// so that an edit of a long line only measures the chunks around it again.
+///
+/// Only the first tab of a chunk depends on the column the chunk starts at: what follows it starts at a tab stop.
+/// The graphemes before the first tab are measured from the start of the chunk, and those after it from that tab stop,
+/// so that they stay valid wherever an edit moves the chunk.
+#[derive(Clone)]
+pub struct Chunk {
+  /// Byte index of the chunk within the line
+  pub start: ByteIdx,
+  pub end: ByteIdx,
+  /// Index of the first grapheme of the chunk within the line
+  pub first_grapheme: GraphemeIdx,
+  pub grapheme_count: GraphemeIdx,
+  /// Column the chunk starts at
+  pub col: ColIdx,
+  /// Columns before the first tab, or of the whole chunk if it has none
+  lead_width: ColIdx,
+  /// Index of the first tab within the chunk
+  first_tab: Option<GraphemeIdx>,
+  /// Columns after the first tab, counted from the tab stop it reaches
+  tail_width: ColIdx,
+  /// Graphemes of the chunk, with bytes counted from its start, and columns from the start of the chunk
+  /// or from the tab stop after its first tab
+  fragments: Vec<TextFragment>,
+}
+
+impl Chunk {
+  /// Splits the text into chunks, which start at the given byte index of the line.
+  /// Their graphemes and columns within the line are set by `Line::place_chunks`.
+  pub fn split(text: &str, offset: ByteIdx, tab_width: ColIdx) -> Vec<Self> {
+    let mut chunks = Vec::new();
+    let mut chunk = Self::empty(offset);
+    for grapheme in text.graphemes(true) {
+      if chunk.end.saturating_sub(chunk.start) >= CHUNK_LEN {
+        let start = chunk.end;
+        chunks.push(chunk);
+        chunk = Self::empty(start);
+      }
+      chunk.push(grapheme, tab_width);
+    }
+    if chunk.grapheme_count > 0 {
+      chunks.push(chunk);
+    }
+    chunks
+  }
+
+  const fn empty(start: ByteIdx) -> Self {
+    Self {
+      start,
+      end: start,
+      first_grapheme: 0,
+      grapheme_count: 0,
+      col: 0,
+      lead_width: 0,
+      first_tab: None,
+      tail_width: 0,
+      fragments: Vec::new(),
+    }
+  }
+
+  fn push(&mut self, grapheme: &str, tab_width: ColIdx) {
+    let col = if self.first_tab.is_some() {
+      self.tail_width
+    } else {
+      self.lead_width
+    };
+    let (replacement, rendered_width) = Line::measure(grapheme, col, tab_width);
+    let start = self.end.saturating_sub(self.start);
+    self.fragments.push(TextFragment {
+      rendered_width,
+      replacement,
+      start,
+      end: start.saturating_add(grapheme.len()),
+      col,
+    });
+
+    if self.first_tab.is_none() && grapheme == "\t" {
+      self.first_tab = Some(self.grapheme_count);
+    } else if self.first_tab.is_none() {
+      self.lead_width = self.lead_width.saturating_add(rendered_width.into());
+    } else {
+      self.tail_width = self.tail_width.saturating_add(rendered_width.into());
+    }
+    self.end = self.end.saturating_add(grapheme.len());
+    self.grapheme_count = self.grapheme_count.saturating_add(1);
+  }
+
+  pub const fn end_grapheme(&self) -> GraphemeIdx {
+    self.first_grapheme.saturating_add(self.grapheme_count)
+  }
+
+  /// Column right after the chunk
+  pub fn end_col(&self, tab_width: ColIdx) -> ColIdx {
+    let lead_end = self.col.saturating_add(self.lead_width);
+    if self.first_tab.is_some() {
+      next_tab_stop(lead_end, tab_width).saturating_add(self.tail_width)
+    } else {
+      lead_end
+    }
+  }
+
+  /// The fragment at the index within the chunk, with its bytes and columns counted from the start of the line
+  fn within_line(
+    &self,
+    idx: GraphemeIdx,
+    fragment: &TextFragment,
+    tab_width: ColIdx,
+  ) -> TextFragment {
+    let start = fragment.start.saturating_add(self.start);
+    let end = fragment.end.saturating_add(self.start);
+    let lead_end = self.col.saturating_add(self.lead_width);
+    match self.first_tab {
+      Some(first_tab) if idx == first_tab => TextFragment {
+        rendered_width: GraphemeWidth::Tab(
+          next_tab_stop(lead_end, tab_width).saturating_sub(lead_end),
+        ),
+        start,
+        end,
+        col: lead_end,
+        ..*fragment
+      },
+      Some(first_tab) if idx > first_tab => TextFragment {
+        start,
+        end,
+        col: next_tab_stop(lead_end, tab_width).saturating_add(fragment.col),
+        ..*fragment
+      },
+      _ => TextFragment {
+        start,
+        end,
+        col: self.col.saturating_add(fragment.col),
+        ..*fragment
+      },
+    }
+  }
+
+  /// The grapheme at the index within the line, with its bytes and columns counted from the start of the line
+  pub fn fragment(&self, grapheme_idx: GraphemeIdx, tab_width: ColIdx) -> Option<TextFragment> {
+    let idx = grapheme_idx.checked_sub(self.first_grapheme)?;
+    self
+      .fragments
+      .get(idx)
+      .map(|fragment| self.within_line(idx, fragment, tab_width))
+  }
+
+  /// The graphemes of the chunk, with their bytes and columns counted from the start of the line
+  pub fn fragments_within_line(&self, tab_width: ColIdx) -> impl Iterator<Item = TextFragment> {
+    self
+      .fragments
+      .iter()
+      .enumerate()
+      .map(move |(idx, fragment)| self.within_line(idx, fragment, tab_width))
+  }
+
+  /// Index within the line of the grapheme of the chunk covering the column, the end of the chunk if it is past it
+  pub fn grapheme_idx_at(&self, col: ColIdx, tab_width: ColIdx) -> GraphemeIdx {
+    let fragments = &self.fragments;
+    let lead_end = self.col.saturating_add(self.lead_width);
+    let within_chunk = match self.first_tab {
+      Some(first_tab) if col >= lead_end => {
+        let tail_start = next_tab_stop(lead_end, tab_width);
+        if col < tail_start {
+          first_tab
+        } else {
+          let tail = fragments
+            .get(first_tab.saturating_add(1)..)
+            .unwrap_or_default();
+          let within_tail =
+            tail.partition_point(|fragment| tail_start.saturating_add(fragment.end_col()) <= col);
+          first_tab.saturating_add(1).saturating_add(within_tail)
+        }
+      }
+      first_tab => {
+        let lead = fragments
+          .get(..first_tab.unwrap_or(fragments.len()))
+          .unwrap_or_default();
+        lead.partition_point(|fragment| self.col.saturating_add(fragment.end_col()) <= col)
+      }
+    };
+    self.first_grapheme.saturating_add(within_chunk)
+  }
+
+  /// Index within the line of the grapheme of the chunk starting at the byte index of the line,
+  /// or else of the grapheme after it
+  pub fn grapheme_at_byte(&self, byte_idx: ByteIdx) -> Result<GraphemeIdx, GraphemeIdx> {
+    self
+      .fragments
+      .binary_search_by_key(&byte_idx.saturating_sub(self.start), |fragment| {
+        fragment.start
+      })
+      .map(|idx| self.first_grapheme.saturating_add(idx))
+      .map_err(|idx| self.first_grapheme.saturating_add(idx))
+  }
+
+  /// Index of the first tab at or after the grapheme index within the line, if there is one in this chunk
+  pub fn tab_from(&self, grapheme_idx: GraphemeIdx) -> Option<GraphemeIdx> {
+    self.first_tab?;
+    let skipped = grapheme_idx.saturating_sub(self.first_grapheme);
+    self
+      .fragments
+      .iter()
+      .enumerate()
+      .skip(skipped)
+      .find(|(_, fragment)| matches!(fragment.rendered_width, GraphemeWidth::Tab(_)))
+      .map(|(idx, _)| self.first_grapheme.saturating_add(idx))
+  }
+}
+
+/// The tab stop right after the column
+fn next_tab_stop(col: ColIdx, tab_width: ColIdx) -> ColIdx {
+  let past_stop = col.checked_rem(tab_width).unwrap_or(0);
+  col
+    .saturating_sub(past_stop)
+    .saturating_add(tab_width.max(1))
+}
@@ -2,6 +2,8 @@
 pub enum GraphemeWidth {
   Half,
   Full,
+  /// A tab, reaching up to the next tab stop
+  Tab(usize),
 }
 
 impl From<GraphemeWidth> for usize {
@@ -9,6 +11,7 @@ impl From<GraphemeWidth> for usize {
     match val {
       GraphemeWidth::Half => 1,
       GraphemeWidth::Full => 2,
+      GraphemeWidth::Tab(width) => width,
     }
   }
 }
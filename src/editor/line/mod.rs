@@ -1,3 +1,4 @@
+mod chunk;
 mod graphemewidth;
 mod textfragment;
 
@@ -5,66 +6,143 @@ use crate::{
   editor::{
     annotatedstring::AnnotatedString,
     annotation::Annotation,
-    line::{graphemewidth::GraphemeWidth, textfragment::TextFragment},
+    line::{chunk::Chunk, graphemewidth::GraphemeWidth, textfragment::TextFragment},
+    searchquery::SearchQuery,
   },
   prelude::*,
 };
 
 use std::{
+  cell::{OnceCell, RefCell},
   cmp::min,
   fmt::{Display, Formatter, Result},
   ops::{Deref, Range},
+  rc::Rc,
 };
 
-use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-#[derive(Default, Clone)]
+/// Columns from one tab stop to the next, unless the line is told otherwise
+const DEFAULT_TAB_WIDTH: ColIdx = 4;
+
+#[derive(Clone)]
 pub struct Line {
-  fragments: Vec<TextFragment>,
+  /// Runs of graphemes covering the string, each measured on its own, so that an edit only measures those around it again.
+  /// Computed on first use, as most lines of a large file are never displayed or edited
+  chunks: OnceCell<Vec<Chunk>>,
+  /// The row starts last computed by `wrap`, along with the width they were computed for
+  wrapped: RefCell<Option<(ColIdx, Rc<[GraphemeIdx]>)>>,
   string: String,
+  /// Columns from one tab stop to the next
+  tab_width: ColIdx,
+}
+
+impl Default for Line {
+  fn default() -> Self {
+    Self::from("")
+  }
 }
 
 impl Line {
   pub fn from(line_str: &str) -> Self {
     debug_assert!(line_str.is_empty() || line_str.lines().count() == 1);
 
-    let fragments = Self::str_to_fragments(line_str);
-
     Self {
-      fragments,
+      chunks: OnceCell::new(),
+      wrapped: RefCell::new(None),
       string: String::from(line_str),
+      tab_width: DEFAULT_TAB_WIDTH,
     }
   }
 
-  fn str_to_fragments(line_str: &str) -> Vec<TextFragment> {
-    line_str
-      .grapheme_indices(true)
-      .map(|(byte_idx, grapheme)| {
-        let (replacement, rendered_width) = Self::get_replacement_character(grapheme).map_or_else(
-          || {
-            let unicode_width = grapheme.width();
-            let rendered_width = match unicode_width {
-              0 | 1 => GraphemeWidth::Half,
-              _ => GraphemeWidth::Full,
-            };
-            (None, rendered_width)
-          },
-          |replacement| (Some(replacement), GraphemeWidth::Half),
-        );
-
-        TextFragment {
-          grapheme: grapheme.to_string(),
-          rendered_width,
-          replacement,
-          start: byte_idx,
-        }
-      })
-      .collect()
+  /// Shows tabs up to the next multiple of the width
+  pub fn with_tab_width(mut self, tab_width: ColIdx) -> Self {
+    self.tab_width = tab_width.max(1);
+    self.chunks = OnceCell::new();
+    self.wrapped = RefCell::new(None);
+    self
+  }
+
+  fn chunks(&self) -> &[Chunk] {
+    self.chunks.get_or_init(|| {
+      let mut chunks = Chunk::split(&self.string, 0, self.tab_width);
+      Self::place_chunks(&mut chunks, 0, self.tab_width);
+      chunks
+    })
+  }
+
+  /// Sets where the chunks from the index on start, following the chunk before
+  fn place_chunks(chunks: &mut [Chunk], from: usize, tab_width: ColIdx) {
+    let (mut byte_idx, mut grapheme_idx, mut col) = from
+      .checked_sub(1)
+      .and_then(|idx| chunks.get(idx))
+      .map_or((0, 0, 0), |chunk| {
+        (chunk.end, chunk.end_grapheme(), chunk.end_col(tab_width))
+      });
+    for chunk in chunks.iter_mut().skip(from) {
+      let len = chunk.end.saturating_sub(chunk.start);
+      chunk.start = byte_idx;
+      chunk.end = byte_idx.saturating_add(len);
+      chunk.first_grapheme = grapheme_idx;
+      chunk.col = col;
+      byte_idx = chunk.end;
+      grapheme_idx = chunk.end_grapheme();
+      col = chunk.end_col(tab_width);
+    }
+  }
+
+  /// The grapheme at the index, with its bytes and columns counted from the start of the line
+  fn fragment(&self, grapheme_idx: GraphemeIdx) -> Option<TextFragment> {
+    let chunks = self.chunks();
+    let chunk = chunks.get(chunks.partition_point(|chunk| chunk.end_grapheme() <= grapheme_idx))?;
+    chunk.fragment(grapheme_idx, self.tab_width)
+  }
+
+  /// The graphemes from the index on, with their bytes and columns counted from the start of the line
+  fn fragments_from(&self, grapheme_idx: GraphemeIdx) -> impl Iterator<Item = TextFragment> {
+    let chunks = self.chunks();
+    let first = chunks.partition_point(|chunk| chunk.end_grapheme() <= grapheme_idx);
+    let chunks = chunks.get(first..).unwrap_or_default();
+    let skipped = chunks
+      .first()
+      .map_or(0, |chunk| grapheme_idx.saturating_sub(chunk.first_grapheme));
+    chunks
+      .iter()
+      .flat_map(|chunk| chunk.fragments_within_line(self.tab_width))
+      .skip(skipped)
   }
 
-  fn rebuild_fragments(&mut self) {
-    self.fragments = Self::str_to_fragments(&self.string);
+  /// Index of the grapheme starting at the byte index, if one does
+  fn grapheme_starting_at(&self, byte_idx: ByteIdx) -> Option<GraphemeIdx> {
+    let chunks = self.chunks();
+    let chunk = chunks.get(chunks.partition_point(|chunk| chunk.end <= byte_idx))?;
+    chunk.grapheme_at_byte(byte_idx).ok()
+  }
+
+  /// How a grapheme starting at the column is shown: the character it is replaced with, if any, and its width
+  fn measure(grapheme: &str, col: ColIdx, tab_width: ColIdx) -> (Option<char>, GraphemeWidth) {
+    Self::get_replacement_character(grapheme).map_or_else(
+      || {
+        let unicode_width = grapheme.width();
+        let rendered_width = match unicode_width {
+          0 | 1 => GraphemeWidth::Half,
+          _ => GraphemeWidth::Full,
+        };
+        (None, rendered_width)
+      },
+      |replacement| {
+        // Tabs depend on the column they start at
+        let rendered_width = if grapheme == "\t" {
+          let to_next_stop = col
+            .checked_rem(tab_width)
+            .map_or(1, |past_stop| tab_width.saturating_sub(past_stop));
+          GraphemeWidth::Tab(to_next_stop)
+        } else {
+          GraphemeWidth::Half
+        };
+        (Some(replacement), rendered_width)
+      },
+    )
   }
 
   fn get_replacement_character(for_str: &str) -> Option<char> {
@@ -73,7 +151,7 @@ impl Line {
       // Space stays unchanged
       " " => None,
 
-      // Tab → space
+      // Tab → spaces up to the next tab stop
       "\t" => Some(' '),
 
       // Visible whitespace (non-zero width)
@@ -110,70 +188,80 @@ impl Line {
       return AnnotatedString::default();
     }
 
-    // Create a new annotated string (annotation is not present here)
-    let mut result = AnnotatedString::from(&self.string);
+    // Only the fragments overlapping the range are looked at, so that a row of a very long line
+    // doesn't take copying the whole line
+    let visible: Vec<TextFragment> = self
+      .fragments_from(self.grapheme_idx_at(range.start))
+      .take_while(|fragment| fragment.col < range.end)
+      .collect();
+    let byte_start = visible
+      .first()
+      .map_or(self.string.len(), |fragment| fragment.start);
+    let byte_end = visible.last().map_or(byte_start, |fragment| fragment.end);
+
+    let mut result =
+      AnnotatedString::from(self.string.get(byte_start..byte_end).unwrap_or_default());
 
-    // Apply annotation for this string
+    // Apply annotation for the visible part of the string
+    // Annotations reaching past the end of the text cover the line break, which is appended below
     if let Some(annotations) = annotations {
       for annotation in annotations {
-        result.add_annotation(annotation.annotation_type, annotation.start, annotation.end);
+        let start = annotation.start.clamp(byte_start, byte_end);
+        let end = annotation.end.clamp(byte_start, byte_end);
+        if start < end {
+          result.add_annotation(
+            annotation.annotation_type,
+            start.saturating_sub(byte_start),
+            end.saturating_sub(byte_start),
+          );
+        }
       }
     }
 
-    // Insert replacement characters, and truncate if needed.
+    // Insert replacement characters, and clip the fragments at the edges.
     // We do this backwards, otherwise the byte indices would be off in case a replacement character has a different width than the original character.
-    let mut fragment_start = self.width();
-
-    for fragment in self.fragments.iter().rev() {
-      let fragment_end = fragment_start;
-
-      fragment_start = fragment_start.saturating_sub(fragment.rendered_width.into());
-
-      // No  processing needed if we haven't reached the visible range yet.
-      if fragment_start > range.end {
-        continue;
-      }
-
-      if fragment_start < range.end && fragment_end > range.end {
-        // clip right if the fragment is partially visible
-        result.replace(fragment.start, self.string.len(), "⋯");
-        continue;
-      } else if fragment_start == range.end {
-        // Truncate right if we've reached the end of the visible range
-        result.truncate_right_from(fragment.start);
-        continue;
-      }
-
-      if fragment_end <= range.start {
-        // Fragment ends at the start of the range: Remove the entire left side of the string (if not already at start of string)
-        result.truncate_left_until(fragment.start.saturating_add(fragment.grapheme.len()));
-        //End processing since all remaining fragments will be invisible.
-        break;
-      } else if fragment_start < range.start && fragment_end > range.start {
-        // Fragment overlaps with the start of range: Remove the left side of the string and add an ellipsis
-        result.replace(
-          0,
-          fragment.start.saturating_add(fragment.grapheme.len()),
-          "⋯",
-        );
-        //End processing since all remaining fragments will be invisible.
-        break;
+    for fragment in visible.iter().rev() {
+      let start = fragment.start.saturating_sub(byte_start);
+      let end = fragment.end.saturating_sub(byte_start);
+
+      if fragment.end_col() > range.end {
+        // Clip right if the fragment is partially visible
+        let clipped = Self::clipped(fragment, range.end.saturating_sub(fragment.col));
+        result.replace(start, end, &clipped);
+      } else if fragment.col < range.start {
+        // Fragment overlaps with the start of range: Replace it with what is visible of it
+        let clipped = Self::clipped(fragment, fragment.end_col().saturating_sub(range.start));
+        result.replace(start, end, &clipped);
+      } else if let Some(replacement) = fragment.replacement {
+        // The replacement fills the width of the fragment, e.g. a tab turns into several spaces
+        let width = fragment.rendered_width.into();
+        result.replace(start, end, &replacement.to_string().repeat(width));
       }
+    }
 
-      // Fragment is fully within range: Apply replacement characters if appropriate
-      if fragment_start >= range.start
-        && fragment_end <= range.end
-        && let Some(replacement) = fragment.replacement
-      {
-        let start = fragment.start;
-        let end = start.saturating_add(fragment.grapheme.len());
-        result.replace(start, end, &replacement.to_string());
-      }
+    // The line break takes up the cell right after the text, if that is visible
+    if (range.start..range.end).contains(&self.width())
+      && let Some(line_break) = annotations
+        .into_iter()
+        .flatten()
+        .rfind(|annotation| annotation.end > self.string.len())
+    {
+      result.push_annotated(" ", line_break.annotation_type);
     }
 
     result
   }
 
+  /// What is shown of a fragment which is only partly visible.
+  /// A tab still takes up its visible columns, so that the text after it stays aligned.
+  fn clipped(fragment: &TextFragment, visible_width: ColIdx) -> String {
+    if matches!(fragment.rendered_width, GraphemeWidth::Tab(_)) {
+      " ".repeat(visible_width)
+    } else {
+      String::from("⋯")
+    }
+  }
+
   // Gets the visible graphemes in the given column index.
   // Note that the column index is not the same as the grapheme index:
   // A grapheme can have a width of 2 columns.
@@ -182,96 +270,282 @@ impl Line {
   }
 
   pub fn grapheme_count(&self) -> GraphemeIdx {
-    self.fragments.len()
+    self.chunks().last().map_or(0, Chunk::end_grapheme)
   }
 
   pub fn width_until(&self, grapheme_idx: GraphemeIdx) -> ColIdx {
     self
-      .fragments
-      .iter()
-      .take(grapheme_idx)
-      .map(|fragment| match fragment.rendered_width {
-        GraphemeWidth::Half => 1,
-        GraphemeWidth::Full => 2,
-      })
-      .sum()
+      .fragment(grapheme_idx)
+      .map_or_else(|| self.width(), |fragment| fragment.col)
   }
 
   pub fn width(&self) -> ColIdx {
-    self.width_until(self.grapheme_count())
+    self
+      .chunks()
+      .last()
+      .map_or(0, |chunk| chunk.end_col(self.tab_width))
   }
 
-  /// Inserts a character into the line, or appends it at the end if at == `grapheme_count` + 1
-  pub fn insert_char(&mut self, character: char, at: GraphemeIdx) {
-    debug_assert!(at.saturating_sub(1) <= self.grapheme_count());
+  /// Index of the grapheme covering the column, `grapheme_count` if the column is past the end of the line
+  pub fn grapheme_idx_at(&self, col: ColIdx) -> GraphemeIdx {
+    let chunks = self.chunks();
+    let Some(chunk) =
+      chunks.get(chunks.partition_point(|chunk| chunk.end_col(self.tab_width) <= col))
+    else {
+      return self.grapheme_count();
+    };
+    chunk.grapheme_idx_at(col, self.tab_width)
+  }
 
-    if let Some(fragment) = self.fragments.get(at) {
-      self.string.insert(fragment.start, character);
+  /// Grapheme indices at which the rows start when the line is wrapped to the width, the first one being 0.
+  /// Rows are broken after whitespace where possible, and a grapheme is never split across two rows.
+  /// A line filling its last row entirely gets an empty row after it, to leave room for the caret at its end.
+  /// The result is kept until the line changes, as it is asked for on every caret move and drawn row.
+  pub fn wrap(&self, width: ColIdx) -> Rc<[GraphemeIdx]> {
+    if let Some((wrapped_width, starts)) = self.wrapped.borrow().as_ref()
+      && *wrapped_width == width
+    {
+      return Rc::clone(starts);
+    }
+    let mut starts = vec![0];
+    starts.extend(self.wrap_rows(width, 0, |_| false).0);
+    let starts: Rc<[GraphemeIdx]> = starts.into();
+    *self.wrapped.borrow_mut() = Some((width, Rc::clone(&starts)));
+    starts
+  }
+
+  /// Starts of the rows after the one starting at `from`, up to the end of the line,
+  /// or up to the first one for which `is_known` holds, which is returned along with them
+  fn wrap_rows(
+    &self,
+    width: ColIdx,
+    from: GraphemeIdx,
+    is_known: impl Fn(GraphemeIdx) -> bool,
+  ) -> (Vec<GraphemeIdx>, Option<GraphemeIdx>) {
+    let mut starts = Vec::new();
+    let mut row_col = self.width_until(from);
+    let mut end_col = row_col;
+    // Where the current row can be broken without splitting a word: right after its last whitespace
+    let mut break_at: Option<(GraphemeIdx, ColIdx)> = None;
+
+    for (idx, fragment) in (from..).zip(self.fragments_from(from)) {
+      let fragment_width: ColIdx = fragment.rendered_width.into();
+      let row_width = fragment.col.saturating_sub(row_col);
+      // A grapheme wider than the row has to go somewhere, so only non-empty rows are broken
+      if row_width > 0 && row_width.saturating_add(fragment_width) > width {
+        let (row_start, col) = break_at.unwrap_or((idx, fragment.col));
+        if is_known(row_start) {
+          return (starts, Some(row_start));
+        }
+        starts.push(row_start);
+        row_col = col;
+        break_at = None;
+      }
+      end_col = fragment.end_col();
+      if self.is_whitespace(&fragment) {
+        break_at = Some((idx.saturating_add(1), end_col));
+      }
+    }
+
+    if width > 0 && end_col.saturating_sub(row_col) >= width {
+      starts.push(self.grapheme_count());
+    }
+    (starts, None)
+  }
+
+  /// Row starts after an edit which replaced the graphemes before `old_end` from `start` on with those before `new_end`,
+  /// keeping the rows the edit didn't change.
+  /// The text after the edit is laid out as before from the first row starting at the same grapheme on,
+  /// unless the edit moved it to another column between tab stops. It then is from the first tab after the edit on.
+  fn rewrap(
+    &self,
+    width: ColIdx,
+    mut old_starts: Rc<[GraphemeIdx]>,
+    start: GraphemeIdx,
+    old_end: GraphemeIdx,
+    new_end: GraphemeIdx,
+    is_aligned: bool,
+  ) -> Rc<[GraphemeIdx]> {
+    // The row before the edit may take up the start of the edited text, so it is wrapped again as well
+    let kept = old_starts
+      .partition_point(|row_start| *row_start <= start)
+      .saturating_sub(1)
+      .max(1);
+    let from = old_starts.get(kept.saturating_sub(1)).copied().unwrap_or(0);
+    let same_from = if is_aligned {
+      new_end
     } else {
-      self.string.push(character);
+      self
+        .next_tab(new_end)
+        .map_or(new_end, |idx| idx.saturating_add(1))
+    };
+    let old_idx = |new_idx: GraphemeIdx| new_idx.saturating_sub(new_end).saturating_add(old_end);
+
+    let (rows, known) = self.wrap_rows(width, from, |row_start| {
+      row_start >= same_from && old_starts.binary_search(&old_idx(row_start)).is_ok()
+    });
+    let rest = known.map_or(old_starts.len(), |known| {
+      old_starts.partition_point(|row_start| *row_start < old_idx(known))
+    });
+    let shift = |row_start: &GraphemeIdx| row_start.saturating_sub(old_end).saturating_add(new_end);
+    // A long line has many rows, which are updated in place unless the edit changed how many there are
+    if rest.saturating_sub(kept) == rows.len()
+      && let Some(starts) = Rc::get_mut(&mut old_starts)
+    {
+      for (row_start, new_start) in starts.iter_mut().skip(kept).zip(rows) {
+        *row_start = new_start;
+      }
+      for row_start in starts.get_mut(rest..).unwrap_or_default() {
+        *row_start = shift(row_start);
+      }
+      return old_starts;
     }
+    old_starts
+      .get(..kept)
+      .unwrap_or_default()
+      .iter()
+      .copied()
+      .chain(rows)
+      .chain(old_starts.get(rest..).unwrap_or_default().iter().map(shift))
+      .collect()
+  }
 
-    self.rebuild_fragments();
+  /// Index of the first tab at or after the grapheme index
+  fn next_tab(&self, grapheme_idx: GraphemeIdx) -> Option<GraphemeIdx> {
+    let chunks = self.chunks();
+    let first = chunks.partition_point(|chunk| chunk.end_grapheme() <= grapheme_idx);
+    chunks
+      .get(first..)
+      .unwrap_or_default()
+      .iter()
+      .find_map(|chunk| chunk.tab_from(grapheme_idx))
   }
 
-  pub fn append_char(&mut self, character: char) {
-    self.insert_char(character, self.grapheme_count());
+  fn is_whitespace(&self, fragment: &TextFragment) -> bool {
+    self
+      .string
+      .get(fragment.start..fragment.end)
+      .unwrap_or_default()
+      .chars()
+      .all(char::is_whitespace)
   }
 
-  pub fn delete(&mut self, at: GraphemeIdx) {
-    debug_assert!(at <= self.grapheme_count());
+  /// Replaces the graphemes within the range with the text.
+  /// Only the chunks around the edit are measured again, and the rows of the wrapped line are kept where the edit didn't change them.
+  fn replace(&mut self, range: Range<GraphemeIdx>, text: &str) {
+    let old_count = self.grapheme_count();
+    let end = min(range.end, old_count);
+    let start = min(range.start, end);
+    let start_byte = self.grapheme_idx_to_byte_idx(start);
+    let end_byte = self.grapheme_idx_to_byte_idx(end);
+    let old_end_col = self.width_until(end);
+
+    self.string.replace_range(start_byte..end_byte, text);
+    let tab_width = self.tab_width;
+    if let Some(chunks) = self.chunks.get_mut() {
+      let mut first = chunks
+        .partition_point(|chunk| chunk.end <= start_byte)
+        .min(chunks.len().saturating_sub(1));
+      let mut last = chunks
+        .partition_point(|chunk| chunk.end < end_byte)
+        .min(chunks.len().saturating_sub(1));
+      // Graphemes next to the edit may join with the text, so a chunk the edit borders is split again as well
+      if chunks
+        .get(first)
+        .is_some_and(|chunk| chunk.start == start_byte)
+      {
+        first = first.saturating_sub(1);
+      }
+      if chunks.get(last).is_some_and(|chunk| chunk.end == end_byte) {
+        last = last.saturating_add(1).min(chunks.len().saturating_sub(1));
+      }
+      let split_start = chunks.get(first).map_or(0, |chunk| chunk.start);
+      let split_end = chunks
+        .get(last)
+        .map_or(end_byte, |chunk| chunk.end)
+        .saturating_sub(end_byte.saturating_sub(start_byte))
+        .saturating_add(text.len());
+      let split = Chunk::split(
+        self.string.get(split_start..split_end).unwrap_or_default(),
+        split_start,
+        tab_width,
+      );
+      if chunks.is_empty() {
+        *chunks = split;
+      } else {
+        chunks.splice(first..=last, split);
+      }
+      Self::place_chunks(chunks, first, tab_width);
+    }
 
-    if let Some(fragment) = self.fragments.get(at) {
-      let start = fragment.start;
-      let end = fragment.start.saturating_add(fragment.grapheme.len());
-      self.string.drain(start..end);
-      self.rebuild_fragments();
+    let wrapped = self.wrapped.take();
+    if let Some((width, old_starts)) = wrapped {
+      let new_end = end
+        .saturating_add(self.grapheme_count())
+        .saturating_sub(old_count);
+      let is_aligned =
+        self.width_until(new_end).checked_rem(tab_width) == old_end_col.checked_rem(tab_width);
+      let starts = self.rewrap(width, old_starts, start, end, new_end, is_aligned);
+      *self.wrapped.borrow_mut() = Some((width, starts));
     }
   }
 
-  pub fn delete_last(&mut self) {
-    self.delete(self.grapheme_count().saturating_sub(1));
+  /// Inserts a character into the line, or appends it at the end if at == `grapheme_count` + 1
+  pub fn insert_char(&mut self, character: char, at: GraphemeIdx) {
+    debug_assert!(at.saturating_sub(1) <= self.grapheme_count());
+
+    self.replace(at..at, character.encode_utf8(&mut [0; 4]));
+  }
+
+  /// Inserts a string into the line, or appends it at the end if at == `grapheme_count`
+  pub fn insert_str(&mut self, string: &str, at: GraphemeIdx) {
+    debug_assert!(at <= self.grapheme_count());
+
+    self.replace(at..at, string);
   }
 
-  /// Append another line to current line
-  pub fn append(&mut self, other: &Self) {
-    self.string.push_str(&other.string);
-    self.rebuild_fragments();
+  pub fn delete(&mut self, at: GraphemeIdx) {
+    debug_assert!(at <= self.grapheme_count());
+
+    self.replace(at..at.saturating_add(1), "");
   }
 
-  /// Split the line at the give grapheme index
-  pub fn split(&mut self, at: GraphemeIdx) -> Self {
-    if let Some(fragment) = self.fragments.get(at) {
-      let remainder = self.string.split_off(fragment.start);
-      self.rebuild_fragments();
-      Self::from(&remainder)
-    } else {
-      Self::default()
-    }
+  /// Delete all graphemes within the given range
+  pub fn delete_range(&mut self, range: Range<GraphemeIdx>) {
+    self.replace(range, "");
   }
 
-  /// Convert a byte index to an option of grapheme index.
-  /// Returns `None` if given index is outside of the string
-  fn byte_idx_to_grapheme_idx(&self, byte_idx: ByteIdx) -> Option<GraphemeIdx> {
-    if byte_idx > self.string.len() {
-      return None;
-    }
+  /// Start of the word before the given grapheme index, with words delimited by whitespace like the shell's Ctrl-W
+  pub fn previous_word_start(&self, grapheme_idx: GraphemeIdx) -> GraphemeIdx {
+    let is_whitespace = |idx: GraphemeIdx| {
+      self
+        .fragment(idx)
+        .is_some_and(|fragment| self.is_whitespace(&fragment))
+    };
 
-    self
-      .fragments
-      .iter()
-      .position(|fragment| fragment.start >= byte_idx)
+    let mut idx = min(grapheme_idx, self.grapheme_count());
+    while idx > 0 && is_whitespace(idx.saturating_sub(1)) {
+      idx = idx.saturating_sub(1);
+    }
+    while idx > 0 && !is_whitespace(idx.saturating_sub(1)) {
+      idx = idx.saturating_sub(1);
+    }
+    idx
   }
 
   /// Convert a grapheme index to a byte index
-  fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+  pub fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
     debug_assert!(grapheme_idx <= self.grapheme_count());
 
     if grapheme_idx == 0 || self.grapheme_count() == 0 {
       return 0;
     }
 
-    self.fragments.get(grapheme_idx).map_or_else(
+    if grapheme_idx == self.grapheme_count() {
+      return self.string.len();
+    }
+
+    self.fragment(grapheme_idx).map_or_else(
       || {
         #[cfg(debug_assertions)]
         {
@@ -286,7 +560,11 @@ impl Line {
     )
   }
 
-  pub fn search_forward(&self, query: &str, from_grapheme_idx: GraphemeIdx) -> Option<GraphemeIdx> {
+  pub fn search_forward(
+    &self,
+    query: &SearchQuery,
+    from_grapheme_idx: GraphemeIdx,
+  ) -> Option<GraphemeIdx> {
     debug_assert!(from_grapheme_idx <= self.grapheme_count());
 
     // Exit Early in case we're already at the end of the line
@@ -304,7 +582,7 @@ impl Line {
 
   pub fn search_backward(
     &self,
-    query: &str,
+    query: &SearchQuery,
     from_grapheme_idx: GraphemeIdx,
   ) -> Option<GraphemeIdx> {
     debug_assert!(from_grapheme_idx <= self.grapheme_count());
@@ -313,11 +591,7 @@ impl Line {
       return None;
     }
 
-    let end = if from_grapheme_idx == self.grapheme_count() {
-      self.string.len()
-    } else {
-      self.grapheme_idx_to_byte_idx(from_grapheme_idx)
-    };
+    let end = self.grapheme_idx_to_byte_idx(from_grapheme_idx);
 
     self
       .find_all(query, 0..end)
@@ -325,65 +599,72 @@ impl Line {
       .map(|(_, grapheme_idx)| *grapheme_idx)
   }
 
+  /// Returns the grapheme range of the match starting exactly at the given grapheme index, if there is one
+  pub fn match_at(
+    &self,
+    query: &SearchQuery,
+    grapheme_idx: GraphemeIdx,
+  ) -> Option<Range<GraphemeIdx>> {
+    let start = self.grapheme_idx_to_byte_idx(grapheme_idx);
+
+    self
+      .find_all(query, start..self.string.len())
+      .first()
+      .filter(|(_, match_grapheme_idx)| *match_grapheme_idx == grapheme_idx)
+      .map(|(range, _)| grapheme_idx..self.byte_idx_to_grapheme_idx(range.end))
+  }
+
+  /// Convert a byte index to the index of the grapheme starting there.
+  /// A byte index within a grapheme maps to the next one.
+  pub fn byte_idx_to_grapheme_idx(&self, byte_idx: ByteIdx) -> GraphemeIdx {
+    let chunks = self.chunks();
+    let Some(chunk) = chunks.get(chunks.partition_point(|chunk| chunk.end <= byte_idx)) else {
+      return self.grapheme_count();
+    };
+    chunk
+      .grapheme_at_byte(byte_idx)
+      .unwrap_or_else(|grapheme_idx| grapheme_idx)
+  }
+
   /// Finds all matches which aligns with grapheme boundaries.
   /// Parameters:
-  /// - `query`: The query to search for.
-  /// - `matches`: A vector of byte indices of potential matches, which might or might not align with the grapheme clusters.
-  /// - `Returns: Vec<(ByteIdx, GraphemeIdx)> `
+  /// - `matches`: A vector of byte ranges of potential matches, which might or might not align with the grapheme clusters.
+  /// - `Returns: Vec<(Range<ByteIdx>, GraphemeIdx)> `
   ///
-  /// A `Vec` of `(byte_index, grapheme_idx)` pairs for each match that alignes with the grapheme clusters, where `byte_index` is the byte index of the match, and `grapheme_idx` is the grapheme index of the match.
+  /// A `Vec` of `(byte_range, grapheme_idx)` pairs for each match that starts and ends on a grapheme boundary,
+  /// where `byte_range` is the byte range of the match, and `grapheme_idx` is the grapheme index of its start.
   fn match_grapheme_clusters(
     &self,
-    matches: &[ByteIdx],
-    query: &str,
-  ) -> Vec<(ByteIdx, GraphemeIdx)> {
-    // Count graphemes in query
-    let grapheme_count = query.graphemes(true).count();
-
+    matches: Vec<Range<ByteIdx>>,
+  ) -> Vec<(Range<ByteIdx>, GraphemeIdx)> {
     matches
-      .iter()
-      .filter_map(|&start| {
-        self
-          .byte_idx_to_grapheme_idx(start)
-          .and_then(|grapheme_idx| {
-            self
-              .fragments
-              .get(grapheme_idx..grapheme_idx.saturating_add(grapheme_count))
-              .and_then(|fragments| {
-                // combine the fragments into a single string
-                let substring = fragments
-                  .iter()
-                  .map(|fragment| fragment.grapheme.as_str())
-                  .collect::<String>();
-                // if combined string matches query, return the start and grapheme count
-                (substring == query).then_some((start, grapheme_idx))
-              })
-          })
+      .into_iter()
+      .filter_map(|range| {
+        let grapheme_idx = self.grapheme_starting_at(range.start)?;
+        let ends_on_boundary =
+          range.end == self.string.len() || self.grapheme_starting_at(range.end).is_some();
+        ends_on_boundary.then_some((range, grapheme_idx))
       })
       .collect()
   }
 
   /// Given a search query and a range in byte indices
-  /// return a vector of pairs of byte indices and grapheme indices of match
-  pub fn find_all(&self, query: &str, range: Range<ByteIdx>) -> Vec<(ByteIdx, GraphemeIdx)> {
+  /// return a vector of pairs of byte ranges and grapheme indices of all matches within that range
+  pub fn find_all(
+    &self,
+    query: &SearchQuery,
+    range: Range<ByteIdx>,
+  ) -> Vec<(Range<ByteIdx>, GraphemeIdx)> {
     let start = range.start;
     let end = min(range.end, self.string.len());
 
     debug_assert!(start <= end);
     debug_assert!(start <= self.string.len());
 
-    self.string.get(start..end).map_or_else(Vec::new, |substr| {
-      // Byte idx of all the potential match
-      let potential_matches: Vec<ByteIdx> = substr
-        .match_indices(query) // find _potential_ matches within the substring
-        .map(|(relative_start, _)| {
-          //convert their relative indices to absolute indices
-          relative_start.saturating_add(start)
-        })
-        .collect();
-      //convert the potential matches into matches which align with the grapheme boundaries.
-      self.match_grapheme_clusters(&potential_matches, query)
-    })
+    // Byte ranges of all the potential matches
+    let potential_matches = query.find_all(&self.string, start..end);
+    //convert the potential matches into matches which align with the grapheme boundaries.
+    self.match_grapheme_clusters(potential_matches)
   }
 }
 
@@ -400,3 +681,161 @@ impl Deref for Line {
     &self.string
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn wrap(text: &str, width: ColIdx) -> Vec<GraphemeIdx> {
+    Line::from(text).wrap(width).to_vec()
+  }
+
+  #[test]
+  fn short_line_is_a_single_row() {
+    assert_eq!(wrap("", 10), [0]);
+    assert_eq!(wrap("abc", 10), [0]);
+  }
+
+  #[test]
+  fn breaks_after_whitespace() {
+    assert_eq!(wrap("aaa bbb ccc", 8), [0, 8]);
+    assert_eq!(wrap("aaa bbb", 5), [0, 4]);
+  }
+
+  #[test]
+  fn breaks_words_longer_than_the_row() {
+    assert_eq!(wrap("abcdefgh", 3), [0, 3, 6]);
+  }
+
+  #[test]
+  fn wide_graphemes_are_not_split() {
+    assert_eq!(wrap("日本語", 5), [0, 2]);
+    assert_eq!(wrap("a日本", 4), [0, 2]);
+  }
+
+  #[test]
+  fn grapheme_wider_than_the_row_gets_a_row_of_its_own() {
+    assert_eq!(wrap("日本", 1), [0, 1, 2]);
+  }
+
+  #[test]
+  fn tabs_break_like_whitespace() {
+    assert_eq!(wrap("a\tb", 4), [0, 2]);
+  }
+
+  #[test]
+  fn full_last_row_is_followed_by_an_empty_one() {
+    assert_eq!(wrap("abcdef", 3), [0, 3, 6]);
+    assert_eq!(wrap("ab cde", 3), [0, 3, 6]);
+  }
+
+  #[test]
+  fn wrapping_again_to_another_width() {
+    let line = Line::from("abcdef");
+    assert_eq!(*line.wrap(3), [0, 3, 6]);
+    assert_eq!(*line.wrap(4), [0, 4]);
+  }
+
+  #[test]
+  fn edits_wrap_the_line_again() {
+    let mut line = Line::from("abc");
+    assert_eq!(*line.wrap(3), [0, 3]);
+    line.delete(0);
+    assert_eq!(*line.wrap(3), [0]);
+  }
+
+  /// Checks that the line, as edited so far, is laid out as it would be if it were built from its text
+  fn assert_laid_out_afresh(line: &Line, width: ColIdx) {
+    let fresh = Line::from(line).with_tab_width(line.tab_width);
+    assert_eq!(line.grapheme_count(), fresh.grapheme_count());
+    assert_eq!(line.width(), fresh.width());
+    for grapheme_idx in (0..fresh.grapheme_count()).step_by(97) {
+      assert_eq!(
+        line.width_until(grapheme_idx),
+        fresh.width_until(grapheme_idx)
+      );
+      assert_eq!(
+        line.grapheme_idx_to_byte_idx(grapheme_idx),
+        fresh.grapheme_idx_to_byte_idx(grapheme_idx)
+      );
+    }
+    assert_eq!(*line.wrap(width), *fresh.wrap(width));
+  }
+
+  #[test]
+  fn edits_of_a_long_line_only_change_what_they_touch() {
+    let mut line = Line::from(&"one\ttwo 日本語 three  ".repeat(600)).with_tab_width(4);
+    for width in [7, 80] {
+      line.wrap(width);
+      for step in 0..24_usize {
+        let at = step.saturating_mul(7919) % line.grapheme_count();
+        match step % 4 {
+          0 => line.insert_char('x', at),
+          1 => line.insert_str("\tab cd", at),
+          2 => line.delete(at),
+          _ => line.delete_range(at..at.saturating_add(300)),
+        }
+        assert_laid_out_afresh(&line, width);
+      }
+    }
+  }
+
+  #[test]
+  fn joins_graphemes_across_an_edit() {
+    let mut line = Line::from("ae\u{301}b");
+    line.insert_char('\u{301}', 1);
+    assert_eq!(line.grapheme_count(), 3);
+    line.delete(0);
+    assert_eq!(&*line, "e\u{301}b");
+  }
+
+  #[test]
+  fn columns_and_graphemes() {
+    let line = Line::from("a\t日b");
+    assert_eq!(line.width_until(2), 4);
+    assert_eq!(line.width(), 7);
+    assert_eq!(line.grapheme_idx_at(3), 1);
+    assert_eq!(line.grapheme_idx_at(5), 2);
+    assert_eq!(line.grapheme_idx_at(7), 4);
+  }
+
+  #[test]
+  fn tabs_reach_the_next_tab_stop() {
+    let line = Line::from("\ta\tab\tabcd\t日\t").with_tab_width(4);
+    let cols: Vec<ColIdx> = (0..=line.grapheme_count())
+      .map(|idx| line.width_until(idx))
+      .collect();
+    assert_eq!(cols, [0, 4, 5, 8, 9, 10, 12, 13, 14, 15, 16, 20, 22, 24]);
+  }
+
+  #[test]
+  fn tab_stops_follow_the_tab_width() {
+    assert_eq!(Line::from("ab\tc").with_tab_width(8).width(), 9);
+    assert_eq!(Line::from("ab\tc").with_tab_width(2).width(), 5);
+    assert_eq!(Line::from("ab\tc").with_tab_width(1).width(), 4);
+    assert_eq!(Line::from("ab\tc").with_tab_width(0).width(), 4);
+  }
+
+  #[test]
+  fn columns_within_a_tab_belong_to_it() {
+    let line = Line::from("a\tb").with_tab_width(8);
+    assert_eq!(line.grapheme_idx_at(1), 1);
+    assert_eq!(line.grapheme_idx_at(7), 1);
+    assert_eq!(line.grapheme_idx_at(8), 2);
+  }
+
+  #[test]
+  fn edits_move_the_following_tab_stops() {
+    let mut line = Line::from("ab\tc").with_tab_width(4);
+    line.insert_str("cd", 0);
+    assert_eq!(line.width_until(5), 8);
+    line.delete_range(0..3);
+    assert_eq!(line.width_until(2), 4);
+  }
+
+  #[test]
+  fn clips_graphemes_at_the_edges_of_the_range() {
+    assert_eq!(Line::from("日本語").get_visible_graphemes(1..5), "⋯本⋯");
+    assert_eq!(Line::from("\tb").get_visible_graphemes(2..5), "  b");
+  }
+}
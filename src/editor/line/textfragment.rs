@@ -1,9 +1,23 @@
-use crate::{editor::line::graphemewidth::GraphemeWidth, prelude::ByteIdx};
+use crate::{
+  editor::line::graphemewidth::GraphemeWidth,
+  prelude::{ByteIdx, ColIdx},
+};
 
-#[derive(Debug, Clone)]
+/// A grapheme of a line, referring to its bytes in the line's string.
+/// Those kept by a chunk count bytes and columns from within the chunk instead.
+#[derive(Debug, Clone, Copy)]
 pub struct TextFragment {
-  pub grapheme: String,
   pub rendered_width: GraphemeWidth,
   pub replacement: Option<char>,
   pub start: ByteIdx,
+  pub end: ByteIdx,
+  /// Column the grapheme starts at, so that columns are found without summing up the widths before
+  pub col: ColIdx,
+}
+
+impl TextFragment {
+  /// Column right after the grapheme
+  pub fn end_col(&self) -> ColIdx {
+    self.col.saturating_add(self.rendered_width.into())
+  }
 }
@@ -0,0 +1,90 @@
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+pub enum LineEnding {
+  #[default]
+  Lf,
+  CrLf,
+  /// Some lines end with CRLF, others with LF. Each line keeps its own ending when saving.
+  Mixed,
+}
+
+impl LineEnding {
+  /// Picks the style used by all lines of the text, LF if there are none
+  pub fn detect(text: &str) -> Self {
+    let line_feeds = text.matches('\n').count();
+    let crlfs = text.matches("\r\n").count();
+    if crlfs == 0 {
+      Self::Lf
+    } else if crlfs == line_feeds {
+      Self::CrLf
+    } else {
+      Self::Mixed
+    }
+  }
+
+  /// The line break written after each line. The carriage returns of a mixed file are part of their lines.
+  pub const fn as_str(self) -> &'static str {
+    match self {
+      Self::Lf | Self::Mixed => "\n",
+      Self::CrLf => "\r\n",
+    }
+  }
+
+  /// Switches between LF and CRLF, a mixed file is converted to LF
+  pub const fn toggled(self) -> Self {
+    match self {
+      Self::Lf => Self::CrLf,
+      Self::CrLf | Self::Mixed => Self::Lf,
+    }
+  }
+}
+
+impl Display for LineEnding {
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+    match self {
+      Self::Lf => write!(formatter, "LF"),
+      Self::CrLf => write!(formatter, "CRLF"),
+      Self::Mixed => write!(formatter, "Mixed"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detects_lf() {
+    assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+  }
+
+  #[test]
+  fn detects_crlf() {
+    assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::CrLf);
+    assert_eq!(LineEnding::detect("a\r\nb"), LineEnding::CrLf);
+  }
+
+  #[test]
+  fn detects_mixed() {
+    assert_eq!(LineEnding::detect("a\r\nb\nc\r\n"), LineEnding::Mixed);
+  }
+
+  #[test]
+  fn text_without_line_breaks_is_lf() {
+    assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a"), LineEnding::Lf);
+  }
+
+  #[test]
+  fn lone_carriage_return_is_no_line_break() {
+    assert_eq!(LineEnding::detect("a\rb\n"), LineEnding::Lf);
+  }
+
+  #[test]
+  fn mixed_is_toggled_to_lf() {
+    assert_eq!(LineEnding::Mixed.toggled(), LineEnding::Lf);
+    assert_eq!(LineEnding::Lf.toggled(), LineEnding::CrLf);
+    assert_eq!(LineEnding::CrLf.toggled(), LineEnding::Lf);
+  }
+}
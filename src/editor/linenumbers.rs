@@ -0,0 +1,56 @@
+use crate::prelude::LineIdx;
+
+use serde::Deserialize;
+
+use std::fmt::{Display, Formatter, Result};
+
+/// Which line numbers the gutter left of the text shows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+  /// No gutter at all
+  #[default]
+  Off,
+  Absolute,
+  /// Distance to the caret line
+  Relative,
+  /// Distance to the caret line, but the absolute number on the caret line itself
+  Hybrid,
+}
+
+impl LineNumbers {
+  pub const fn next(self) -> Self {
+    match self {
+      Self::Off => Self::Absolute,
+      Self::Absolute => Self::Relative,
+      Self::Relative => Self::Hybrid,
+      Self::Hybrid => Self::Off,
+    }
+  }
+
+  /// Whether the numbers change when the caret moves to another line
+  pub const fn follows_caret(self) -> bool {
+    matches!(self, Self::Relative | Self::Hybrid)
+  }
+
+  /// Number shown next to the line, `None` without a gutter
+  pub const fn number(self, line_idx: LineIdx, caret_line_idx: LineIdx) -> Option<usize> {
+    match self {
+      Self::Off => None,
+      Self::Absolute => Some(line_idx.saturating_add(1)),
+      Self::Hybrid if line_idx == caret_line_idx => Some(line_idx.saturating_add(1)),
+      Self::Relative | Self::Hybrid => Some(line_idx.abs_diff(caret_line_idx)),
+    }
+  }
+}
+
+impl Display for LineNumbers {
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+    match self {
+      Self::Off => write!(formatter, "off"),
+      Self::Absolute => write!(formatter, "absolute"),
+      Self::Relative => write!(formatter, "relative"),
+      Self::Hybrid => write!(formatter, "hybrid"),
+    }
+  }
+}
@@ -1,68 +1,191 @@
 mod annotatedstring;
 mod annotation;
 mod annotationtype;
+#[doc(hidden)]
+pub mod bench;
+mod bufferlist;
 mod command;
+mod config;
+mod displayoptions;
+mod document;
 mod documentstatus;
+mod encoding;
 mod filetype;
+mod fuzzymatch;
+mod indentation;
+mod keymap;
+mod layout;
 mod line;
+mod lineending;
+mod linenumbers;
+mod projectfiles;
+mod prompthistory;
+mod register;
+mod searchoptions;
+mod searchquery;
+mod statedir;
+mod swapfile;
 mod terminal;
 mod uicomponents;
 
 use crate::{
   editor::{
+    bufferlist::BufferList,
     command::{
-      Command::{self, Edit, Move, System},
-      Edit::InsertNewline,
-      Move::{Down, Left, Right, Up},
-      System::{Dismiss, Quit, Resize, Save, Search},
+      Command::{self, Edit, Move, Select, System},
+      Edit as EditCommand,
+      Edit::{Insert, InsertNewline},
+      Move::{Down, PageDown, PageUp, Up},
+      System::{
+        CloseBuffer, CloseWindow, CycleLineNumbers, Dismiss, FocusWindow, GrowWindow, ListBindings,
+        NextBuffer, OpenFile, PickBuffer, PreviousBuffer, Quit, ReopenWithEncoding, Replace,
+        Resize, Save, SaveWithEncoding, Search, ShrinkWindow, SplitWindow, ToggleInsertSpaces,
+        ToggleLineEnding, ToggleSearchOption, ToggleSoftWrap,
+      },
     },
+    config::Config,
+    displayoptions::DisplayOptions,
+    encoding::Encoding,
+    keymap::{Key, KeySequence, Lookup},
+    layout::{Direction, Layout, SplitDirection},
+    projectfiles::project_files,
+    prompthistory::{HistoryKind, PromptHistory},
+    register::Register,
+    searchoptions::SearchOptions,
+    swapfile::{SWAP_INTERVAL, describe_difference, difference_lines, flush_watched},
     terminal::Terminal,
-    uicomponents::{CommandBar, MessageBar, StatusBar, UIComponent, View},
+    uicomponents::{CommandBar, MessageBar, Picker, Preview, UIComponent, View},
   },
   prelude::*,
 };
 
-use crossterm::event::{Event, KeyEvent, KeyEventKind, read};
+use crossterm::event::{Event, KeyEvent, KeyEventKind, poll, read};
 
 use std::{
+  cmp::min,
   env,
   io::Error,
+  mem,
   panic::{set_hook, take_hook},
+  path::Path,
+  time::Duration,
 };
 
-const QUIT_TIMES: u8 = 3;
+/// How long typing has to pause before the matches of a search are counted
+const MATCH_COUNT_DELAY: Duration = Duration::from_millis(300);
 
-#[derive(Default, Eq, PartialEq)]
+/// Encodings offered when reopening or saving with an encoding
+const ENCODING_NAMES: &str = "utf-8, utf-16le, utf-16be, windows-1252";
+
+#[derive(Default, Eq, PartialEq, Clone, Copy)]
 enum PromptType {
   Search,
   Save,
+  /// Asks for the text to replace
+  Replace,
+  /// Asks for the replacement
+  ReplaceWith,
+  /// Asks whether to replace the current match
+  ReplaceConfirm,
+  /// Asks for the encoding to read the file with
+  ReopenWithEncoding,
+  /// Asks for the encoding to save the file with
+  SaveWithEncoding,
+  /// Asks what to do with the swap file of an earlier session
+  RecoverSwap,
+  /// Shows how the swap file of an earlier session differs, and asks what to do with it
+  SwapDifference,
+  /// Asks what to do about the file having been changed by someone else
+  ExternalChange,
+  /// Filters the list of open buffers to switch to
+  PickBuffer,
+  /// Filters the files below the current directory to open one
+  OpenFile,
+  /// Filters the key bindings to run the command of one
+  ListBindings,
   #[default]
   None,
 }
 
 impl PromptType {
-  fn is_none(&self) -> bool {
-    *self == Self::None
+  fn is_none(self) -> bool {
+    self == Self::None
+  }
+
+  /// The history recalled with Up/Down in this prompt, if any
+  const fn history_kind(self) -> Option<HistoryKind> {
+    match self {
+      Self::Search | Self::Replace => Some(HistoryKind::Search),
+      Self::Save => Some(HistoryKind::Save),
+      Self::ReplaceWith
+      | Self::ReplaceConfirm
+      | Self::ReopenWithEncoding
+      | Self::SaveWithEncoding
+      | Self::RecoverSwap
+      | Self::SwapDifference
+      | Self::ExternalChange
+      | Self::PickBuffer
+      | Self::OpenFile
+      | Self::ListBindings
+      | Self::None => None,
+    }
+  }
+
+  /// Whether the picker is shown above the command bar
+  const fn shows_picker(self) -> bool {
+    matches!(self, Self::PickBuffer | Self::OpenFile | Self::ListBindings)
+  }
+
+  /// Whether the preview is shown above the command bar, next to the picker if there is one
+  const fn shows_preview(self) -> bool {
+    matches!(self, Self::OpenFile | Self::SwapDifference)
+  }
+
+  /// Whether part of the windows is covered by the picker or the preview
+  const fn covers_windows(self) -> bool {
+    self.shows_picker() || self.shows_preview()
   }
 }
 
 #[derive(Default)]
 pub struct Editor {
   should_quit: bool,
-  view: View,
-  status_bar: StatusBar,
+  buffers: BufferList,
+  /// The windows showing the buffers, each with its own status bar
+  windows: Layout,
   title: String,
   message_bar: MessageBar,
   command_bar: CommandBar,
+  /// Lists the open buffers or the files to open while picking one
+  picker: Picker,
+  /// Shows the start of the file selected while opening one
+  preview: Preview,
   prompt_type: PromptType,
   terminal_size: Size,
   quit_times: u8,
+  /// Set once the user was warned that closing the current buffer drops its unsaved changes
+  confirm_close: bool,
+  /// Text of the last cut or copy
+  clipboard: Register,
+  /// Settings from the configuration files
+  config: Config,
+  /// Keys of a chord typed so far
+  pending_keys: Vec<Key>,
+  /// How every window shows its text
+  display_options: DisplayOptions,
+  search_options: SearchOptions,
+  /// Previously submitted search queries and file names
+  prompt_history: PromptHistory,
 }
 
 impl Editor {
   // region: struct lifecycle
 
   /// Create new editor
+  ///
+  /// # Errors
+  ///
+  /// Fails if the terminal can't be switched into raw mode and the alternate screen.
   pub fn new() -> Result<Self, Error> {
     //Retrieve the current hook, which by default does some nice printing of the panic
     let current_hook = take_hook();
@@ -70,10 +193,15 @@ impl Editor {
     // Move any external variables needed within the closure here.
     // Place the closure into a Box and set it as the new panic hook.
     set_hook(Box::new(move |panic_info| {
-      // Our custom panic hook logic goes here
+      // Our custom panic hook logic goes here.
+      // Unsaved changes are written to the swap files first, as the process might abort right after.
       // Execute the original hook to retain default panic output behavior.
+      let swap_paths = flush_watched();
       let _ = Terminal::terminate();
       current_hook(panic_info);
+      for path in swap_paths {
+        eprintln!("Unsaved changes were written to {}", path.display());
+      }
     }));
 
     // Initialize terminal
@@ -81,30 +209,37 @@ impl Editor {
 
     // Create new editor
     let mut editor = Self::default();
+    editor.prompt_history = PromptHistory::load();
+    let (config, config_errors) = Config::load();
+    editor.apply_config(config);
 
     // Retrieve current terminal size
     let size = Terminal::size().unwrap_or_default();
     // Resize Editor to current terminal size
     editor.handle_resize_command(size);
     // Update message bar
-    editor.update_message("HELP: Ctrl-F = find | Ctrl-S = save | Ctrl-Q = quit");
-
-    // Vector to collect all args
-    let args: Vec<String> = env::args().collect();
+    editor.update_message(&editor.help_message());
+    if !config_errors.is_empty() {
+      editor.update_message(&config_errors.join(" | "));
+    }
 
-    // If there's a first arg
-    if let Some(file_name) = args.get(1) {
+    // Open every file given as an argument, starting with the first one
+    for file_name in env::args().skip(1) {
       debug_assert!(!file_name.is_empty());
 
-      // Try to load file
-      if editor.view.load(file_name).is_err() {
+      match editor.buffers.open(&file_name) {
+        Ok(true) => editor.apply_indentation(),
+        Ok(false) => {}
         // If there's an error load error message in message bar
-        editor.update_message(&format!("ERR: Could not open file: {file_name}"));
+        Err(_) => editor.update_message(&format!("ERR: Could not open file: {file_name}")),
       }
     }
+    editor.buffers.select(0);
+
+    editor.check_swap_files();
 
     // Refresh editor to update ui
-    editor.refresh_status();
+    editor.show_current_buffer();
     Ok(editor)
   }
 
@@ -112,13 +247,31 @@ impl Editor {
 
   // region: Event Loop
 
-  /// Loop through all events
+  /// Loop through all events.
+  /// If anything panics, the panic hook writes unsaved changes to the swap files.
+  ///
+  /// # Panics
+  ///
+  /// Debug builds panic if an event can't be read.
   pub fn run(&mut self) {
     loop {
       self.refresh_screen();
       if self.should_quit {
         break;
       }
+      // Wake up regularly even without input, so that the swap file catches up with the latest edits.
+      // Matches of a search are counted as soon as typing pauses.
+      let timeout = if self.view().is_counting_matches() {
+        MATCH_COUNT_DELAY
+      } else {
+        SWAP_INTERVAL
+      };
+      if matches!(poll(timeout), Ok(false)) {
+        self.view_mut().count_matches();
+        self.update_swap_files(false);
+        self.refresh_status();
+        continue;
+      }
       match read() {
         Ok(event) => self.evaluate_event(event),
         Err(err) => {
@@ -132,6 +285,7 @@ impl Editor {
           }
         }
       }
+      self.update_swap_files(false);
       self.refresh_status();
     }
   }
@@ -146,19 +300,40 @@ impl Editor {
     let _ = Terminal::hide_caret();
 
     if self.in_prompt() {
-      self.command_bar.render(bottom_bar_row);
+      self.command_bar.render(Position {
+        row: bottom_bar_row,
+        col: 0,
+      });
     } else {
-      self.message_bar.render(bottom_bar_row);
+      self.message_bar.render(Position {
+        row: bottom_bar_row,
+        col: 0,
+      });
     }
 
     if self.terminal_size.height > 1 {
-      self
-        .status_bar
-        .render(self.terminal_size.height.saturating_sub(2));
-    }
-
-    if self.terminal_size.height > 2 {
-      self.view.render(0);
+      let is_layout_outdated = self.windows.needs_redraw();
+      self.windows.render(Position::default());
+      if self.prompt_type.covers_windows() {
+        // The picker covers part of the windows, so it has to be drawn again on top of them
+        if is_layout_outdated {
+          self.picker.set_needs_redraw(true);
+          self.preview.set_needs_redraw(true);
+        }
+        let origin = Position {
+          row: self.picker_origin_row(),
+          col: 0,
+        };
+        if self.prompt_type.shows_picker() {
+          self.picker.render(origin);
+        }
+        if self.prompt_type.shows_preview() {
+          self.preview.render(Position {
+            col: self.picker_width(),
+            ..origin
+          });
+        }
+      }
     }
 
     let new_caret_pos = if self.in_prompt() {
@@ -167,7 +342,7 @@ impl Editor {
         col: self.command_bar.caret_position_col(),
       }
     } else {
-      self.view.caret_position()
+      self.windows.caret_position()
     };
 
     debug_assert!(new_caret_pos.col <= self.terminal_size.width);
@@ -179,23 +354,30 @@ impl Editor {
   }
 
   pub fn refresh_status(&mut self) {
-    let status = self.view.get_status();
-    let title = format!("{} - {NAME}", status.file_name);
-    self.status_bar.update_status(status);
+    for window in self.windows.windows_mut() {
+      window.refresh_status();
+    }
+    let title = format!("{} - {NAME}", self.view().get_status().file_name);
     if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
       self.title = title;
     }
   }
 
   fn evaluate_event(&mut self, event: Event) {
-    // Check if we should continue with the event
-    let should_process = match &event {
-      Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
-      Event::Resize(_, _) => true,
-      _ => false,
-    };
+    if matches!(event, Event::FocusGained) {
+      self.check_for_external_change();
+      return;
+    }
 
-    if should_process {
+    if let Event::Key(key_event) = event {
+      if key_event.kind == KeyEventKind::Press {
+        self.process_key(key_event);
+      }
+      return;
+    }
+
+    // Check if we should continue with the event
+    if matches!(event, Event::Resize(_, _) | Event::Paste(_)) {
       // If conversion is success pass that to be processed
       if let Ok(command) = Command::try_from(event) {
         self.process_command(command);
@@ -203,6 +385,47 @@ impl Editor {
     }
   }
 
+  /// Runs the command bound to the keys typed so far, or waits for the rest of a chord
+  fn process_key(&mut self, key_event: KeyEvent) {
+    self.pending_keys.push(Key::from(key_event));
+    let is_chord = self.pending_keys.len() > 1;
+    match self.config.keymap.lookup(&self.pending_keys) {
+      Lookup::Prefix => {
+        let keys = KeySequence(self.pending_keys.clone());
+        self.update_message(&format!("{keys} …"));
+      }
+      Lookup::Command(command) => {
+        self.pending_keys.clear();
+        if is_chord {
+          // Remove the keys typed so far
+          self.update_message("");
+        }
+        self.process_command(command);
+      }
+      Lookup::Unbound => {
+        let keys = KeySequence(mem::take(&mut self.pending_keys));
+        if is_chord {
+          self.update_message(&format!("{keys} is not bound to a command."));
+        }
+      }
+    }
+  }
+
+  /// Tells the keys of the most common commands
+  fn help_message(&self) -> String {
+    let entries = [
+      ("search", "find"),
+      ("replace", "replace"),
+      ("save", "save"),
+      ("open-file", "open"),
+      ("pick-buffer", "buffers"),
+      ("list-bindings", "keys"),
+      ("quit", "quit"),
+    ]
+    .map(|(name, label)| format!("{} = {label}", self.config.keymap.describe(name)));
+    format!("HELP: {}", entries.join(" | "))
+  }
+
   // endregion
 
   // region: command handling
@@ -216,6 +439,19 @@ impl Editor {
     match self.prompt_type {
       PromptType::Search => self.process_command_during_search(command),
       PromptType::Save => self.process_command_during_save(command),
+      PromptType::Replace => self.process_command_during_replace(command),
+      PromptType::ReplaceWith => self.process_command_during_replace_with(command),
+      PromptType::ReplaceConfirm => self.process_command_during_replace_confirm(&command),
+      PromptType::ReopenWithEncoding | PromptType::SaveWithEncoding => {
+        self.process_command_during_encoding(command);
+      }
+      PromptType::RecoverSwap | PromptType::SwapDifference => {
+        self.process_command_during_recover_swap(&command);
+      }
+      PromptType::ExternalChange => self.process_command_during_external_change(&command),
+      PromptType::PickBuffer | PromptType::OpenFile | PromptType::ListBindings => {
+        self.process_command_during_pick(command);
+      }
       PromptType::None => self.process_command_no_prompt(command),
     }
   }
@@ -227,14 +463,71 @@ impl Editor {
     }
     // Reset quit time for all the other commands
     self.reset_quit_times();
+    if !matches!(command, System(CloseBuffer)) {
+      self.confirm_close = false;
+    }
 
     match command {
-      System(Quit | Resize(_) | Dismiss) => {} // Quit and Resize already handled above, others not applicable
+      System(Quit | Resize(_) | Dismiss | ToggleSearchOption(_)) => {} // Quit and Resize already handled above, others not applicable
       System(Search) => self.set_prompt(PromptType::Search),
+      System(ToggleLineEnding) => {
+        let line_ending = self.view_mut().toggle_line_ending();
+        self.update_message(&format!("Line endings will be saved as {line_ending}."));
+      }
+      System(CycleLineNumbers) => {
+        self.display_options.line_numbers = self.display_options.line_numbers.next();
+        self.apply_display_options();
+        self.update_message(&format!(
+          "Line numbers: {}.",
+          self.display_options.line_numbers
+        ));
+      }
+      System(ToggleSoftWrap) => {
+        self.display_options.soft_wrap = !self.display_options.soft_wrap;
+        self.apply_display_options();
+        let state = if self.display_options.soft_wrap {
+          "on"
+        } else {
+          "off"
+        };
+        self.update_message(&format!("Soft wrap: {state}."));
+      }
+      System(ToggleInsertSpaces) => {
+        let message = if self.view_mut().toggle_insert_spaces() {
+          "Tab inserts spaces."
+        } else {
+          "Tab inserts a tab character."
+        };
+        self.update_message(message);
+      }
+      System(Replace) => self.set_prompt(PromptType::Replace),
       System(Save) => self.handle_save_command(),
-      Edit(edit_command) => self.view.handle_edit_command(edit_command),
+      System(ReopenWithEncoding) => self.handle_reopen_with_encoding_command(),
+      System(SaveWithEncoding) => self.set_prompt(PromptType::SaveWithEncoding),
+      System(NextBuffer) => {
+        self.buffers.select_next();
+        self.show_current_buffer();
+      }
+      System(PreviousBuffer) => {
+        self.buffers.select_previous();
+        self.show_current_buffer();
+      }
+      System(PickBuffer) => self.set_prompt(PromptType::PickBuffer),
+      System(OpenFile) => self.set_prompt(PromptType::OpenFile),
+      System(CloseBuffer) => self.handle_close_buffer_command(),
+      System(SplitWindow(direction)) => self.handle_split_window_command(direction),
+      System(CloseWindow) => self.handle_close_window_command(),
+      System(GrowWindow) => self.handle_resize_window_command(true),
+      System(ShrinkWindow) => self.handle_resize_window_command(false),
+      System(FocusWindow(direction)) => self.handle_focus_window_command(direction),
+      System(ListBindings) => self.set_prompt(PromptType::ListBindings),
+      Edit(EditCommand::Cut) => self.handle_cut_command(),
+      Edit(EditCommand::Copy) => self.handle_copy_command(),
+      Edit(EditCommand::Paste) => self.windows.focused_mut().view.paste(&self.clipboard),
+      Edit(edit_command) => self.view_mut().handle_edit_command(edit_command),
 
-      Move(move_command) => self.view.handle_move_command(move_command),
+      Move(move_command) => self.view_mut().handle_move_command(move_command),
+      Select(move_command) => self.view_mut().handle_select_command(move_command),
     }
   }
 
@@ -242,10 +535,8 @@ impl Editor {
 
   fn handle_resize_command(&mut self, size: Size) {
     self.terminal_size = size;
-    self.view.resize(Size {
-      height: size.height.saturating_sub(2),
-      width: size.width,
-    });
+    self.windows.resize(self.layout_size());
+    self.resize_picker();
 
     let bar_size = Size {
       height: 1,
@@ -253,7 +544,6 @@ impl Editor {
     };
 
     self.message_bar.resize(bar_size);
-    self.status_bar.resize(bar_size);
     self.command_bar.resize(bar_size);
   }
 
@@ -261,16 +551,25 @@ impl Editor {
 
   // region: quit command handling
 
-  // clippy::arithmetic_side_effects: quit_times is guaranteed to be between 0 and QUIT_TIMES
+  // clippy::arithmetic_side_effects: quit_times is guaranteed to be between 0 and the configured quit times
   #[allow(clippy::arithmetic_side_effects)]
   fn handle_quit_command(&mut self) {
-    // If buffer is not changed and quit times are equal to 3 then quit
-    if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
+    let modified: Vec<String> = self
+      .buffers
+      .iter()
+      .filter(|document| document.is_modified())
+      .map(|document| document.view.get_status().file_name)
+      .collect();
+
+    // If no buffer is changed or Ctrl-Q was pressed as often as configured then quit
+    if modified.is_empty() || self.quit_times + 1 >= self.config.quit_times {
       self.should_quit = true;
-    } else if self.view.get_status().is_modified {
+    } else {
       self.update_message(&format!(
-        "WARNING! File has unsaved changes. Press Ctrl-Q {} more times to quit.",
-        QUIT_TIMES - self.quit_times - 1
+        "WARNING! Unsaved changes in {}. Press {} {} more times to quit.",
+        modified.join(", "),
+        self.config.keymap.describe("quit"),
+        self.config.quit_times - self.quit_times - 1
       ));
 
       self.quit_times += 1;
@@ -288,10 +587,302 @@ impl Editor {
 
   // end region
 
+  // region: buffer list handling
+
+  /// The view of the focused window, which shows the current buffer
+  fn view(&self) -> &View {
+    &self.windows.focused().view
+  }
+
+  fn view_mut(&mut self) -> &mut View {
+    &mut self.windows.focused_mut().view
+  }
+
+  /// The windows take all rows but the one of the message and command bar
+  const fn layout_size(&self) -> Size {
+    Size {
+      height: self.terminal_size.height.saturating_sub(1),
+      width: self.terminal_size.width,
+    }
+  }
+
+  /// Shows the current buffer in the focused window after switching to it
+  fn show_current_buffer(&mut self) {
+    let mut view = self.buffers.current().view.clone();
+    view.set_display_options(self.display_options);
+    let previous = self.windows.focused_mut().replace_view(view);
+    self.remember_view(previous);
+    self.enter_current_buffer();
+  }
+
+  /// Shows the text of every window the way the user chose
+  fn apply_display_options(&mut self) {
+    for window in self.windows.windows_mut() {
+      window.view.set_display_options(self.display_options);
+    }
+    self.command_bar.set_theme(self.display_options.theme);
+  }
+
+  /// Takes over the settings from the configuration files
+  fn apply_config(&mut self, config: Config) {
+    self.display_options = config.display;
+    self.apply_display_options();
+    self.message_bar.set_duration(config.message_duration);
+    self.config = config;
+  }
+
+  /// Indents the current buffer as configured for its file type
+  fn apply_indentation(&mut self) {
+    let view = &mut self.buffers.current_mut().view;
+    let indentation = self.config.indentation(view.get_status().file_type);
+    view.set_indentation(indentation);
+    // Other windows showing the buffer draw its tabs at the new width too
+    self.windows.set_needs_redraw(true);
+  }
+
+  /// Keeps where the view was in its buffer, to continue from there when the buffer is shown again
+  fn remember_view(&mut self, view: View) {
+    if let Some(document) = self
+      .buffers
+      .iter_mut()
+      .find(|document| document.view.shows_same_buffer(&view))
+    {
+      document.view = view;
+    }
+  }
+
+  /// Asks about a swap file or a change on disk of the buffer the user just switched to
+  fn enter_current_buffer(&mut self) {
+    self.refresh_status();
+    if self.buffers.current().stale_swap().is_some() {
+      self.set_prompt(PromptType::RecoverSwap);
+    } else {
+      self.check_for_external_change();
+    }
+  }
+
+  fn handle_close_buffer_command(&mut self) {
+    let document = self.buffers.current();
+    if document.is_modified() && !self.confirm_close {
+      self.confirm_close = true;
+      let file_name = document.view.get_status().file_name;
+      let keys = self.config.keymap.describe("close-buffer");
+      self.update_message(&format!(
+        "WARNING! {file_name} has unsaved changes. Press {keys} again to close it anyway."
+      ));
+      return;
+    }
+    self.confirm_close = false;
+
+    let mut document = self.buffers.close_current();
+    // Closing is a deliberate choice to drop unsaved changes
+    document.remove_swap_file();
+    // Every window showing the closed buffer shows the current one instead
+    let current = &self.buffers.current().view;
+    for window in self.windows.windows_mut() {
+      if window.view.shows_same_buffer(&document.view) {
+        window.replace_view(current.clone());
+      }
+    }
+    self.enter_current_buffer();
+    self.update_message(&format!("Closed {}.", document.view.get_status().file_name));
+  }
+
+  /// Opens the file, or switches to it if it's already open
+  fn open_file(&mut self, file_name: &str) {
+    match self.buffers.open(file_name) {
+      Ok(is_new) => {
+        if is_new {
+          self.apply_indentation();
+        }
+        if is_new && let Some(pid) = self.buffers.current_mut().check_swap_file() {
+          self.update_message(&format!(
+            "ERR: {file_name} is also open in process {pid}, unsaved changes are not swapped."
+          ));
+        }
+        self.show_current_buffer();
+      }
+      Err(err) => self.update_message(&format!("ERR: Could not open file: {err}")),
+    }
+  }
+
+  // endregion
+
+  // region: window handling
+
+  fn handle_split_window_command(&mut self, direction: SplitDirection) {
+    if !self.windows.split(direction) {
+      self.update_message("Not enough room to split the window.");
+    }
+  }
+
+  fn handle_close_window_command(&mut self) {
+    let Some(window) = self.windows.close_focused() else {
+      let keys = self.config.keymap.describe("quit");
+      self.update_message(&format!(
+        "Can't close the last window, press {keys} to quit."
+      ));
+      return;
+    };
+    self.remember_view(window.view);
+    self.select_focused_buffer();
+    self.enter_current_buffer();
+  }
+
+  fn handle_resize_window_command(&mut self, grow: bool) {
+    if !self.windows.resize_focused(grow) {
+      self.update_message("The window can't be resized any further.");
+    }
+  }
+
+  fn handle_focus_window_command(&mut self, direction: Direction) {
+    if self.windows.focus(direction) {
+      self.select_focused_buffer();
+      self.enter_current_buffer();
+    }
+  }
+
+  /// Makes the buffer shown in the focused window the current one
+  fn select_focused_buffer(&mut self) {
+    let view = &self.windows.focused().view;
+    let idx = self
+      .buffers
+      .iter()
+      .position(|document| document.view.shows_same_buffer(view));
+    if let Some(idx) = idx {
+      self.buffers.select(idx);
+    }
+  }
+
+  // endregion
+
+  // region: picker handling
+
+  /// Picks a buffer to switch to, a file to open, or a command to run
+  fn process_command_during_pick(&mut self, command: Command) {
+    match command {
+      System(Dismiss) => self.set_prompt(PromptType::None),
+      Edit(InsertNewline) => {
+        let prompt_type = self.prompt_type;
+        let selected = self.picker.selected();
+        let file_name = self.picker.selected_entry().map(str::to_string);
+        self.set_prompt(PromptType::None);
+        match (prompt_type, selected, file_name) {
+          (PromptType::OpenFile, _, Some(file_name)) => self.open_file(&file_name),
+          (PromptType::PickBuffer, Some(idx), _) => {
+            self.buffers.select(idx);
+            self.show_current_buffer();
+          }
+          (PromptType::ListBindings, Some(idx), _) => {
+            if let Some(command) = self.config.keymap.command_at(idx) {
+              self.process_command(command);
+            }
+          }
+          _ => {}
+        }
+      }
+      Edit(edit_command) => {
+        self.command_bar.handle_edit_command(edit_command);
+        self.picker.filter(&self.command_bar.value());
+        self.update_preview();
+      }
+      Move(Up) => {
+        self.picker.select_previous();
+        self.update_preview();
+      }
+      Move(Down) => {
+        self.picker.select_next();
+        self.update_preview();
+      }
+      Move(move_command) => self.command_bar.handle_move_command(move_command),
+      // Not applicable while picking, Resize already handled at this stage
+      System(_) | Select(_) => {}
+    }
+  }
+
+  /// Shows the start of the selected file while picking one to open
+  fn update_preview(&mut self) {
+    if self.prompt_type == PromptType::OpenFile {
+      self
+        .preview
+        .show_file(self.picker.selected_entry().map(Path::new));
+    }
+  }
+
+  /// The picker lists as many entries as fit into half of the screen.
+  /// When opening a file, the preview takes the right half of the picker's rows.
+  fn resize_picker(&mut self) {
+    let height = self.picker_height();
+    let picker_width = self.picker_width();
+    self.picker.resize(Size {
+      height,
+      width: picker_width,
+    });
+    self.preview.resize(Size {
+      height,
+      width: self.terminal_size.width.saturating_sub(picker_width),
+    });
+  }
+
+  fn picker_width(&self) -> usize {
+    match self.prompt_type {
+      PromptType::OpenFile => self.terminal_size.width.div_ceil(2),
+      // The preview takes the whole width
+      PromptType::SwapDifference => 0,
+      _ => self.terminal_size.width,
+    }
+  }
+
+  /// With a preview, half of the screen is taken however many entries there are, to leave room for it
+  fn picker_height(&self) -> usize {
+    let half = self.layout_size().height.div_ceil(2);
+    if self.prompt_type.shows_preview() {
+      half
+    } else {
+      min(self.picker.len().max(1), half)
+    }
+  }
+
+  /// The picker is shown at the bottom of the windows, right above the message bar
+  fn picker_origin_row(&self) -> RowIdx {
+    self
+      .layout_size()
+      .height
+      .saturating_sub(self.picker_height())
+  }
+
+  // endregion
+
+  // region: clipboard command handling
+
+  fn handle_cut_command(&mut self) {
+    if let Some(register) = self.view_mut().cut() {
+      self.set_clipboard(register);
+    }
+  }
+
+  fn handle_copy_command(&mut self) {
+    if let Some(register) = self.view_mut().copy() {
+      self.set_clipboard(register);
+    }
+  }
+
+  fn set_clipboard(&mut self, register: Register) {
+    #[cfg(feature = "osc52")]
+    {
+      let _ = Terminal::copy_to_clipboard(&register.text);
+    }
+    self.clipboard = register;
+  }
+
+  // endregion
+
   // region: save command & prompt handling
 
   fn handle_save_command(&mut self) {
-    if self.view.is_file_loaded() {
+    if self.view().is_file_loaded() && self.view().is_changed_on_disk_before_save() {
+      self.set_prompt(PromptType::ExternalChange);
+    } else if self.view().is_file_loaded() {
       self.save(None);
     } else {
       self.set_prompt(PromptType::Save);
@@ -300,31 +891,169 @@ impl Editor {
 
   fn process_command_during_save(&mut self, command: Command) {
     match command {
-      // Not applicable during save, Resize already handled at this stage
-      System(Quit | Resize(_) | Search | Save) | Move(_) => {}
       System(Dismiss) => {
         self.set_prompt(PromptType::None);
         self.update_message("Save aborted.");
       }
+      Move(Up) => self.recall_previous(),
+      Move(Down) => self.recall_next(),
+      Move(move_command) => self.command_bar.handle_move_command(move_command),
       Edit(InsertNewline) => {
         let file_name = self.command_bar.value();
+        self.add_to_history(&file_name);
         self.save(Some(&file_name));
         self.set_prompt(PromptType::None);
       }
       Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+      // Not applicable during save, Resize already handled at this stage
+      System(_) | Select(_) => {}
     }
   }
 
   fn save(&mut self, file_name: Option<&str>) {
+    let file_type = self.view().get_status().file_type;
     let result = if let Some(name) = file_name {
-      self.view.save_as(name)
+      self.view_mut().save_as(name)
     } else {
-      self.view.save()
+      self.view_mut().save()
     };
-    if result.is_ok() {
-      self.update_message("File saved successfully.");
+    match result {
+      Ok(()) => {
+        // Saving under a name of another type indents like files of that type
+        if self.view().get_status().file_type != file_type {
+          self.apply_indentation();
+        }
+        self.update_message("File saved successfully.");
+      }
+      Err(err) => self.update_message(&format!("Error writing file: {err}")),
+    }
+  }
+
+  // endregion
+
+  // region: external change handling
+
+  /// Reloads the file if someone else changed it, or asks what to do if that would drop unsaved changes
+  fn check_for_external_change(&mut self) {
+    // Don't interrupt a prompt, saving checks again anyway
+    if self.in_prompt() || !self.view().is_changed_on_disk() {
+      return;
+    }
+    if self.view().get_status().is_modified {
+      self.set_prompt(PromptType::ExternalChange);
     } else {
-      self.update_message("Error writing file!");
+      self.reload();
+    }
+  }
+
+  fn process_command_during_external_change(&mut self, command: &Command) {
+    match command {
+      Edit(Insert('r')) => {
+        self.set_prompt(PromptType::None);
+        self.reload();
+      }
+      Edit(Insert('o')) => {
+        self.set_prompt(PromptType::None);
+        self.save(None);
+      }
+      Edit(Insert('k')) | System(Dismiss) => {
+        self.set_prompt(PromptType::None);
+        self.view_mut().acknowledge_disk_version();
+        self.update_message("Keeping your version, saving will overwrite the file on disk.");
+      }
+      // The user has to decide what to do about the change first
+      _ => {}
+    }
+  }
+
+  fn reload(&mut self) {
+    match self.view_mut().reload() {
+      Ok(()) => self.update_message("File was changed on disk and has been reloaded."),
+      Err(err) => self.update_message(&format!("ERR: Could not reload file: {err}")),
+    }
+  }
+
+  // endregion
+
+  // region: swap file handling
+
+  /// Looks for swap files left behind by an earlier session.
+  /// What to do with them is asked when switching to their buffer.
+  fn check_swap_files(&mut self) {
+    for document in self.buffers.iter_mut() {
+      if let Some(pid) = document.check_swap_file() {
+        let file_name = document.view.get_status().file_name;
+        self.message_bar.update_message(&format!(
+          "ERR: {file_name} is also open in process {pid}, unsaved changes are not swapped."
+        ));
+      }
+    }
+  }
+
+  /// Keeps the swap files in line with their buffers
+  fn update_swap_files(&mut self, force: bool) {
+    for document in self.buffers.iter_mut() {
+      document.update_swap_file(force);
+    }
+  }
+
+  fn process_command_during_recover_swap(&mut self, command: &Command) {
+    match command {
+      Edit(Insert('r')) => {
+        self.buffers.current_mut().recover_swap();
+        self.set_prompt(PromptType::None);
+        self.update_message("Recovered unsaved changes. Save to keep them, or undo to drop them.");
+      }
+      Edit(Insert('d')) => self.set_prompt(PromptType::SwapDifference),
+      Edit(Insert('x')) => {
+        self.buffers.current_mut().discard_swap();
+        self.set_prompt(PromptType::None);
+        self.update_message("Discarded the swap file.");
+      }
+      // The user has to decide what to do with the swap file first
+      _ => {}
+    }
+  }
+
+  // endregion
+
+  // region: encoding command & prompt handling
+
+  fn handle_reopen_with_encoding_command(&mut self) {
+    if !self.view().is_file_loaded() {
+      self.update_message("There is no file to reopen.");
+    } else if self.view().get_status().is_modified {
+      self.update_message("Save or undo your changes before reopening the file.");
+    } else {
+      self.set_prompt(PromptType::ReopenWithEncoding);
+    }
+  }
+
+  fn process_command_during_encoding(&mut self, command: Command) {
+    match command {
+      System(Dismiss) => self.set_prompt(PromptType::None),
+      Edit(InsertNewline) => {
+        let name = self.command_bar.value();
+        let prompt_type = self.prompt_type;
+        self.set_prompt(PromptType::None);
+        let Some(encoding) = Encoding::from_name(&name) else {
+          self.update_message(&format!("ERR: Unknown encoding: {name}"));
+          return;
+        };
+        if prompt_type == PromptType::ReopenWithEncoding {
+          match self.view_mut().reopen_with_encoding(encoding) {
+            Ok(()) => self.update_message(&format!("Reopened as {encoding}.")),
+            Err(err) => self.update_message(&format!("ERR: Could not reopen file: {err}")),
+          }
+        } else {
+          self.view_mut().set_encoding(encoding);
+          self.update_message(&format!("File will be saved as {encoding}."));
+        }
+      }
+      Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+      Move(move_command) => self.command_bar.handle_move_command(move_command),
+      // Not applicable while picking an encoding, Resize already handled at this stage
+      System(_) | Select(_) => {}
     }
   }
 
@@ -336,24 +1065,148 @@ impl Editor {
     match command {
       System(Dismiss) => {
         self.set_prompt(PromptType::None);
-        self.view.dismiss_search();
+        self.view_mut().dismiss_search();
       }
       Edit(InsertNewline) => {
+        self.add_to_history(&self.command_bar.value());
         self.set_prompt(PromptType::None);
-        self.view.exit_search();
+        self.view_mut().exit_search();
       }
       Edit(edit_command) => {
         self.command_bar.handle_edit_command(edit_command);
-        let query = self.command_bar.value();
-        self.view.search(&query);
+        self.search();
       }
-      Move(Right | Down) => self.view.search_next(),
-      Move(Up | Left) => self.view.search_prev(),
-      // Not applicable during save, Resize already handled at this stage
-      System(Quit | Resize(_) | Search | Save) | Move(_) => {}
+      System(ToggleSearchOption(option)) => {
+        self.search_options.toggle(option);
+        self.command_bar.set_prompt(&self.search_prompt());
+        self.search();
+      }
+      Move(PageDown) => self.view_mut().search_next(),
+      Move(PageUp) => self.view_mut().search_prev(),
+      Move(Up) => {
+        self.recall_previous();
+        self.search();
+      }
+      Move(Down) => {
+        self.recall_next();
+        self.search();
+      }
+      Move(move_command) => self.command_bar.handle_move_command(move_command),
+      // Not applicable during search, Resize already handled at this stage
+      System(_) | Select(_) => {}
     }
   }
 
+  fn search(&mut self) {
+    let query = self.command_bar.value();
+    let search_options = self.search_options;
+    if let Err(err) = self.view_mut().search(&query, search_options) {
+      // The full error spans multiple lines, the last one describes the problem
+      let reason = err.to_string();
+      let reason = reason.lines().last().unwrap_or_default();
+      self.update_message(&format!("ERR: Invalid regex: {reason}"));
+    }
+  }
+
+  fn search_prompt(&self) -> String {
+    let label = if self.prompt_type == PromptType::Replace {
+      "Replace"
+    } else {
+      "Search"
+    };
+    let options = self.search_options.to_string();
+    let options = if options.is_empty() {
+      String::new()
+    } else {
+      format!(" [{options}]")
+    };
+    let keymap = &self.config.keymap;
+    format!(
+      "{label}{options} ({} to cancel, {}/{} to navigate, {}/{} for history): ",
+      keymap.describe("dismiss"),
+      keymap.describe("page-up"),
+      keymap.describe("page-down"),
+      keymap.describe("move-up"),
+      keymap.describe("move-down"),
+    )
+  }
+
+  /// How to use the picker, with the keys bound to choosing, accepting and cancelling
+  fn picker_prompt(&self, label: &str, action: &str) -> String {
+    let keymap = &self.config.keymap;
+    format!(
+      "{label} ({}/{} to choose, {} to {action}, {} to cancel): ",
+      keymap.describe("move-up"),
+      keymap.describe("move-down"),
+      keymap.describe("insert-newline"),
+      keymap.describe("dismiss"),
+    )
+  }
+
+  // endregion
+
+  // region: replace command & prompt handling
+
+  fn process_command_during_replace(&mut self, command: Command) {
+    match command {
+      Edit(InsertNewline) => {
+        self.add_to_history(&self.command_bar.value());
+        self.set_prompt(PromptType::ReplaceWith);
+      }
+      // Entering the text to replace works just like searching for it
+      command => self.process_command_during_search(command),
+    }
+  }
+
+  fn process_command_during_replace_with(&mut self, command: Command) {
+    match command {
+      System(Dismiss) => {
+        self.set_prompt(PromptType::None);
+        self.view_mut().dismiss_search();
+      }
+      Edit(InsertNewline) => {
+        let replacement = self.command_bar.value();
+        if self.view_mut().start_replace(replacement) {
+          self.set_prompt(PromptType::ReplaceConfirm);
+        } else {
+          self.finish_replace();
+        }
+      }
+      Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+      Move(move_command) => self.command_bar.handle_move_command(move_command),
+      // Not applicable during replace, Resize already handled at this stage
+      System(_) | Select(_) => {}
+    }
+  }
+
+  fn process_command_during_replace_confirm(&mut self, command: &Command) {
+    let has_next = match command {
+      Edit(Insert('y')) => self.view_mut().replace_match(),
+      Edit(Insert('n')) => self.view_mut().skip_match(),
+      Edit(Insert('a')) => {
+        self.view_mut().replace_all();
+        false
+      }
+      Edit(Insert('q')) | System(Dismiss) => false,
+      // Nothing else is applicable while confirming
+      _ => true,
+    };
+    if !has_next {
+      self.finish_replace();
+    }
+  }
+
+  fn finish_replace(&mut self) {
+    let count = self.view_mut().finish_replace();
+    self.set_prompt(PromptType::None);
+    let noun = if count == 1 {
+      "occurrence"
+    } else {
+      "occurrences"
+    };
+    self.update_message(&format!("Replaced {count} {noun}"));
+  }
+
   // endregion
 
   // region message & command bar
@@ -371,19 +1224,137 @@ impl Editor {
   }
 
   fn set_prompt(&mut self, prompt_type: PromptType) {
+    if self.prompt_type.covers_windows() {
+      // Uncover the part of the windows below the picker
+      self.windows.set_needs_redraw(true);
+    }
+    self.prompt_type = prompt_type;
+    self.command_bar.clear_value();
+    self.prompt_history.reset_browsing();
     match prompt_type {
       // Ensures the message bar is properly painted during the next redraw cycle
       PromptType::None => self.message_bar.set_needs_redraw(true),
       PromptType::Save => self.command_bar.set_prompt("Save as: "),
-      PromptType::Search => {
-        self.view.enter_search();
+      PromptType::Search | PromptType::Replace => {
+        self.view_mut().enter_search();
+        self.command_bar.set_prompt(&self.search_prompt());
+        if let Some(last_search) = self.prompt_history.last(HistoryKind::Search) {
+          self.command_bar.prefill(last_search);
+          self.search();
+        }
+      }
+      PromptType::ReplaceWith => self.command_bar.set_prompt(&format!(
+        "Replace with ({} to cancel): ",
+        self.config.keymap.describe("dismiss")
+      )),
+      PromptType::ReplaceConfirm => self
+        .command_bar
+        .set_prompt("Replace this occurrence? (y)es (n)o (a)ll (q)uit"),
+      PromptType::ReopenWithEncoding => self
+        .command_bar
+        .set_prompt(&format!("Reopen with encoding ({ENCODING_NAMES}): ")),
+      PromptType::SaveWithEncoding => self
+        .command_bar
+        .set_prompt(&format!("Save with encoding ({ENCODING_NAMES}): ")),
+      PromptType::ExternalChange => {
+        let reload = if self.view().get_status().is_modified {
+          "(r)eload and lose your changes"
+        } else {
+          "(r)eload"
+        };
+        self.command_bar.set_prompt(&format!(
+          "File was changed on disk: {reload} (o)verwrite (k)eep editing"
+        ));
+      }
+      // A crash in the middle of an edit leaves the swap file as it was last written, a moment before
+      PromptType::RecoverSwap => self.command_bar.set_prompt(
+        "Unsaved changes from an earlier session, the last few may be missing: (r)ecover (d)iff (x) discard",
+      ),
+      PromptType::SwapDifference => {
+        let document = self.buffers.current();
+        if let Some(swap) = document.stale_swap() {
+          let text = document.view.contents();
+          let difference = describe_difference(&text, swap);
+          let lines = difference_lines(&text, swap);
+          self
+            .command_bar
+            .set_prompt(&format!("{difference} (r)ecover (x) discard"));
+          self.preview.show_lines(lines);
+          self.resize_picker();
+        }
+      }
+      PromptType::PickBuffer => {
+        let entries = self
+          .buffers
+          .iter()
+          .enumerate()
+          .map(|(idx, document)| format!("{}: {}", idx.saturating_add(1), document.name()))
+          .collect();
+        self.picker.set_entries(entries, self.buffers.current_idx());
+        self.resize_picker();
         self
           .command_bar
-          .set_prompt("Search (Esc to cancel, Arrows to navigate): ");
+          .set_prompt(&self.picker_prompt("Switch to buffer", "switch"));
+      }
+      PromptType::OpenFile => {
+        self.picker.set_entries(project_files(Path::new(".")), 0);
+        self.resize_picker();
+        self.update_preview();
+        self
+          .command_bar
+          .set_prompt(&self.picker_prompt("Open file", "open"));
+      }
+      PromptType::ListBindings => {
+        let bindings: Vec<(String, &str)> = self
+          .config
+          .keymap
+          .bindings()
+          .map(|(keys, name)| (keys.to_string(), name))
+          .collect();
+        let keys_width = bindings
+          .iter()
+          .map(|(keys, _)| keys.len())
+          .max()
+          .unwrap_or_default();
+        let entries = bindings
+          .into_iter()
+          .map(|(keys, name)| format!("{keys:keys_width$}  {name}"))
+          .collect();
+        self.picker.set_entries(entries, 0);
+        self.resize_picker();
+        self
+          .command_bar
+          .set_prompt(&self.picker_prompt("Key bindings", "run"));
       }
     }
-    self.command_bar.clear_value();
-    self.prompt_type = prompt_type;
+  }
+
+  /// Remembers a submitted prompt value and writes the history file
+  fn add_to_history(&mut self, value: &str) {
+    if let Some(kind) = self.prompt_type.history_kind() {
+      self.prompt_history.push(kind, value);
+      // The history is a convenience, failing to persist it should not interrupt editing
+      let _ = self.prompt_history.persist();
+    }
+  }
+
+  fn recall_previous(&mut self) {
+    let Some(kind) = self.prompt_type.history_kind() else {
+      return;
+    };
+    let current = self.command_bar.value();
+    if let Some(value) = self.prompt_history.previous(kind, &current) {
+      self.command_bar.set_value(value);
+    }
+  }
+
+  fn recall_next(&mut self) {
+    let Some(kind) = self.prompt_type.history_kind() else {
+      return;
+    };
+    if let Some(value) = self.prompt_history.next(kind) {
+      self.command_bar.set_value(value);
+    }
   }
 
   // end region
@@ -393,6 +1364,10 @@ impl Drop for Editor {
   fn drop(&mut self) {
     let _ = Terminal::terminate();
     if self.should_quit {
+      // Quitting is a deliberate choice to drop unsaved changes
+      for document in self.buffers.iter_mut() {
+        document.remove_swap_file();
+      }
       let _ = Terminal::print("Goodbye.\r\n");
     }
   }
@@ -0,0 +1,38 @@
+use ignore::WalkBuilder;
+
+use std::path::Path;
+
+/// Build output is never worth opening, even where no `.gitignore` excludes it
+const SKIPPED_DIR: &str = "target";
+
+/// Paths of the files below the directory, relative to it and sorted.
+/// Hidden files and what `.gitignore` excludes are skipped, as are directories which can't be read.
+pub fn project_files(dir: &Path) -> Vec<String> {
+  let mut files: Vec<String> = WalkBuilder::new(dir)
+    // Respect `.gitignore` outside of git repositories as well
+    .require_git(false)
+    .filter_entry(|entry| {
+      !(entry.depth() > 0
+        && entry.file_name() == SKIPPED_DIR
+        && entry
+          .file_type()
+          .is_some_and(|file_type| file_type.is_dir()))
+    })
+    .build()
+    .filter_map(Result::ok)
+    .filter(|entry| {
+      entry
+        .file_type()
+        .is_some_and(|file_type| file_type.is_file())
+    })
+    .filter_map(|entry| {
+      entry
+        .path()
+        .strip_prefix(dir)
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+    })
+    .collect();
+  files.sort();
+  files
+}
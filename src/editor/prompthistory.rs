@@ -0,0 +1,155 @@
+use crate::editor::statedir::state_dir;
+
+use std::{
+  fs::{create_dir_all, read_to_string, write},
+  io::Error,
+  path::PathBuf,
+};
+
+/// Maximum number of entries kept per kind
+const MAX_ENTRIES: usize = 100;
+
+/// Prompts with their own history
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+  Search,
+  Save,
+}
+
+impl HistoryKind {
+  /// Name used to tag the entries in the history file
+  const fn tag(self) -> &'static str {
+    match self {
+      Self::Search => "search",
+      Self::Save => "save",
+    }
+  }
+
+  fn from_tag(tag: &str) -> Option<Self> {
+    match tag {
+      "search" => Some(Self::Search),
+      "save" => Some(Self::Save),
+      _ => None,
+    }
+  }
+}
+
+/// Previously submitted prompt values, persisted across sessions
+#[derive(Default)]
+pub struct PromptHistory {
+  /// Oldest entries first
+  search: Vec<String>,
+  save: Vec<String>,
+  /// Index of the entry currently shown while browsing, `None` if not browsing
+  browse_idx: Option<usize>,
+  /// What the user had typed before starting to browse
+  draft: String,
+}
+
+impl PromptHistory {
+  /// Loads the history file. Returns an empty history if there is none.
+  pub fn load() -> Self {
+    let mut history = Self::default();
+    let Some(contents) = history_file_path().and_then(|path| read_to_string(path).ok()) else {
+      return history;
+    };
+
+    for line in contents.lines() {
+      if let Some((tag, value)) = line.split_once('\t')
+        && let Some(kind) = HistoryKind::from_tag(tag)
+      {
+        history.push(kind, value);
+      }
+    }
+    history
+  }
+
+  /// Writes the history file, creating its directory if needed
+  pub fn persist(&self) -> Result<(), Error> {
+    let Some(path) = history_file_path() else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      create_dir_all(dir)?;
+    }
+
+    let mut contents = String::new();
+    for kind in [HistoryKind::Search, HistoryKind::Save] {
+      for value in self.entries(kind) {
+        contents.push_str(kind.tag());
+        contents.push('\t');
+        contents.push_str(value);
+        contents.push('\n');
+      }
+    }
+    write(path, contents)
+  }
+
+  /// Adds a submitted value as the most recent entry
+  pub fn push(&mut self, kind: HistoryKind, value: &str) {
+    self.reset_browsing();
+    if value.is_empty() {
+      return;
+    }
+
+    let entries = self.entries_mut(kind);
+    entries.retain(|entry| entry != value);
+    entries.push(value.to_string());
+    if entries.len() > MAX_ENTRIES {
+      entries.drain(..entries.len().saturating_sub(MAX_ENTRIES));
+    }
+  }
+
+  pub fn last(&self, kind: HistoryKind) -> Option<&str> {
+    self.entries(kind).last().map(String::as_str)
+  }
+
+  /// Steps back to the previous (older) entry.
+  /// `current` is remembered when starting to browse, so that it can be restored later on.
+  pub fn previous(&mut self, kind: HistoryKind, current: &str) -> Option<&str> {
+    let idx = if let Some(idx) = self.browse_idx {
+      idx
+    } else {
+      self.draft = current.to_string();
+      self.entries(kind).len()
+    };
+    let idx = idx.checked_sub(1)?;
+    self.browse_idx = Some(idx);
+    self.entries(kind).get(idx).map(String::as_str)
+  }
+
+  /// Steps forward to the next (newer) entry, or back to what the user typed after the newest one
+  pub fn next(&mut self, kind: HistoryKind) -> Option<&str> {
+    let idx = self.browse_idx?.saturating_add(1);
+    if idx < self.entries(kind).len() {
+      self.browse_idx = Some(idx);
+      self.entries(kind).get(idx).map(String::as_str)
+    } else {
+      self.browse_idx = None;
+      Some(&self.draft)
+    }
+  }
+
+  pub fn reset_browsing(&mut self) {
+    self.browse_idx = None;
+    self.draft.clear();
+  }
+
+  const fn entries(&self, kind: HistoryKind) -> &Vec<String> {
+    match kind {
+      HistoryKind::Search => &self.search,
+      HistoryKind::Save => &self.save,
+    }
+  }
+
+  const fn entries_mut(&mut self, kind: HistoryKind) -> &mut Vec<String> {
+    match kind {
+      HistoryKind::Search => &mut self.search,
+      HistoryKind::Save => &mut self.save,
+    }
+  }
+}
+
+fn history_file_path() -> Option<PathBuf> {
+  Some(state_dir()?.join("history"))
+}
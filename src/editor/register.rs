@@ -0,0 +1,8 @@
+/// Internal clipboard register, holding the text of the last cut or copy
+#[derive(Default, Clone)]
+pub struct Register {
+  pub text: String,
+  /// True if a whole line was copied because nothing was selected.
+  /// Such text is pasted above the caret line instead of at the caret.
+  pub is_line: bool,
+}
@@ -0,0 +1,99 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// How letter case is treated when matching
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+  #[default]
+  Sensitive,
+  Insensitive,
+  /// Insensitive, unless the query contains an uppercase letter
+  Smart,
+}
+
+/// A single search option which can be toggled by the user
+#[derive(Clone, Copy)]
+pub enum SearchOption {
+  Regex,
+  CaseSensitivity,
+  WholeWord,
+}
+
+/// Options which determine how a search query is matched
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+  /// Interpret the query as a regular expression instead of a literal
+  pub regex: bool,
+  pub case_sensitivity: CaseSensitivity,
+  /// Only match if not surrounded by other word characters
+  pub whole_word: bool,
+}
+
+impl SearchOptions {
+  pub const fn toggle(&mut self, option: SearchOption) {
+    match option {
+      SearchOption::Regex => self.regex = !self.regex,
+      SearchOption::WholeWord => self.whole_word = !self.whole_word,
+      SearchOption::CaseSensitivity => {
+        self.case_sensitivity = match self.case_sensitivity {
+          CaseSensitivity::Sensitive => CaseSensitivity::Insensitive,
+          CaseSensitivity::Insensitive => CaseSensitivity::Smart,
+          CaseSensitivity::Smart => CaseSensitivity::Sensitive,
+        };
+      }
+    }
+  }
+
+  /// Determines if the given query should be matched ignoring case
+  pub fn ignores_case(self, query: &str) -> bool {
+    match self.case_sensitivity {
+      CaseSensitivity::Sensitive => false,
+      CaseSensitivity::Insensitive => true,
+      CaseSensitivity::Smart => !query.chars().any(char::is_uppercase),
+    }
+  }
+}
+
+impl Display for SearchOptions {
+  /// Lists all options which differ from the default, separated by commas
+  fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+    let case = match self.case_sensitivity {
+      CaseSensitivity::Sensitive => None,
+      CaseSensitivity::Insensitive => Some("ignore case"),
+      CaseSensitivity::Smart => Some("smart case"),
+    };
+    let active: Vec<&str> = [
+      self.regex.then_some("regex"),
+      case,
+      self.whole_word.then_some("whole word"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    write!(formatter, "{}", active.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn toggling_case_cycles_through_smart_case() {
+    let mut options = SearchOptions::default();
+    options.toggle(SearchOption::CaseSensitivity);
+    assert_eq!(options.case_sensitivity, CaseSensitivity::Insensitive);
+    options.toggle(SearchOption::CaseSensitivity);
+    assert_eq!(options.case_sensitivity, CaseSensitivity::Smart);
+    options.toggle(SearchOption::CaseSensitivity);
+    assert_eq!(options.case_sensitivity, CaseSensitivity::Sensitive);
+  }
+
+  #[test]
+  fn lists_the_options_in_use() {
+    let mut options = SearchOptions::default();
+    assert_eq!(options.to_string(), "");
+    options.toggle(SearchOption::WholeWord);
+    options.toggle(SearchOption::CaseSensitivity);
+    assert_eq!(options.to_string(), "ignore case, whole word");
+  }
+}
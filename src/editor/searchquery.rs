@@ -0,0 +1,208 @@
+use crate::{editor::searchoptions::SearchOptions, prelude::ByteIdx};
+
+use regex::{Error, Regex, RegexBuilder};
+
+use std::ops::Range;
+
+/// A compiled search query. Literal queries are escaped, so every query can be matched as a regex.
+#[derive(Clone)]
+pub struct SearchQuery {
+  text: String,
+  regex: Regex,
+  is_regex: bool,
+}
+
+impl SearchQuery {
+  pub fn new(text: &str, options: SearchOptions) -> Result<Self, Error> {
+    let pattern = if options.regex {
+      text.to_string()
+    } else {
+      regex::escape(text)
+    };
+    let pattern = if options.whole_word {
+      // Half boundaries also work for queries starting or ending with a non-word character
+      format!(r"\b{{start-half}}(?:{pattern})\b{{end-half}}")
+    } else {
+      pattern
+    };
+    let regex = RegexBuilder::new(&pattern)
+      .case_insensitive(options.ignores_case(text))
+      .build()?;
+
+    Ok(Self {
+      text: text.to_string(),
+      regex,
+      is_regex: options.regex,
+    })
+  }
+
+  pub const fn is_empty(&self) -> bool {
+    self.text.is_empty()
+  }
+
+  /// Whether the haystack contains a match anywhere, even one which doesn't align with grapheme boundaries
+  pub fn is_match(&self, haystack: &str) -> bool {
+    self.regex.is_match(haystack)
+  }
+
+  /// Returns the byte ranges of all non-overlapping, non-empty matches which lie entirely within `range`.
+  /// Everything outside of the range is still taken into account for anchors like `^` or `\b`.
+  pub fn find_all(&self, haystack: &str, range: Range<ByteIdx>) -> Vec<Range<ByteIdx>> {
+    let mut result = Vec::new();
+    let mut from = range.start;
+
+    while let Some(found) = self.regex.find_at(haystack, from) {
+      if found.end() > range.end {
+        break;
+      }
+
+      if found.is_empty() {
+        // Skip empty matches, but move on by a whole character to stay on a char boundary
+        let Some(char) = haystack
+          .get(found.start()..)
+          .and_then(|rest| rest.chars().next())
+        else {
+          break;
+        };
+        from = found.start().saturating_add(char.len_utf8());
+      } else {
+        from = found.end();
+        result.push(found.range());
+      }
+    }
+    result
+  }
+
+  /// Returns the text to replace the match starting at `at` with.
+  /// In regex mode, references to capture groups like `$1` or `${name}` are expanded.
+  pub fn expand_replacement(&self, haystack: &str, at: ByteIdx, replacement: &str) -> String {
+    if !self.is_regex {
+      return replacement.to_string();
+    }
+
+    self
+      .regex
+      .captures_at(haystack, at)
+      .filter(|captures| captures.get(0).is_some_and(|found| found.start() == at))
+      .map_or_else(
+        || replacement.to_string(),
+        |captures| {
+          let mut result = String::new();
+          captures.expand(replacement, &mut result);
+          result
+        },
+      )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::editor::searchoptions::CaseSensitivity;
+
+  fn query(text: &str, options: SearchOptions) -> SearchQuery {
+    SearchQuery::new(text, options).expect("query is valid")
+  }
+
+  fn regex() -> SearchOptions {
+    SearchOptions {
+      regex: true,
+      ..SearchOptions::default()
+    }
+  }
+
+  #[test]
+  fn literal_queries_match_special_characters() {
+    let query = query("a.b(", SearchOptions::default());
+    assert_eq!(query.find_all("axb( a.b(", 0..9), vec![5..9]);
+  }
+
+  #[test]
+  fn regex_matches_have_varying_lengths() {
+    let query = query(r"fn \w+\(", regex());
+    let haystack = "fn a() fn long_name()";
+    assert_eq!(
+      query.find_all(haystack, 0..haystack.len()),
+      vec![0..5, 7..20]
+    );
+  }
+
+  #[test]
+  fn empty_matches_are_skipped() {
+    let query = query("x*", regex());
+    assert_eq!(query.find_all("äxxä", 0..6), vec![2..4]);
+  }
+
+  #[test]
+  fn matches_lie_within_the_range_but_anchor_outside_of_it() {
+    let query = query(r"\bab", regex());
+    let haystack = "ab cab ab";
+    assert_eq!(query.find_all(haystack, 1..9), vec![7..9]);
+    assert_eq!(query.find_all(haystack, 0..8), vec![0..2]);
+  }
+
+  fn case(case_sensitivity: CaseSensitivity) -> SearchOptions {
+    SearchOptions {
+      case_sensitivity,
+      ..SearchOptions::default()
+    }
+  }
+
+  #[test]
+  fn matches_case_as_chosen() {
+    let haystack = "Foo foo";
+    let matches =
+      |text, case_sensitivity| query(text, case(case_sensitivity)).find_all(haystack, 0..7);
+    assert_eq!(matches("foo", CaseSensitivity::Sensitive), vec![4..7]);
+    assert_eq!(
+      matches("foo", CaseSensitivity::Insensitive),
+      vec![0..3, 4..7]
+    );
+    assert_eq!(
+      matches("FOO", CaseSensitivity::Insensitive),
+      vec![0..3, 4..7]
+    );
+  }
+
+  #[test]
+  fn smart_case_is_sensitive_only_with_uppercase_letters() {
+    let haystack = "Foo foo Ärger ärger";
+    let matches =
+      |text| query(text, case(CaseSensitivity::Smart)).find_all(haystack, 0..haystack.len());
+    assert_eq!(matches("foo"), vec![0..3, 4..7]);
+    assert_eq!(matches("Foo"), vec![0..3]);
+    assert_eq!(matches("ärger"), vec![8..14, 15..21]);
+    assert_eq!(matches("Ärger"), vec![8..14]);
+  }
+
+  #[test]
+  fn whole_words_are_not_surrounded_by_word_characters() {
+    let options = SearchOptions {
+      whole_word: true,
+      ..SearchOptions::default()
+    };
+    let haystack = "foo foobar (foo) .foo.";
+    let matches = |text| query(text, options).find_all(haystack, 0..haystack.len());
+    assert_eq!(matches("foo"), vec![0..3, 12..15, 18..21]);
+    assert_eq!(matches(".foo"), vec![17..21]);
+    assert_eq!(matches("(foo)"), vec![11..16]);
+  }
+
+  #[test]
+  fn whole_words_apply_to_the_whole_regex() {
+    let options = SearchOptions {
+      regex: true,
+      whole_word: true,
+      ..SearchOptions::default()
+    };
+    let query = query("a|bc", options);
+    assert_eq!(query.find_all("abc bc a", 0..8), vec![4..6, 7..8]);
+  }
+
+  #[test]
+  fn invalid_patterns_are_errors() {
+    assert!(SearchQuery::new("(", regex()).is_err());
+    assert!(SearchQuery::new("(", SearchOptions::default()).is_ok());
+  }
+}
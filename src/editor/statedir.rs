@@ -0,0 +1,13 @@
+use crate::prelude::NAME;
+
+use std::{env, path::PathBuf};
+
+/// Directory for data which should survive restarts, but is not worth backing up:
+/// `$XDG_STATE_HOME/hecto`, falling back to `~/.local/state/hecto`
+pub fn state_dir() -> Option<PathBuf> {
+  let state_home = env::var_os("XDG_STATE_HOME")
+    .map(PathBuf::from)
+    .filter(|path| path.is_absolute())
+    .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+  Some(state_home.join(NAME))
+}
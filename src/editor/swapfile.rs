@@ -0,0 +1,413 @@
+use crate::{editor::statedir::state_dir, prelude::NAME};
+
+use std::{
+  cell::RefCell,
+  fs::{OpenOptions, create_dir_all, read_dir, read_to_string, remove_file, rename},
+  io::{Error, Write},
+  path::{Path, PathBuf},
+  process,
+  rc::{Rc, Weak},
+  time::{Duration, Instant},
+};
+
+/// Unchanged lines shown before the lines which differ from the swap file
+const DIFFERENCE_CONTEXT: usize = 2;
+
+/// Minimum time between two writes of the swap file
+pub const SWAP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// First line of every swap file, followed by the id of the process which wrote it
+const HEADER: &str = "hecto swap file, pid ";
+
+thread_local! {
+  /// Swap files to bring up to date when the editor panics.
+  /// Buffers are only edited on the main thread, which is also the one running the panic hook then.
+  static WATCHED: RefCell<Vec<Weak<RefCell<SwapFile>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The buffer whose unsaved text a swap file keeps a copy of
+pub trait SwapSource {
+  /// Revision and text of the buffer, `None` if there are no unsaved changes
+  fn unsaved_text(&self) -> Option<(usize, String)>;
+}
+
+/// What was found at the location of a swap file
+pub enum SwapState {
+  Missing,
+  /// Left behind by a session which ended without saving, contains the unsaved text
+  Stale(String),
+  /// Belongs to a session which is still running, identified by its process id
+  InUse(u32),
+}
+
+/// Copy of the unsaved text of a buffer, so that it can be recovered after a crash
+pub struct SwapFile {
+  /// The file the swap belongs to, `None` for a buffer which was never saved
+  file: Option<PathBuf>,
+  path: PathBuf,
+  /// Revision of the buffer that was last written, `None` if the swap file doesn't exist
+  written_revision: Option<usize>,
+  last_write: Instant,
+  /// Set if another session owns the swap file, which then must not be touched
+  disabled: bool,
+  /// Where the text comes from when the panic hook writes the swap file
+  source: Weak<RefCell<dyn SwapSource>>,
+}
+
+impl SwapFile {
+  /// Swap for the given file, which lives next to it as `.name.hecto.swp`, so as not to be mistaken for another editor's.
+  /// Buffers without a file get a swap in the state directory instead.
+  pub fn new(file: Option<&Path>, source: Weak<RefCell<dyn SwapSource>>) -> Option<Self> {
+    let path = match file {
+      Some(file) => {
+        let name = file.file_name()?.to_string_lossy();
+        file.with_file_name(format!(".{name}.{NAME}.swp"))
+      }
+      None => state_dir()?
+        .join("swap")
+        .join(format!("unnamed-{}.swp", process::id())),
+    };
+    Some(Self {
+      file: file.map(Path::to_path_buf),
+      path,
+      written_revision: None,
+      last_write: Instant::now(),
+      disabled: false,
+      source,
+    })
+  }
+
+  pub fn belongs_to(&self, file: Option<&Path>) -> bool {
+    self.file.as_deref() == file
+  }
+
+  /// Looks for a swap file which was not written by this session.
+  /// A buffer without a file takes over the swap of an unnamed buffer of a session which is no longer running, if there is one.
+  /// A file without the header of a hecto swap file is ignored, and replaced once the swap is written.
+  pub fn inspect(&mut self) -> SwapState {
+    if self.file.is_none()
+      && let Some(path) = self.path.parent().and_then(find_left_behind)
+    {
+      self.path = path;
+    }
+    let Some((pid, text)) = read_swap(&self.path) else {
+      return SwapState::Missing;
+    };
+    if pid != process::id() && is_process_running(pid) {
+      SwapState::InUse(pid)
+    } else {
+      SwapState::Stale(text)
+    }
+  }
+
+  /// Stops this session from writing or removing the swap file
+  pub const fn disable(&mut self) {
+    self.disabled = true;
+  }
+
+  /// Whether the swap file lags behind the given buffer revision
+  pub fn is_outdated(&self, revision: usize) -> bool {
+    self.written_revision != Some(revision)
+  }
+
+  /// Whether the swap file is outdated, and enough time has passed since it was last written
+  pub fn is_due(&self, revision: usize) -> bool {
+    self.is_outdated(revision) && self.last_write.elapsed() >= SWAP_INTERVAL
+  }
+
+  pub fn write(&mut self, text: &str, revision: usize) -> Result<(), Error> {
+    if self.disabled {
+      return Ok(());
+    }
+    if let Some(dir) = self.path.parent() {
+      create_dir_all(dir)?;
+    }
+    self.last_write = Instant::now();
+    // Written in full before it replaces the previous swap, which a crash in between then keeps
+    let temp_path = self.path.with_extension("swp.tmp");
+    let _ = remove_file(&temp_path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    // Only the owner may read the unsaved text
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::OpenOptionsExt;
+      options.mode(0o600);
+    }
+    let result = options
+      .open(&temp_path)
+      .and_then(|mut file| file.write_all(format!("{HEADER}{}\n{text}", process::id()).as_bytes()))
+      .and_then(|()| rename(&temp_path, &self.path));
+    if result.is_err() {
+      let _ = remove_file(&temp_path);
+      return result;
+    }
+    self.written_revision = Some(revision);
+    Ok(())
+  }
+
+  /// Writes the unsaved text of the source, unless the swap file holds it already.
+  /// A source which is borrowed at the moment is skipped.
+  fn flush(&mut self) {
+    let Some(source) = self.source.upgrade() else {
+      return;
+    };
+    let Ok(source) = source.try_borrow() else {
+      return;
+    };
+    if let Some((revision, text)) = source.unsaved_text()
+      && self.is_outdated(revision)
+    {
+      let _ = self.write(&text, revision);
+    }
+  }
+
+  /// Removes the swap file, once its text is no longer needed
+  pub fn remove(&mut self) {
+    if self.disabled {
+      return;
+    }
+    let _ = remove_file(&self.path);
+    self.written_revision = None;
+  }
+}
+
+/// Shares the swap file with the panic hook, which writes it one last time if the editor panics
+pub fn watch(swap_file: SwapFile) -> Rc<RefCell<SwapFile>> {
+  let swap_file = Rc::new(RefCell::new(swap_file));
+  WATCHED.with_borrow_mut(|watched| {
+    watched.retain(|watched| watched.strong_count() > 0);
+    watched.push(Rc::downgrade(&swap_file));
+  });
+  swap_file
+}
+
+/// Writes the unsaved text of every buffer to its swap file, meant to be called from the panic hook.
+/// Unlike unwinding to the event loop, this also works when panics abort.
+/// A buffer in the middle of an edit when the panic hit is skipped, its swap file keeps the last regular write.
+/// Returns the paths of the swap files this session left with unsaved text.
+pub fn flush_watched() -> Vec<PathBuf> {
+  WATCHED
+    .try_with(|watched| {
+      let Ok(watched) = watched.try_borrow() else {
+        return Vec::new();
+      };
+      watched
+        .iter()
+        .filter_map(Weak::upgrade)
+        .filter_map(|shared| {
+          let mut swap_file = shared.try_borrow_mut().ok()?;
+          swap_file.flush();
+          (!swap_file.disabled && swap_file.written_revision.is_some())
+            .then(|| swap_file.path.clone())
+        })
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Id of the process which wrote the swap file, and the text it contains. `None` if it is no hecto swap file.
+fn read_swap(path: &Path) -> Option<(u32, String)> {
+  let contents = read_to_string(path).ok()?;
+  let (header, text) = contents.split_once('\n')?;
+  let pid = header.strip_prefix(HEADER)?.parse().ok()?;
+  Some((pid, text.to_string()))
+}
+
+/// The most recent swap of an unnamed buffer in the directory whose session is no longer running
+fn find_left_behind(dir: &Path) -> Option<PathBuf> {
+  read_dir(dir)
+    .ok()?
+    .filter_map(Result::ok)
+    .filter(|entry| {
+      let name = entry.file_name();
+      let name = name.to_string_lossy();
+      name.starts_with("unnamed-") && name.ends_with(".swp")
+    })
+    .filter(|entry| {
+      read_swap(&entry.path())
+        .is_some_and(|(pid, _)| pid != process::id() && !is_process_running(pid))
+    })
+    .max_by_key(|entry| {
+      entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+    })
+    .map(|entry| entry.path())
+}
+
+/// Summarizes which lines of the swap differ from the text of the buffer
+pub fn describe_difference(text: &str, swap: &str) -> String {
+  let lines: Vec<&str> = text.split('\n').collect();
+  let swap_lines: Vec<&str> = swap.split('\n').collect();
+  let (common_start, common_end) = common_lines(&lines, &swap_lines);
+
+  if common_start == lines.len() && common_start == swap_lines.len() {
+    return "The swap file contains the same text as the file.".to_string();
+  }
+  let changed = lines
+    .len()
+    .saturating_sub(common_start)
+    .saturating_sub(common_end);
+  let swap_changed = swap_lines
+    .len()
+    .saturating_sub(common_start)
+    .saturating_sub(common_end);
+  format!(
+    "From line {}, {changed} line(s) of the file differ from {swap_changed} line(s) in the swap file.",
+    common_start.saturating_add(1)
+  )
+}
+
+/// The lines of the buffer which differ from the swap, prefixed with `-`, followed by those of the swap, prefixed with `+`.
+/// A few unchanged lines before them show where they are.
+pub fn difference_lines(text: &str, swap: &str) -> Vec<String> {
+  let lines: Vec<&str> = text.split('\n').collect();
+  let swap_lines: Vec<&str> = swap.split('\n').collect();
+  let (common_start, common_end) = common_lines(&lines, &swap_lines);
+
+  let prefixed = |lines: &[&str], start: usize, end: usize, prefix: &str| -> Vec<String> {
+    lines
+      .get(start..end)
+      .unwrap_or_default()
+      .iter()
+      .map(|line| format!("{prefix}{line}"))
+      .collect()
+  };
+  let mut result = prefixed(
+    &lines,
+    common_start.saturating_sub(DIFFERENCE_CONTEXT),
+    common_start,
+    "  ",
+  );
+  result.extend(prefixed(
+    &lines,
+    common_start,
+    lines.len().saturating_sub(common_end),
+    "- ",
+  ));
+  result.extend(prefixed(
+    &swap_lines,
+    common_start,
+    swap_lines.len().saturating_sub(common_end),
+    "+ ",
+  ));
+  result
+}
+
+/// How many lines both texts start with, and how many of the remaining lines they end with
+fn common_lines(lines: &[&str], swap_lines: &[&str]) -> (usize, usize) {
+  let common_start = lines
+    .iter()
+    .zip(swap_lines)
+    .take_while(|(line, swap_line)| line == swap_line)
+    .count();
+  let max_common_end = lines
+    .len()
+    .min(swap_lines.len())
+    .saturating_sub(common_start);
+  let common_end = lines
+    .iter()
+    .rev()
+    .zip(swap_lines.iter().rev())
+    .take(max_common_end)
+    .take_while(|(line, swap_line)| line == swap_line)
+    .count();
+  (common_start, common_end)
+}
+
+#[cfg(target_os = "linux")]
+fn is_process_running(pid: u32) -> bool {
+  Path::new(&format!("/proc/{pid}")).exists()
+}
+
+/// Without a cheap way to check, every swap file of another process is treated as left behind
+#[cfg(not(target_os = "linux"))]
+const fn is_process_running(_pid: u32) -> bool {
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::{
+    env,
+    fs::{remove_dir_all, write},
+  };
+
+  struct NoSource;
+
+  impl SwapSource for NoSource {
+    fn unsaved_text(&self) -> Option<(usize, String)> {
+      None
+    }
+  }
+
+  fn swap_file(file: &Path) -> SwapFile {
+    let source: Weak<RefCell<dyn SwapSource>> = Weak::<RefCell<NoSource>>::new();
+    SwapFile::new(Some(file), source).expect("File has a name")
+  }
+
+  /// An empty directory of its own for each test
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("hecto-swapfile-{}-{name}", process::id()));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).expect("Could not create the test directory");
+    dir
+  }
+
+  #[test]
+  fn lives_next_to_the_file() {
+    let swap_file = swap_file(Path::new("/some/dir/notes.txt"));
+    assert_eq!(swap_file.path, Path::new("/some/dir/.notes.txt.hecto.swp"));
+  }
+
+  #[test]
+  fn written_swap_is_stale_for_the_next_session() {
+    let dir = test_dir("stale");
+    let mut swap_file = swap_file(&dir.join("notes.txt"));
+    swap_file
+      .write("unsaved\ntext", 1)
+      .expect("Could not write");
+
+    let Some((pid, text)) = read_swap(&swap_file.path) else {
+      panic!("Swap file has a header");
+    };
+    assert_eq!(pid, process::id());
+    assert_eq!(text, "unsaved\ntext");
+    #[cfg(unix)]
+    {
+      use std::{fs::metadata, os::unix::fs::MetadataExt};
+      let mode = metadata(&swap_file.path).expect("Swap file exists").mode();
+      assert_eq!(mode & 0o777, 0o600);
+    }
+    assert!(matches!(swap_file.inspect(), SwapState::Stale(text) if text == "unsaved\ntext"));
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn ignores_files_of_other_programs() {
+    let dir = test_dir("foreign");
+    let mut swap_file = swap_file(&dir.join("notes.txt"));
+    write(&swap_file.path, "b0VIM 9.0\nbinary").expect("Could not write");
+    assert!(matches!(swap_file.inspect(), SwapState::Missing));
+    let _ = remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn finds_unnamed_swaps_of_ended_sessions() {
+    let dir = test_dir("unnamed");
+    // No process has the largest id
+    let ended = dir.join("unnamed-1.swp");
+    write(&ended, format!("{HEADER}{}\ntext", u32::MAX)).expect("Could not write");
+    write(
+      dir.join("unnamed-2.swp"),
+      format!("{HEADER}{}\ntext", process::id()),
+    )
+    .expect("Could not write");
+    assert_eq!(find_left_behind(&dir), Some(ended));
+    let _ = remove_dir_all(&dir);
+  }
+}
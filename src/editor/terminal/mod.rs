@@ -1,13 +1,18 @@
 mod attribute;
+mod theme;
+
+pub use attribute::Attribute;
+pub use theme::Theme;
 
 use crate::{
-  editor::{annotatedstring::AnnotatedString, terminal::attribute::Attribute},
+  editor::annotatedstring::AnnotatedString,
   prelude::{Position, RowIdx, Size},
 };
 
 use crossterm::{
   Command,
   cursor::{Hide, MoveTo, Show},
+  event::{DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange},
   queue,
   style::{
     Attribute::{Reset, Reverse},
@@ -19,6 +24,9 @@ use crossterm::{
   },
 };
 
+#[cfg(feature = "osc52")]
+use crossterm::clipboard::CopyToClipboard;
+
 use std::io::{Error, Write, stdout};
 
 /// Represents the Terminal.
@@ -38,6 +46,8 @@ impl Terminal {
   pub fn initialize() -> Result<(), Error> {
     enable_raw_mode()?;
     Self::enter_alternate_screen()?;
+    Self::enable_bracketed_paste()?;
+    Self::enable_focus_change()?;
     Self::disable_line_wrap()?;
     Self::clear_screen()?;
     Self::execute()?;
@@ -45,6 +55,8 @@ impl Terminal {
   }
 
   pub fn terminate() -> Result<(), Error> {
+    Self::disable_bracketed_paste()?;
+    Self::disable_focus_change()?;
     Self::leave_alternate_screen()?;
     Self::enable_line_wrap()?;
     Self::show_caret()?;
@@ -68,6 +80,28 @@ impl Terminal {
     Ok(())
   }
 
+  /// Makes the terminal deliver pasted text as a single event instead of individual key presses
+  pub fn enable_bracketed_paste() -> Result<(), Error> {
+    Self::queue_command(EnableBracketedPaste)?;
+    Ok(())
+  }
+
+  pub fn disable_bracketed_paste() -> Result<(), Error> {
+    Self::queue_command(DisableBracketedPaste)?;
+    Ok(())
+  }
+
+  /// Makes the terminal report when it gains or loses focus
+  pub fn enable_focus_change() -> Result<(), Error> {
+    Self::queue_command(EnableFocusChange)?;
+    Ok(())
+  }
+
+  pub fn disable_focus_change() -> Result<(), Error> {
+    Self::queue_command(DisableFocusChange)?;
+    Ok(())
+  }
+
   pub fn clear_screen() -> Result<(), Error> {
     Self::queue_command(Clear(ClearType::All))?;
     Ok(())
@@ -111,6 +145,14 @@ impl Terminal {
     Ok(())
   }
 
+  /// Copies the text to the system clipboard using OSC 52, which also works over SSH.
+  /// Terminals without support for it simply ignore the sequence.
+  #[cfg(feature = "osc52")]
+  pub fn copy_to_clipboard(text: &str) -> Result<(), Error> {
+    Self::queue_command(CopyToClipboard::to_clipboard_from(text))?;
+    Ok(())
+  }
+
   /// Returns the current size of this Terminal.
   /// Edge Case for systems with `usize` < `u16`:
   /// * A `Size` representing the terminal size. Any coordinate `z` truncated to `usize` if `usize` < `z` < `u16`
@@ -137,13 +179,29 @@ impl Terminal {
     Ok(())
   }
 
-  pub fn print_inverted_row(row: RowIdx, line_text: &str) -> Result<(), Error> {
-    let width = Self::size()?.width;
-    Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}"))
+  /// Prints the text into `width` columns of a row, starting at the origin.
+  /// Unlike `print_row`, the rest of the row is left alone, e.g. a window next to this one.
+  pub fn print_at(origin: Position, width: usize, line_text: &str) -> Result<(), Error> {
+    Self::clear_columns(origin, width)?;
+    Self::print(line_text)?;
+    Ok(())
+  }
+
+  /// Clears `width` columns of a row, starting at the origin, and moves the caret back there
+  fn clear_columns(origin: Position, width: usize) -> Result<(), Error> {
+    Self::move_caret_to(origin)?;
+    Self::print(&" ".repeat(width))?;
+    Self::move_caret_to(origin)?;
+    Ok(())
+  }
+
+  pub fn print_inverted_row(origin: Position, width: usize, line_text: &str) -> Result<(), Error> {
+    Self::move_caret_to(origin)?;
+    Self::print(&format!("{Reverse}{line_text:width$.width$}{Reset}"))
   }
 
   /// Applies attribute content to the terminal
-  fn set_attribute(attribute: &Attribute) -> Result<(), Error> {
+  fn set_attribute(attribute: Attribute) -> Result<(), Error> {
     if let Some(foreground_color) = attribute.foreground {
       Self::queue_command(SetForegroundColor(foreground_color))?;
     }
@@ -159,18 +217,21 @@ impl Terminal {
     Ok(())
   }
 
-  /// Takes annotated string and prints it into a row
-  pub fn print_annotated_row(row: RowIdx, annotated_string: &AnnotatedString) -> Result<(), Error> {
-    Self::move_caret_to(Position { col: 0, row })?;
-
-    Self::clear_line()?;
+  /// Takes annotated string and prints it into `width` columns of a row, starting at the origin,
+  /// in the colours of the theme
+  pub fn print_annotated_row(
+    origin: Position,
+    width: usize,
+    annotated_string: &AnnotatedString,
+    theme: &Theme,
+  ) -> Result<(), Error> {
+    Self::clear_columns(origin, width)?;
 
     annotated_string
       .into_iter()
       .try_for_each(|part| -> Result<(), Error> {
         if let Some(annotation_type) = part.annotation_type {
-          let attribute: Attribute = annotation_type.into();
-          Self::set_attribute(&attribute)?;
+          Self::set_attribute(theme.attribute(annotation_type))?;
         }
         Self::print(part.string)?;
         Self::reset_color()?;
@@ -0,0 +1,96 @@
+use crossterm::style::Color;
+
+use crate::editor::{annotationtype::AnnotationType, terminal::attribute::Attribute};
+
+/// Colours of the annotated parts of the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+  matches: Attribute,
+  selected_match: Attribute,
+  selection: Attribute,
+  line_number: Attribute,
+  number: Attribute,
+  keyword: Attribute,
+  types: Attribute,
+  known_value: Attribute,
+  char: Attribute,
+  lifetime_specifier: Attribute,
+  comment: Attribute,
+  string: Attribute,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+  Color::Rgb { r, g, b }
+}
+
+const fn foreground(r: u8, g: u8, b: u8) -> Attribute {
+  Attribute {
+    foreground: Some(rgb(r, g, b)),
+    background: None,
+  }
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self {
+      matches: Attribute {
+        foreground: Some(rgb(255, 255, 255)),
+        background: Some(rgb(211, 211, 211)),
+      },
+      selected_match: Attribute {
+        foreground: Some(rgb(255, 255, 255)),
+        background: Some(rgb(255, 255, 153)),
+      },
+      selection: Attribute {
+        foreground: None,
+        background: Some(rgb(68, 90, 160)),
+      },
+      line_number: foreground(128, 128, 128),
+      number: foreground(255, 99, 71),
+      keyword: foreground(100, 149, 237),
+      types: foreground(175, 225, 175),
+      known_value: foreground(195, 177, 225),
+      char: foreground(255, 191, 0),
+      lifetime_specifier: foreground(102, 205, 170),
+      comment: foreground(34, 139, 34),
+      string: foreground(255, 179, 102),
+    }
+  }
+}
+
+impl Theme {
+  pub const fn attribute(&self, annotation_type: AnnotationType) -> Attribute {
+    match annotation_type {
+      AnnotationType::Match => self.matches,
+      AnnotationType::SelectedMatch => self.selected_match,
+      AnnotationType::Selection => self.selection,
+      AnnotationType::LineNumber => self.line_number,
+      AnnotationType::Number => self.number,
+      AnnotationType::Keyword => self.keyword,
+      AnnotationType::Type => self.types,
+      AnnotationType::KnownValue => self.known_value,
+      AnnotationType::Char => self.char,
+      AnnotationType::LifetimeSpecifier => self.lifetime_specifier,
+      AnnotationType::Comment => self.comment,
+      AnnotationType::String => self.string,
+    }
+  }
+
+  pub const fn set_attribute(&mut self, annotation_type: AnnotationType, attribute: Attribute) {
+    let entry = match annotation_type {
+      AnnotationType::Match => &mut self.matches,
+      AnnotationType::SelectedMatch => &mut self.selected_match,
+      AnnotationType::Selection => &mut self.selection,
+      AnnotationType::LineNumber => &mut self.line_number,
+      AnnotationType::Number => &mut self.number,
+      AnnotationType::Keyword => &mut self.keyword,
+      AnnotationType::Type => &mut self.types,
+      AnnotationType::KnownValue => &mut self.known_value,
+      AnnotationType::Char => &mut self.char,
+      AnnotationType::LifetimeSpecifier => &mut self.lifetime_specifier,
+      AnnotationType::Comment => &mut self.comment,
+      AnnotationType::String => &mut self.string,
+    };
+    *entry = attribute;
+  }
+}
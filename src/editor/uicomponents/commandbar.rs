@@ -1,49 +1,157 @@
 use crate::{
-  editor::{command::Edit, line::Line, terminal::Terminal, uicomponents::UIComponent},
-  prelude::{ColIdx, RowIdx, Size},
+  editor::{
+    annotatedstring::AnnotatedString,
+    annotationtype::AnnotationType,
+    command::{Edit, Move},
+    line::Line,
+    terminal::{Terminal, Theme},
+    uicomponents::UIComponent,
+  },
+  prelude::{ColIdx, GraphemeIdx, Position, Size},
 };
 
 use std::{cmp::min, io::Error};
 
 #[derive(Default)]
 pub struct CommandBar {
-  prompt: String,
+  prompt: Line,
   value: Line,
+  /// Position of the caret within the value
+  caret: GraphemeIdx,
+  /// First column of the value that is visible
+  scroll_offset: ColIdx,
+  /// Whether the value was filled in for the user, in which case typing replaces it
+  prefilled: bool,
+  /// Colours of the prefilled value
+  theme: Theme,
   needs_redraw: bool,
   size: Size,
 }
 
 impl CommandBar {
   pub fn handle_edit_command(&mut self, command: Edit) {
+    if self.prefilled {
+      self.prefilled = false;
+      match command {
+        Edit::Insert(_) | Edit::InsertText(_) => self.clear_value(),
+        Edit::Delete | Edit::DeleteBackward | Edit::DeleteWordBackward => {
+          self.clear_value();
+          return;
+        }
+        _ => {}
+      }
+    }
+
+    match command {
+      Edit::Insert(character) => {
+        self.value.insert_char(character, self.caret);
+        self.caret = self.caret.saturating_add(1);
+      }
+      // The command bar is a single line, so only the first line of the text is used
+      Edit::InsertText(text) => {
+        let first_line = text.lines().next().unwrap_or_default();
+        let old_count = self.value.grapheme_count();
+        self.value.insert_str(first_line, self.caret);
+        let inserted = self.value.grapheme_count().saturating_sub(old_count);
+        self.caret = self.caret.saturating_add(inserted);
+      }
+      Edit::Delete => self.value.delete(self.caret),
+      Edit::DeleteBackward => {
+        if self.caret > 0 {
+          self.caret = self.caret.saturating_sub(1);
+          self.value.delete(self.caret);
+        }
+      }
+      Edit::DeleteWordBackward => {
+        let start = self.value.previous_word_start(self.caret);
+        self.value.delete_range(start..self.caret);
+        self.caret = start;
+      }
+      Edit::InsertNewline | Edit::Undo | Edit::Redo | Edit::Cut | Edit::Copy | Edit::Paste => {}
+    }
+    self.scroll_caret_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  pub fn handle_move_command(&mut self, command: Move) {
     match command {
-      Edit::Insert(character) => self.value.append_char(character),
-      Edit::Delete | Edit::InsertNewline => {}
-      Edit::DeleteBackward => self.value.delete_last(),
+      Move::Left => self.caret = self.caret.saturating_sub(1),
+      Move::Right => self.caret = min(self.caret.saturating_add(1), self.value.grapheme_count()),
+      Move::StartOfLine => self.caret = 0,
+      Move::EndOfLine => self.caret = self.value.grapheme_count(),
+      // There is only one line
+      Move::Up | Move::Down | Move::PageUp | Move::PageDown => {}
     }
+    self.prefilled = false;
+    self.scroll_caret_into_view();
     self.set_needs_redraw(true);
   }
 
   pub fn caret_position_col(&self) -> ColIdx {
-    let max_width = self
+    let caret_col = self
       .prompt
-      .len()
-      .saturating_add(self.value.grapheme_count());
-    min(max_width, self.size.width)
+      .width()
+      .saturating_add(self.value.width_until(self.caret))
+      .saturating_sub(self.scroll_offset);
+    min(caret_col, self.size.width)
   }
 
   pub fn value(&self) -> String {
     self.value.to_string()
   }
 
+  pub fn set_theme(&mut self, theme: Theme) {
+    if self.theme != theme {
+      self.theme = theme;
+      self.set_needs_redraw(true);
+    }
+  }
+
   pub fn set_prompt(&mut self, prompt: &str) {
-    self.prompt = prompt.to_string();
+    self.prompt = Line::from(prompt);
+    self.scroll_caret_into_view();
     self.set_needs_redraw(true);
   }
 
-  pub fn clear_value(&mut self) {
-    self.value = Line::default();
+  /// Replaces the value and puts the caret at its end
+  pub fn set_value(&mut self, value: &str) {
+    self.value = Line::from(value);
+    self.caret = self.value.grapheme_count();
+    self.prefilled = false;
+    self.scroll_caret_into_view();
     self.set_needs_redraw(true);
   }
+
+  /// Sets a value the user can accept as is or overwrite by typing
+  pub fn prefill(&mut self, value: &str) {
+    self.set_value(value);
+    self.prefilled = !value.is_empty();
+  }
+
+  pub fn clear_value(&mut self) {
+    self.set_value("");
+  }
+
+  /// Space between the right side of the prompt and the edge of the bar
+  fn area_for_value(&self) -> ColIdx {
+    self.size.width.saturating_sub(self.prompt.width())
+  }
+
+  /// Adjusts the scroll offset so that the caret is visible,
+  /// and so that as much of the value is shown as fits into the bar
+  fn scroll_caret_into_view(&mut self) {
+    let area = self.area_for_value();
+    let caret_col = self.value.width_until(self.caret);
+    // The caret can sit right after the value, so it needs one more column
+    let max_offset = self.value.width().saturating_add(1).saturating_sub(area);
+
+    self.scroll_offset = min(self.scroll_offset, max_offset);
+    if caret_col < self.scroll_offset {
+      self.scroll_offset = caret_col;
+    } else if caret_col >= self.scroll_offset.saturating_add(area) {
+      self.scroll_offset = caret_col.saturating_add(1).saturating_sub(area);
+    }
+  }
 }
 
 impl UIComponent for CommandBar {
@@ -57,28 +165,120 @@ impl UIComponent for CommandBar {
 
   fn set_size(&mut self, size: Size) {
     self.size = size;
+    self.scroll_caret_into_view();
   }
 
-  fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
-    // This is how much space there is between the right side of the prompt and the edge of the bar
-    let area_for_value = self.size.width.saturating_sub(self.prompt.len());
-    // We always want to show the left part of the value
-    // therefore the end of the visible range we try to access will be equal to the full width
-    let value_end = self.value.width();
-    // This should give us the start for the grapheme subrange we want to print out.
-    let value_start = value_end.saturating_sub(area_for_value);
-    let message = format!(
-      "{}{}",
-      self.prompt,
-      self.value.get_visible_graphemes(value_start..value_end)
-    );
-
-    let to_print = if message.len() <= self.size.width {
-      message
-    } else {
-      String::new()
-    };
-
-    Terminal::print_row(origin_row, &to_print)
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    // A prompt wider than the bar is cut off, leaving no room for the value
+    let prompt = self.prompt.get_visible_graphemes(0..self.size.width);
+    let value_end = self.scroll_offset.saturating_add(self.area_for_value());
+    let value = self
+      .value
+      .get_visible_graphemes(self.scroll_offset..value_end);
+
+    let mut to_print = AnnotatedString::from(&format!("{prompt}{value}"));
+    if self.prefilled {
+      to_print.add_annotation(
+        AnnotationType::Selection,
+        prompt.len(),
+        prompt.len().saturating_add(value.len()),
+      );
+    }
+
+    Terminal::print_annotated_row(origin, self.size.width, &to_print, &self.theme)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bar(width: ColIdx) -> CommandBar {
+    let mut bar = CommandBar::default();
+    bar.resize(Size { height: 1, width });
+    bar
+  }
+
+  fn type_text(bar: &mut CommandBar, text: &str) {
+    for character in text.chars() {
+      bar.handle_edit_command(Edit::Insert(character));
+    }
+  }
+
+  #[test]
+  fn edits_at_the_caret() {
+    let mut bar = bar(20);
+    type_text(&mut bar, "helo");
+    bar.handle_move_command(Move::Left);
+    type_text(&mut bar, "l");
+    assert_eq!(bar.value(), "hello");
+    bar.handle_move_command(Move::StartOfLine);
+    bar.handle_edit_command(Edit::Delete);
+    bar.handle_edit_command(Edit::DeleteBackward);
+    assert_eq!(bar.value(), "ello");
+    bar.handle_move_command(Move::EndOfLine);
+    bar.handle_move_command(Move::Right);
+    bar.handle_edit_command(Edit::DeleteBackward);
+    assert_eq!(bar.value(), "ell");
+  }
+
+  #[test]
+  fn deletes_the_word_before_the_caret() {
+    let mut bar = bar(20);
+    type_text(&mut bar, "one two  three");
+    bar.handle_move_command(Move::Left);
+    bar.handle_move_command(Move::Left);
+    bar.handle_edit_command(Edit::DeleteWordBackward);
+    assert_eq!(bar.value(), "one two  ee");
+    bar.handle_edit_command(Edit::DeleteWordBackward);
+    assert_eq!(bar.value(), "one ee");
+  }
+
+  #[test]
+  fn caret_column_counts_wide_characters() {
+    let mut bar = bar(20);
+    bar.set_prompt("Süche: ");
+    type_text(&mut bar, "日本語");
+    assert_eq!(bar.caret_position_col(), 13);
+    bar.handle_move_command(Move::Left);
+    assert_eq!(bar.caret_position_col(), 11);
+  }
+
+  #[test]
+  fn scrolls_to_keep_the_caret_visible() {
+    let mut bar = bar(10);
+    bar.set_prompt("> ");
+    type_text(&mut bar, "0123456789");
+    assert_eq!(bar.scroll_offset, 3);
+    assert_eq!(bar.caret_position_col(), 9);
+    bar.handle_move_command(Move::StartOfLine);
+    assert_eq!(bar.scroll_offset, 0);
+    assert_eq!(bar.caret_position_col(), 2);
+    bar.handle_move_command(Move::EndOfLine);
+    bar.handle_edit_command(Edit::DeleteWordBackward);
+    assert_eq!(bar.scroll_offset, 0);
+  }
+
+  #[test]
+  fn pasting_takes_the_first_line() {
+    let mut bar = bar(20);
+    type_text(&mut bar, "ab");
+    bar.handle_move_command(Move::Left);
+    bar.handle_edit_command(Edit::InsertText("xy\nz".to_string()));
+    assert_eq!(bar.value(), "axyb");
+    assert_eq!(bar.caret, 3);
+  }
+
+  #[test]
+  fn typing_replaces_a_prefilled_value() {
+    let mut bar = bar(20);
+    bar.prefill("old");
+    type_text(&mut bar, "n");
+    assert_eq!(bar.value(), "n");
+
+    bar.prefill("old");
+    bar.handle_move_command(Move::Left);
+    type_text(&mut bar, "n");
+    assert_eq!(bar.value(), "olnd");
   }
 }
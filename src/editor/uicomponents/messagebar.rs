@@ -1,6 +1,6 @@
 use crate::{
   editor::{terminal::Terminal, uicomponents::UIComponent},
-  prelude::{RowIdx, Size},
+  prelude::{Position, Size},
 };
 
 use std::{
@@ -8,7 +8,7 @@ use std::{
   time::{Duration, Instant},
 };
 
-const DEFAULT_DURATION: Duration = Duration::new(5, 0);
+pub const DEFAULT_MESSAGE_DURATION: Duration = Duration::new(5, 0);
 
 struct Message {
   text: String,
@@ -25,21 +25,37 @@ impl Default for Message {
 }
 
 impl Message {
-  fn is_expired(&self) -> bool {
-    Instant::now().duration_since(self.time) > DEFAULT_DURATION
+  fn is_expired(&self, duration: Duration) -> bool {
+    Instant::now().duration_since(self.time) > duration
   }
 }
 
-#[derive(Default)]
 pub struct MessageBar {
   current_message: Message,
+  /// How long a message is shown
+  duration: Duration,
   need_redraw: bool,
   // ensures we can properly hide expired messages
   // once a message expires we need to clear it
   cleared_after_expiry: bool,
 }
 
+impl Default for MessageBar {
+  fn default() -> Self {
+    Self {
+      current_message: Message::default(),
+      duration: DEFAULT_MESSAGE_DURATION,
+      need_redraw: false,
+      cleared_after_expiry: false,
+    }
+  }
+}
+
 impl MessageBar {
+  pub const fn set_duration(&mut self, duration: Duration) {
+    self.duration = duration;
+  }
+
   pub fn update_message(&mut self, new_message: &str) {
     self.current_message = Message {
       text: new_message.to_string(),
@@ -57,22 +73,23 @@ impl UIComponent for MessageBar {
 
   fn needs_redraw(&self) -> bool {
     // Check if current message is cleared we send redraw every time till it gets cleared
-    let is_message_cleared = !self.cleared_after_expiry && self.current_message.is_expired();
+    let is_message_cleared =
+      !self.cleared_after_expiry && self.current_message.is_expired(self.duration);
     is_message_cleared || self.need_redraw
   }
 
-  fn draw(&mut self, origin_y: RowIdx) -> Result<(), Error> {
-    if self.current_message.is_expired() {
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    if self.current_message.is_expired(self.duration) {
       // Upon expiration, we need to write out "" once to clear the message.
       // To avoid clearing more than necessary, we keep track of the fact that we've already cleared the expired message once.
       self.cleared_after_expiry = true;
     }
-    let message = if self.current_message.is_expired() {
+    let message = if self.current_message.is_expired(self.duration) {
       ""
     } else {
       &self.current_message.text
     };
-    Terminal::print_row(origin_y, message)
+    Terminal::print_row(origin.row, message)
   }
 
   fn set_size(&mut self, _: Size) {}
@@ -1,11 +1,17 @@
 mod commandbar;
 mod messagebar;
+mod picker;
+mod preview;
 mod statusbar;
 mod uicomponent;
 mod view;
+mod window;
 
 pub use commandbar::CommandBar;
-pub use messagebar::MessageBar;
+pub use messagebar::{DEFAULT_MESSAGE_DURATION, MessageBar};
+pub use picker::Picker;
+pub use preview::Preview;
 pub use statusbar::StatusBar;
 pub use uicomponent::UIComponent;
 pub use view::View;
+pub use window::Window;
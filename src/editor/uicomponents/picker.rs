@@ -0,0 +1,147 @@
+use crate::{
+  editor::{fuzzymatch::fuzzy_score, line::Line, terminal::Terminal, uicomponents::UIComponent},
+  prelude::*,
+};
+
+use std::{cmp::Reverse, io::Error};
+
+/// A list of entries shown on top of the view, to pick one of them.
+/// The command bar holds the text the entries are filtered by.
+#[derive(Default)]
+pub struct Picker {
+  entries: Vec<String>,
+  /// Indices of the entries which match the filter, in the order they are listed
+  matches: Vec<usize>,
+  /// Index into `matches`
+  selected: usize,
+  /// Index into `matches` of the first visible row
+  scroll_offset: usize,
+  needs_redraw: bool,
+  size: Size,
+}
+
+impl Picker {
+  /// Replaces the entries, selecting the one at the given index
+  pub fn set_entries(&mut self, entries: Vec<String>, selected: usize) {
+    self.matches = (0..entries.len()).collect();
+    self.entries = entries;
+    self.selected = selected.min(self.matches.len().saturating_sub(1));
+    self.scroll_offset = 0;
+    self.scroll_selection_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  pub const fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Only lists the entries which fuzzily match the filter, best matches first.
+  /// Equally good matches are listed shorter ones first, then in their original order.
+  /// The best match is selected.
+  pub fn filter(&mut self, filter: &str) {
+    if filter.is_empty() {
+      self.matches = (0..self.entries.len()).collect();
+    } else {
+      let mut scored: Vec<(usize, usize, usize)> = self
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| {
+          fuzzy_score(filter, entry).map(|score| (idx, score, entry.len()))
+        })
+        .collect();
+      scored.sort_by_key(|(idx, score, len)| (Reverse(*score), *len, *idx));
+      self.matches = scored.into_iter().map(|(idx, _, _)| idx).collect();
+    }
+    self.selected = 0;
+    self.scroll_offset = 0;
+    self.set_needs_redraw(true);
+  }
+
+  pub fn select_previous(&mut self) {
+    self.selected = self
+      .selected
+      .checked_sub(1)
+      .unwrap_or_else(|| self.matches.len().saturating_sub(1));
+    self.scroll_selection_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  pub fn select_next(&mut self) {
+    self.selected = self
+      .selected
+      .saturating_add(1)
+      .checked_rem(self.matches.len())
+      .unwrap_or(0);
+    self.scroll_selection_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  /// Index of the selected entry, `None` if no entry matches the filter
+  pub fn selected(&self) -> Option<usize> {
+    self.matches.get(self.selected).copied()
+  }
+
+  pub fn selected_entry(&self) -> Option<&str> {
+    self
+      .selected()
+      .and_then(|idx| self.entries.get(idx))
+      .map(String::as_str)
+  }
+
+  fn scroll_selection_into_view(&mut self) {
+    let height = self.size.height.max(1);
+    if self.selected < self.scroll_offset {
+      self.scroll_offset = self.selected;
+    } else if self.selected >= self.scroll_offset.saturating_add(height) {
+      self.scroll_offset = self.selected.saturating_add(1).saturating_sub(height);
+    }
+  }
+}
+
+impl UIComponent for Picker {
+  fn set_needs_redraw(&mut self, value: bool) {
+    self.needs_redraw = value;
+  }
+
+  fn needs_redraw(&self) -> bool {
+    self.needs_redraw
+  }
+
+  fn set_size(&mut self, size: Size) {
+    self.size = size;
+    self.scroll_selection_into_view();
+  }
+
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    let width = self.size.width;
+    for row in 0..self.size.height {
+      let current_origin = Position {
+        row: origin.row.saturating_add(row),
+        col: origin.col,
+      };
+      let match_idx = self.scroll_offset.saturating_add(row);
+      let Some(entry) = self
+        .matches
+        .get(match_idx)
+        .and_then(|idx| self.entries.get(*idx))
+      else {
+        let text = if self.matches.is_empty() && row == 0 {
+          "  No matches"
+        } else {
+          ""
+        };
+        Terminal::print_at(current_origin, width, text)?;
+        continue;
+      };
+
+      let text = Line::from(&format!("  {entry}")).get_visible_graphemes(0..width);
+      if match_idx == self.selected {
+        Terminal::print_inverted_row(current_origin, width, &text)?;
+      } else {
+        Terminal::print_at(current_origin, width, &text)?;
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,85 @@
+use crate::{
+  editor::{line::Line, terminal::Terminal, uicomponents::UIComponent},
+  prelude::*,
+};
+
+use std::{
+  fs::File,
+  io::{Error, Read},
+  path::Path,
+};
+
+/// Only the start of a file is read, the preview shows no more than a screen of it anyway
+const MAX_PREVIEW_BYTES: u64 = 16 * 1024;
+/// Separates the preview from the list next to it
+const BORDER: &str = "│ ";
+
+/// Shows the first lines of the file selected in the file finder, or other text which can't be edited
+#[derive(Default)]
+pub struct Preview {
+  lines: Vec<String>,
+  needs_redraw: bool,
+  size: Size,
+}
+
+impl Preview {
+  /// Shows the start of the file, or nothing if there is no file
+  pub fn show_file(&mut self, path: Option<&Path>) {
+    self.lines = path.map(read_start).unwrap_or_default();
+    self.set_needs_redraw(true);
+  }
+
+  pub fn show_lines(&mut self, lines: Vec<String>) {
+    self.lines = lines;
+    self.set_needs_redraw(true);
+  }
+}
+
+fn read_start(path: &Path) -> Vec<String> {
+  let mut bytes = Vec::new();
+  if let Err(err) =
+    File::open(path).and_then(|file| file.take(MAX_PREVIEW_BYTES).read_to_end(&mut bytes))
+  {
+    return vec![format!("Could not read file: {err}")];
+  }
+  if bytes.contains(&0) {
+    return vec![String::from("Binary file")];
+  }
+  String::from_utf8_lossy(&bytes)
+    .lines()
+    .map(str::to_string)
+    .collect()
+}
+
+impl UIComponent for Preview {
+  fn set_needs_redraw(&mut self, value: bool) {
+    self.needs_redraw = value;
+  }
+
+  fn needs_redraw(&self) -> bool {
+    self.needs_redraw
+  }
+
+  fn set_size(&mut self, size: Size) {
+    self.size = size;
+  }
+
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    let width = self.size.width;
+    let text_width = width.saturating_sub(BORDER.chars().count());
+    for row in 0..self.size.height {
+      let line = self.lines.get(row).map_or_else(String::new, |line| {
+        Line::from(line).get_visible_graphemes(0..text_width)
+      });
+      Terminal::print_at(
+        Position {
+          row: origin.row.saturating_add(row),
+          col: origin.col,
+        },
+        width,
+        &format!("{BORDER}{line}"),
+      )?;
+    }
+    Ok(())
+  }
+}
@@ -1,6 +1,6 @@
 use crate::{
   editor::{documentstatus::DocumentStatus, terminal::Terminal, uicomponents::UIComponent},
-  prelude::{RowIdx, Size},
+  prelude::{Position, Size},
 };
 
 #[derive(Default)]
@@ -32,7 +32,7 @@ impl UIComponent for StatusBar {
     self.size = size;
   }
 
-  fn draw(&mut self, origin_row: RowIdx) -> Result<(), std::io::Error> {
+  fn draw(&mut self, origin: Position) -> Result<(), std::io::Error> {
     // Assemble the first part of the status bar
     let line_count = self.current_status.line_count_to_string();
     let modified_indicator = self.current_status.modified_indicator_to_string();
@@ -46,8 +46,20 @@ impl UIComponent for StatusBar {
     let position_indicator = self.current_status.position_indicator_to_string();
 
     let file_type = self.current_status.file_type_to_string();
+    let encoding = self.current_status.encoding_to_string();
+    let line_ending = self.current_status.line_ending_to_string();
 
-    let back_part = format!("{file_type} | {position_indicator}");
+    let back_part = self
+      .current_status
+      .search_indicator_to_string()
+      .map_or_else(
+        || format!("{file_type} | {encoding} | {line_ending} | {position_indicator}"),
+        |search_indicator| {
+          format!(
+            "{search_indicator} | {file_type} | {encoding} | {line_ending} | {position_indicator}"
+          )
+        },
+      );
 
     // Assemble the whole status bar
     let remainder_len = self.size.width.saturating_sub(beginning.len());
@@ -61,7 +73,7 @@ impl UIComponent for StatusBar {
       String::new()
     };
 
-    Terminal::print_inverted_row(origin_row, &to_print)?;
+    Terminal::print_inverted_row(origin, self.size.width, &to_print)?;
 
     Ok(())
   }
@@ -1,6 +1,6 @@
 use std::io::Error;
 
-use crate::prelude::{RowIdx, Size};
+use crate::prelude::{Position, Size};
 
 /// Trait to be used by all ui Components like `view` , `message_bar`
 pub trait UIComponent {
@@ -13,8 +13,12 @@ pub trait UIComponent {
   // Updates the size. Needs to be implemented by each component.
   fn set_size(&mut self, size: Size);
 
-  // Method to actually draw the component, must be implemented by each component
-  fn draw(&mut self, origin_row: usize) -> Result<(), Error>;
+  /// Method to actually draw the component at the given top left corner, must be implemented by each component.
+  ///
+  /// # Errors
+  ///
+  /// Fails if writing to the terminal fails.
+  fn draw(&mut self, origin: Position) -> Result<(), Error>;
 
   // Updates the size and marks as redraw-needed
   fn resize(&mut self, size: Size) {
@@ -23,9 +27,9 @@ pub trait UIComponent {
   }
 
   // Draw this component if it's visible and in need of redrawing
-  fn render(&mut self, origin_row: RowIdx) {
+  fn render(&mut self, origin: Position) {
     if self.needs_redraw() {
-      if let Err(err) = self.draw(origin_row) {
+      if let Err(err) = self.draw(origin) {
         #[cfg(debug_assertions)]
         {
           panic!("Could not render component: {err:?}");
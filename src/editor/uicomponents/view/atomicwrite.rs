@@ -0,0 +1,274 @@
+use std::{
+  fs::{
+    File, Metadata, OpenOptions, canonicalize, metadata, read_link, remove_file, rename,
+    set_permissions, symlink_metadata,
+  },
+  io::{Error, ErrorKind, Write},
+  path::{Path, PathBuf},
+  process,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// How many temporary file names are tried, in case some are taken, e.g. left behind by a crash
+const MAX_TEMP_ATTEMPTS: usize = 100;
+
+/// Tells the temporary files of this process apart
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Replaces the contents of the file at `path` without ever leaving it half written.
+///
+/// The contents go to a temporary file in the same directory, which is synced and then renamed over the target.
+/// Mode and ownership of the target are kept. A new file takes them from `template` instead, if given.
+/// A symlink is followed, and the file it points to is replaced, so that the link keeps pointing at the new contents.
+pub fn write_atomically(
+  path: &Path,
+  contents: &[u8],
+  template: Option<&Path>,
+) -> Result<(), Error> {
+  let target = symlink_metadata(path)
+    .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    .then(|| resolve_symlink(path));
+  let path = target.as_deref().unwrap_or(path);
+  let existing = metadata(path).ok();
+  if existing.as_ref().is_some_and(has_other_links) {
+    return write_in_place(path, contents);
+  }
+
+  let source = existing
+    .clone()
+    .or_else(|| template.and_then(|template| metadata(template).ok()));
+  let (temp_path, mut temp_file) = match create_temp_file(path, source.is_some()) {
+    Ok(created) => created,
+    // The directory is not writable, but the file itself might be
+    Err(err) if err.kind() == ErrorKind::PermissionDenied && existing.is_some() => {
+      return write_in_place(path, contents);
+    }
+    Err(err) => return Err(err),
+  };
+
+  let result = write_and_sync(&mut temp_file, contents)
+    .and_then(|()| copy_attributes(&temp_path, source.as_ref()))
+    .and_then(|()| rename(&temp_path, path));
+  if result.is_err() {
+    let _ = remove_file(&temp_path);
+    return result;
+  }
+
+  sync_parent_dir(path);
+  Ok(())
+}
+
+/// The file a symlink points to, even if it doesn't exist yet
+fn resolve_symlink(path: &Path) -> PathBuf {
+  canonicalize(path)
+    .or_else(|_| {
+      read_link(path).map(|target| {
+        path
+          .parent()
+          .map_or_else(|| target.clone(), |parent| parent.join(&target))
+      })
+    })
+    .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Truncates the file and writes it anew, so that a crash partway through leaves it incomplete.
+/// Only used where a rename can't take its place: for a file with other hard links, which would keep the old contents,
+/// and in a directory in which no temporary file can be created.
+fn write_in_place(path: &Path, contents: &[u8]) -> Result<(), Error> {
+  let mut file = File::create(path)?;
+  write_and_sync(&mut file, contents)
+}
+
+fn write_and_sync(file: &mut File, contents: &[u8]) -> Result<(), Error> {
+  file.write_all(contents)?;
+  file.sync_all()
+}
+
+/// Creates a temporary file next to the target, skipping names which are already taken.
+/// Until it gets the mode of the file it replaces, only the owner may read it.
+/// A file which doesn't replace any gets the mode of any new file.
+fn create_temp_file(path: &Path, is_private: bool) -> Result<(PathBuf, File), Error> {
+  let mut options = OpenOptions::new();
+  options.write(true).create_new(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(if is_private { 0o600 } else { 0o666 });
+  }
+  #[cfg(not(unix))]
+  let _ = is_private;
+
+  for _ in 0..MAX_TEMP_ATTEMPTS {
+    let temp_path = temp_path_for(path);
+    match options.open(&temp_path) {
+      Ok(file) => return Ok((temp_path, file)),
+      Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+      Err(err) => return Err(err),
+    }
+  }
+  Err(Error::new(
+    ErrorKind::AlreadyExists,
+    "Could not find an unused name for a temporary file",
+  ))
+}
+
+/// `.name.hecto-<pid>-<n>.tmp` next to the target, so that renaming it stays within one file system.
+/// The process id may be reused after a crash, the counter still gives a new name then.
+fn temp_path_for(path: &Path) -> PathBuf {
+  let file_name = path
+    .file_name()
+    .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+  let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+  path.with_file_name(format!(
+    ".{file_name}.hecto-{}-{counter}.tmp",
+    process::id()
+  ))
+}
+
+fn copy_attributes(path: &Path, source: Option<&Metadata>) -> Result<(), Error> {
+  let Some(source) = source else {
+    return Ok(());
+  };
+  set_permissions(path, source.permissions())?;
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::{MetadataExt, chown};
+    // Only the superuser may hand a file to another user, so not keeping the owner is no error
+    let _ = chown(path, Some(source.uid()), Some(source.gid()));
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn has_other_links(metadata: &Metadata) -> bool {
+  use std::os::unix::fs::MetadataExt;
+  metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+const fn has_other_links(_metadata: &Metadata) -> bool {
+  false
+}
+
+/// Makes sure the rename itself survives a crash. Not all platforms and file systems support this.
+fn sync_parent_dir(path: &Path) {
+  #[cfg(unix)]
+  {
+    let parent = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .unwrap_or_else(|| Path::new("."));
+    if let Ok(dir) = File::open(parent) {
+      let _ = dir.sync_all();
+    }
+  }
+  #[cfg(not(unix))]
+  let _ = path;
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  use std::{
+    fs::{create_dir_all, hard_link, read, read_dir, remove_dir_all, write},
+    os::unix::fs::{MetadataExt, PermissionsExt, symlink},
+  };
+
+  /// An empty directory of its own for each test
+  struct TestDir(PathBuf);
+
+  impl TestDir {
+    fn new(name: &str) -> Self {
+      let path = std::env::temp_dir().join(format!("hecto-atomicwrite-{}-{name}", process::id()));
+      let _ = remove_dir_all(&path);
+      create_dir_all(&path).expect("Could not create the test directory");
+      Self(path)
+    }
+
+    fn file(&self, contents: &str) -> PathBuf {
+      let path = self.0.join("file.txt");
+      write(&path, contents).expect("Could not write the test file");
+      path
+    }
+
+    fn entries(&self) -> usize {
+      read_dir(&self.0)
+        .expect("Could not list the test directory")
+        .count()
+    }
+  }
+
+  impl Drop for TestDir {
+    fn drop(&mut self) {
+      let _ = remove_dir_all(&self.0);
+    }
+  }
+
+  fn mode(path: &Path) -> u32 {
+    metadata(path).expect("File exists").mode() & 0o777
+  }
+
+  #[test]
+  fn replaces_the_file_with_a_new_one() {
+    let dir = TestDir::new("rename");
+    let path = dir.file("old");
+    let old_inode = metadata(&path).expect("File exists").ino();
+
+    write_atomically(&path, b"new", None).expect("Could not write");
+    assert_eq!(read(&path).expect("File exists"), b"new");
+    assert_ne!(metadata(&path).expect("File exists").ino(), old_inode);
+    // No temporary file is left behind
+    assert_eq!(dir.entries(), 1);
+  }
+
+  #[test]
+  fn keeps_the_mode() {
+    let dir = TestDir::new("mode");
+    let path = dir.file("old");
+    set_permissions(&path, PermissionsExt::from_mode(0o640)).expect("Could not set the mode");
+
+    write_atomically(&path, b"new", None).expect("Could not write");
+    assert_eq!(mode(&path), 0o640);
+  }
+
+  #[test]
+  fn new_file_takes_the_mode_of_the_template() {
+    let dir = TestDir::new("template");
+    let template = dir.file("template");
+    set_permissions(&template, PermissionsExt::from_mode(0o750)).expect("Could not set the mode");
+    let path = dir.0.join("new.txt");
+
+    write_atomically(&path, b"new", Some(&template)).expect("Could not write");
+    assert_eq!(mode(&path), 0o750);
+  }
+
+  #[test]
+  fn follows_symlinks() {
+    let dir = TestDir::new("symlink");
+    let target = dir.file("old");
+    let link = dir.0.join("link.txt");
+    symlink(&target, &link).expect("Could not create the symlink");
+
+    write_atomically(&link, b"new", None).expect("Could not write");
+    assert!(
+      symlink_metadata(&link)
+        .expect("Link exists")
+        .file_type()
+        .is_symlink()
+    );
+    assert_eq!(read(&target).expect("File exists"), b"new");
+    assert_eq!(dir.entries(), 2);
+  }
+
+  #[test]
+  fn writes_hard_linked_files_in_place() {
+    let dir = TestDir::new("hardlink");
+    let path = dir.file("old");
+    let other = dir.0.join("other.txt");
+    hard_link(&path, &other).expect("Could not create the hard link");
+
+    write_atomically(&path, b"new", None).expect("Could not write");
+    assert_eq!(read(&other).expect("File exists"), b"new");
+  }
+}
@@ -1,45 +1,142 @@
 use crate::{
   editor::{
     annotatedstring::AnnotatedString,
+    encoding::Encoding,
+    indentation::Indentation,
     line::Line,
-    uicomponents::view::{fileinfo::FileInfo, highlighter::Highlighter},
+    lineending::LineEnding,
+    searchquery::SearchQuery,
+    swapfile::SwapSource,
+    uicomponents::view::{
+      atomicwrite::write_atomically,
+      fileinfo::FileInfo,
+      filestamp::FileStamp,
+      highlighter::{Highlighter, SyntaxHighlighter, create_syntax_highlighter},
+      history::{Change, History, TransactionKind},
+      linecache::LineCache,
+    },
   },
   prelude::*,
 };
 
 use std::{
-  fs::{File, read_to_string},
-  io::{Error, Write},
+  borrow::Cow,
+  cell::RefCell,
+  cmp::min,
+  fs::{metadata, read},
+  io::Error,
   ops::Range,
+  path::Path,
+  rc::Rc,
 };
 
+use ropey::{Rope, RopeBuilder, RopeSlice};
+use unicode_segmentation::UnicodeSegmentation;
+
 #[derive(Default)]
 pub struct Buffer {
-  /// Store line as a vector
-  lines: Vec<Line>,
+  /// The text, with every line ending in `\n`, so that an empty buffer has no lines.
+  /// Lines are only turned into a `Line` when they are needed.
+  text: Rope,
   file_info: FileInfo,
   /// Marked true if there is change in original buffer
   dirty: bool,
+  /// Format of the file as last loaded or saved, to tell whether it has been changed since
+  saved_line_ending: LineEnding,
+  saved_encoding: Encoding,
+  history: History,
+  /// Increased with every change to the text
+  revision: usize,
+  /// Kept across frames and edits, so that only lines affected by an edit are highlighted again
+  syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
+  /// Lines recently asked for, as building a `Line` takes going through all of its text
+  line_cache: RefCell<LineCache>,
 }
 
 impl Buffer {
-  pub const fn is_dirty(&self) -> bool {
+  pub fn is_dirty(&self) -> bool {
     self.dirty
+      || self.file_info.get_line_ending() != self.saved_line_ending
+      || self.file_info.get_encoding() != self.saved_encoding
+  }
+
+  pub const fn revision(&self) -> usize {
+    self.revision
+  }
+
+  /// Takes the place of this buffer, e.g. when reloading it.
+  /// The revision keeps increasing, so that every view of the buffer notices the change.
+  pub fn replace_with(&mut self, buffer: Self) {
+    let revision = self.revision.wrapping_add(1);
+    *self = buffer;
+    self.revision = revision;
   }
 
   pub const fn get_file_info(&self) -> &FileInfo {
     &self.file_info
   }
 
+  /// Text of the line at the given index, without its line break
+  fn line_str(&self, idx: LineIdx) -> Option<Cow<'_, str>> {
+    if idx >= self.height() {
+      return None;
+    }
+    Some(Self::without_line_break(self.text.line(idx)))
+  }
+
+  fn without_line_break(line: RopeSlice<'_>) -> Cow<'_, str> {
+    line.slice(..line.len_chars().saturating_sub(1)).into()
+  }
+
+  fn line(&self, idx: LineIdx) -> Option<Rc<Line>> {
+    let tab_width = self.file_info.get_indentation().tab_width;
+    self
+      .line_cache
+      .borrow_mut()
+      .get_or_insert_with(idx, tab_width, || {
+        self
+          .line_str(idx)
+          .map(|line| Line::from(&line).with_tab_width(tab_width))
+      })
+  }
+
+  /// The line at the given index, if it possibly contains a match for the query
+  fn matching_line(&self, query: &SearchQuery, idx: LineIdx) -> Option<Rc<Line>> {
+    self
+      .line_str(idx)
+      .is_some_and(|line| query.is_match(&line))
+      .then(|| self.line(idx))
+      .flatten()
+  }
+
   pub fn grapheme_count(&self, idx: LineIdx) -> GraphemeIdx {
-    self.lines.get(idx).map_or(0, Line::grapheme_count)
+    self.line(idx).map_or(0, |line| line.grapheme_count())
+  }
+
+  /// Start of the word before `at`, on the same line
+  pub fn previous_word_start(&self, at: Location) -> Location {
+    let grapheme_idx = self
+      .line(at.line_idx)
+      .map_or(0, |line| line.previous_word_start(at.grapheme_idx));
+    Location {
+      line_idx: at.line_idx,
+      grapheme_idx,
+    }
   }
 
   pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx) -> GraphemeIdx {
+    self.line(idx).map_or(0, |line| line.width_until(until))
+  }
+
+  pub fn grapheme_idx_at(&self, idx: LineIdx, col: ColIdx) -> GraphemeIdx {
+    self.line(idx).map_or(0, |line| line.grapheme_idx_at(col))
+  }
+
+  /// Where the rows of the line start when it is wrapped to the width, see `Line::wrap`
+  pub fn wrap(&self, idx: LineIdx, width: ColIdx) -> Rc<[GraphemeIdx]> {
     self
-      .lines
-      .get(idx)
-      .map_or(0, |line| line.width_until(until))
+      .line(idx)
+      .map_or_else(|| Rc::from([0]), |line| line.wrap(width))
   }
 
   pub fn get_highlighted_substring(
@@ -48,61 +145,215 @@ impl Buffer {
     range: Range<GraphemeIdx>,
     highlighter: &Highlighter,
   ) -> Option<AnnotatedString> {
-    self.lines.get(line_idx).map(|line| {
+    self.line(line_idx).map(|line| {
       line.get_annotated_visible_substr(range, Some(&highlighter.get_annotations(line_idx)))
     })
   }
 
   pub fn highlight(&self, idx: LineIdx, highlighter: &mut Highlighter) {
-    if let Some(line) = self.lines.get(idx) {
-      highlighter.highlight(idx, line);
+    if let Some(line) = self.line(idx) {
+      highlighter.highlight(idx, &line);
+    }
+  }
+
+  pub fn syntax_highlighter(&self) -> Option<&dyn SyntaxHighlighter> {
+    self.syntax_highlighter.as_deref()
+  }
+
+  /// Brings the syntax highlighting up to date for all lines before `end`.
+  /// Lines after an edit are only highlighted again until the state carried over from one line to the next
+  /// is the same as before the edit.
+  pub fn update_syntax_highlighting(&mut self, end: LineIdx) {
+    let Some(mut syntax_highlighter) = self.syntax_highlighter.take() else {
+      return;
+    };
+    for idx in syntax_highlighter.highlighted_until()..min(end, self.height()) {
+      if !syntax_highlighter.reuse(idx)
+        && let Some(line) = self.line(idx)
+      {
+        syntax_highlighter.highlight(idx, &line);
+      }
+    }
+    self.syntax_highlighter = Some(syntax_highlighter);
+  }
+
+  /// Tells the syntax highlighter that the `removed` lines starting at `idx` were replaced with `inserted` lines
+  fn invalidate_syntax_highlighting(&mut self, idx: LineIdx, removed: usize, inserted: usize) {
+    if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
+      syntax_highlighter.invalidate(idx, removed, inserted);
     }
   }
 
-  pub fn load(file_name: &str) -> Result<Self, Error> {
-    let contents = read_to_string(file_name)?;
-    let mut lines = Vec::new();
-    for value in contents.lines() {
-      lines.push(Line::from(value));
+  /// Load a file, remembering its encoding, line ending style, final newline and byte order mark
+  /// so that saving writes them back unchanged.
+  /// The encoding is detected unless one is given.
+  pub fn load(file_name: &str, encoding: Option<Encoding>) -> Result<Self, Error> {
+    let bytes = read(file_name)?;
+    let (contents, encoding, has_bom) = match encoding {
+      Some(encoding) => {
+        let (contents, has_bom) = encoding.decode_with_bom(&bytes)?;
+        (contents, encoding, has_bom)
+      }
+      None => Encoding::decode_detected(&bytes),
+    };
+    let mut file_info = FileInfo::from(file_name);
+    file_info.set_encoding(encoding);
+    file_info.set_bom(has_bom);
+    file_info.set_stamp(
+      metadata(file_name)
+        .ok()
+        .map(|metadata| FileStamp::from(&bytes, &metadata)),
+    );
+
+    let line_ending = LineEnding::detect(&contents);
+    file_info.set_line_ending(line_ending);
+    let has_final_newline = contents.ends_with('\n');
+    file_info.set_final_newline(has_final_newline);
+
+    let contents = match line_ending {
+      LineEnding::CrLf => Cow::Owned(contents.replace("\r\n", "\n")),
+      // A carriage return on its own is part of the line, so that it survives saving.
+      // In a mixed file this keeps the ending of each line until the user converts them.
+      LineEnding::Lf | LineEnding::Mixed => Cow::Borrowed(contents.as_str()),
+    };
+
+    let mut text = RopeBuilder::new();
+    text.append(&contents);
+    // An empty file has no lines, while a file with just a newline has one empty line
+    if !contents.is_empty() && !has_final_newline {
+      text.append("\n");
     }
+
     Ok(Self {
-      lines,
-      file_info: FileInfo::from(file_name),
-      dirty: false,
+      text: text.finish(),
+      syntax_highlighter: create_syntax_highlighter(file_info.get_file_type()),
+      file_info,
+      saved_line_ending: line_ending,
+      saved_encoding: encoding,
+      ..Self::default()
     })
   }
 
-  /// Save the buffer in the given file
-  fn save_to_file(&self, file_info: &FileInfo) -> Result<(), Error> {
-    if let Some(file_path) = &file_info.get_path() {
-      let mut file = File::create(file_path)?;
-      for line in &self.lines {
-        writeln!(file, "{line}")?;
+  /// Save the buffer in the given file.
+  /// A new file gets its permissions from `template`, if given.
+  /// Returns the stamp of the written file.
+  fn save_to_file(
+    &self,
+    file_info: &FileInfo,
+    template: Option<&Path>,
+  ) -> Result<Option<FileStamp>, Error> {
+    let Some(file_path) = file_info.get_path() else {
+      return Ok(None);
+    };
+
+    let line_ending = file_info.get_line_ending();
+    let mut contents = String::with_capacity(self.text.len_bytes());
+    for chunk in self.text.chunks() {
+      match line_ending {
+        LineEnding::Lf | LineEnding::Mixed => contents.push_str(chunk),
+        LineEnding::CrLf => contents.push_str(&chunk.replace('\n', line_ending.as_str())),
       }
     }
-    Ok(())
+    if !file_info.has_final_newline() {
+      let len = contents
+        .strip_suffix(line_ending.as_str())
+        .map_or(contents.len(), str::len);
+      contents.truncate(len);
+    }
+
+    let encoding = file_info.get_encoding();
+    let mut bytes = Vec::new();
+    if file_info.has_bom() {
+      bytes.extend_from_slice(encoding.bom());
+    }
+    bytes.extend(encoding.encode(&contents)?);
+
+    write_atomically(file_path, &bytes, template)?;
+    Ok(
+      metadata(file_path)
+        .ok()
+        .map(|metadata| FileStamp::from(&bytes, &metadata)),
+    )
   }
 
   /// Save the buffer in the file by creating new file with ` file_name `
   pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-    let file_info = FileInfo::from(file_name);
-    self.save_to_file(&file_info)?;
+    let mut file_info = self.file_info.with_path(file_name);
+    // A script saved under a new name stays executable
+    let stamp = self.save_to_file(&file_info, self.file_info.get_path())?;
+    file_info.set_stamp(stamp);
+    if file_info.get_file_type() != self.file_info.get_file_type() {
+      self.syntax_highlighter = create_syntax_highlighter(file_info.get_file_type());
+    }
     self.file_info = file_info;
-
-    self.dirty = false;
+    self.mark_saved();
     Ok(())
   }
 
   /// Save the existing file
   pub fn save(&mut self) -> Result<(), Error> {
-    self.save_to_file(&self.file_info)?;
-    self.dirty = false;
+    let stamp = self.save_to_file(&self.file_info, None)?;
+    self.file_info.set_stamp(stamp);
+    self.mark_saved();
     Ok(())
   }
 
+  fn mark_saved(&mut self) {
+    self.dirty = false;
+    self.saved_line_ending = self.file_info.get_line_ending();
+    self.saved_encoding = self.file_info.get_encoding();
+    self.history.mark_saved();
+  }
+
+  /// Whether someone else changed the file since it was last loaded or saved
+  pub fn is_changed_on_disk(&self) -> bool {
+    match (self.file_info.get_path(), self.file_info.get_stamp()) {
+      (Some(path), Some(stamp)) => !stamp.matches(path),
+      _ => false,
+    }
+  }
+
+  /// Like `is_changed_on_disk`, but reads the whole file, as saving would overwrite any change missed
+  pub fn is_changed_on_disk_before_save(&self) -> bool {
+    match (self.file_info.get_path(), self.file_info.get_stamp()) {
+      (Some(path), Some(stamp)) => !stamp.matches_contents(path),
+      _ => false,
+    }
+  }
+
+  /// Accepts the current version on disk as the one the buffer is based on, so that saving overwrites it
+  pub fn acknowledge_disk_version(&mut self) {
+    let Some(path) = self.file_info.get_path() else {
+      return;
+    };
+    let stamp = read(path)
+      .ok()
+      .zip(metadata(path).ok())
+      .map(|(bytes, metadata)| FileStamp::from(&bytes, &metadata));
+    self.file_info.set_stamp(stamp);
+  }
+
+  /// Changes the line ending style used when saving
+  pub const fn set_line_ending(&mut self, line_ending: LineEnding) {
+    self.file_info.set_line_ending(line_ending);
+  }
+
+  pub const fn set_indentation(&mut self, indentation: Indentation) {
+    self.file_info.set_indentation(indentation);
+  }
+
+  /// Changes the encoding used when saving
+  pub fn set_encoding(&mut self, encoding: Encoding) {
+    let current = self.file_info.get_encoding();
+    // Keep the byte order mark of UTF-8 files, but don't add one to files which never had one
+    let has_bom = encoding.requires_bom() || (encoding == current && self.file_info.has_bom());
+    self.file_info.set_encoding(encoding);
+    self.file_info.set_bom(has_bom);
+  }
+
   /// Return True if buffer is empty
   pub fn is_empty(&self) -> bool {
-    self.lines.is_empty()
+    self.text.len_chars() == 0
   }
 
   pub const fn is_file_loaded(&self) -> bool {
@@ -111,7 +362,8 @@ impl Buffer {
 
   /// Return total height covered by buffer
   pub fn height(&self) -> LineIdx {
-    self.lines.len()
+    // The text after the last line break counts as a line of its own, but is always empty
+    self.text.len_lines().saturating_sub(1)
   }
 
   /// Insert a character in a line at
@@ -119,71 +371,371 @@ impl Buffer {
     // We don't insert anything more than line below the document
     debug_assert!(at.line_idx <= self.height());
 
-    // At the end of document then add a new line
-    if at.line_idx == self.height() {
-      self.lines.push(Line::from(&character.to_string()));
-      self.dirty = true;
-    } else if let Some(line) = self.lines.get_mut(at.line_idx) {
-      // If we are at the end of line, just insert the character
-      line.insert_char(character, at.grapheme_idx);
-      self.dirty = true;
-    }
+    self.insert_recorded(&character.to_string(), at);
   }
 
   /// Delete a char given at location
   pub fn delete(&mut self, at: Location) {
     // Check if we are at a valid line
-    if let Some(line) = self.lines.get(at.line_idx) {
+    if at.line_idx < self.height() {
+      let grapheme_count = self.grapheme_count(at.line_idx);
       // Check if we are at the end of current line and there's at least next line available
-      if at.grapheme_idx >= line.grapheme_count() && self.height() > at.line_idx.saturating_add(1) {
-        let next_line = self.lines.remove(at.line_idx.saturating_add(1));
-
-        // clippy::indexing_slicing: We checked for existence of this line in the surrounding if statement
-        #[allow(clippy::indexing_slicing)]
-        self.lines[at.line_idx].append(&next_line);
-        self.dirty = true;
-      } else if at.grapheme_idx < line.grapheme_count() {
-        // clippy::indexing_slicing: We checked for existence of this line in the surrounding if statement
-        #[allow(clippy::indexing_slicing)]
-        self.lines[at.line_idx].delete(at.grapheme_idx);
-        self.dirty = true;
+      if at.grapheme_idx >= grapheme_count && self.height() > at.line_idx.saturating_add(1) {
+        self.delete_recorded(
+          at,
+          Location {
+            line_idx: at.line_idx.saturating_add(1),
+            grapheme_idx: 0,
+          },
+        );
+      } else if at.grapheme_idx < grapheme_count {
+        self.delete_recorded(
+          at,
+          Location {
+            line_idx: at.line_idx,
+            grapheme_idx: at.grapheme_idx.saturating_add(1),
+          },
+        );
       }
     }
   }
 
   /// Insert a new line given at location
   pub fn insert_newline(&mut self, at: Location) {
-    // If we are at the end of document, insert an empty line.
-    if at.line_idx == self.height() {
-      self.lines.push(Line::default());
+    // At the end of the document a new empty line is appended,
+    // which is exactly what inserting an empty text there does.
+    let text = if at.line_idx == self.height() {
+      ""
+    } else {
+      "\n"
+    };
+    self.insert_recorded(text, at);
+  }
+
+  /// Insert a (possibly multi line) text as a single change.
+  /// Returns the location right after the inserted text.
+  pub fn insert_text_at(&mut self, text: &str, at: Location) -> Location {
+    self.insert_recorded(text, at)
+  }
+
+  /// Get the whole text, lines separated by `\n`
+  pub fn contents(&self) -> String {
+    self.get_text(Location::default(), self.end_location())
+  }
+
+  /// Replace the whole text in a single undo step, e.g. with a recovered version of it
+  pub fn replace_contents(&mut self, text: &str, caret: Location) {
+    self
+      .history
+      .begin_transaction(TransactionKind::Other, caret);
+    self.delete_recorded(Location::default(), self.end_location());
+    self.insert_recorded(text, Location::default());
+    self.history.end_transaction(caret);
+  }
+
+  /// Get all text between the two locations, lines separated by `\n`
+  pub fn get_text(&self, from: Location, to: Location) -> String {
+    let to = min(to, self.end_location());
+    if from >= to {
+      return String::new();
+    }
+
+    self
+      .text
+      .slice(self.char_idx(from)..self.char_idx(to))
+      .to_string()
+  }
+
+  /// Replaces the match starting at the given location.
+  /// Returns the location right after the replacement, or `None` if there's no match.
+  pub fn replace_match(
+    &mut self,
+    query: &SearchQuery,
+    at: Location,
+    replacement: &str,
+  ) -> Option<Location> {
+    let line = self.line(at.line_idx)?;
+    let range = line.match_at(query, at.grapheme_idx)?;
+    let replacement = query.expand_replacement(
+      &line,
+      line.grapheme_idx_to_byte_idx(at.grapheme_idx),
+      replacement,
+    );
+    // The cached line is edited in place once no one else holds it
+    drop(line);
+
+    self.delete_recorded(
+      at,
+      Location {
+        line_idx: at.line_idx,
+        grapheme_idx: range.end,
+      },
+    );
+    Some(self.insert_recorded(&replacement, at))
+  }
+
+  /// Delete all text between the two locations
+  pub fn delete_range(&mut self, from: Location, to: Location) {
+    self.delete_recorded(from, to);
+  }
+
+  // region: history
+
+  /// Start a new undo step, or continue the last one if it's of the same kind
+  pub fn begin_transaction(&mut self, kind: TransactionKind, caret: Location) {
+    self.history.begin_transaction(kind, caret);
+  }
+
+  pub fn end_transaction(&mut self, caret: Location) {
+    self.history.end_transaction(caret);
+  }
+
+  /// Revert the last undo step, returns the caret location from before that step
+  pub fn undo(&mut self) -> Option<Location> {
+    let transaction = self.history.pop_undo()?;
+    for change in transaction.changes.iter().rev() {
+      match change {
+        Change::Insert { at, text } => {
+          self.remove_text(*at, Self::end_of_text(*at, text));
+        }
+        Change::Delete { at, text } => {
+          self.insert_text(text, *at);
+        }
+      }
+    }
+    let caret = transaction.caret_before;
+    self.history.push_redo(transaction);
+    self.dirty = !self.history.is_at_saved_state();
+    Some(caret)
+  }
+
+  /// Reapply the last undone step, returns the caret location from after that step
+  pub fn redo(&mut self) -> Option<Location> {
+    let transaction = self.history.pop_redo()?;
+    for change in &transaction.changes {
+      match change {
+        Change::Insert { at, text } => {
+          self.insert_text(text, *at);
+        }
+        Change::Delete { at, text } => {
+          self.remove_text(*at, Self::end_of_text(*at, text));
+        }
+      }
+    }
+    let caret = transaction.caret_after;
+    self.history.push_undo(transaction);
+    self.dirty = !self.history.is_at_saved_state();
+    Some(caret)
+  }
+
+  /// Insert text and record the change in the history
+  fn insert_recorded(&mut self, text: &str, at: Location) -> Location {
+    // Inserting below the last line is the same as inserting after a line break at the end of the last line.
+    // In an empty document, it is the same as inserting the text with a line break of its own.
+    // Normalizing it this way makes the change revertible.
+    let was_empty = self.is_empty();
+    let (at, normalized) = match self.height().checked_sub(1) {
+      Some(last_line_idx) if at.line_idx == self.height() => (
+        Location {
+          line_idx: last_line_idx,
+          grapheme_idx: self.grapheme_count(last_line_idx),
+        },
+        format!("\n{text}"),
+      ),
+      None => (Location::default(), format!("{text}\n")),
+      _ => (at, text.to_string()),
+    };
+
+    let end = self.insert_text(&normalized, at);
+    if !normalized.is_empty() {
+      self.history.record(Change::Insert {
+        at,
+        text: normalized,
+      });
       self.dirty = true;
     }
-    // If we are in middle of document
-    else if let Some(line) = self.lines.get_mut(at.line_idx) {
-      // Split the current line
-      let new = line.split(at.grapheme_idx);
-      // Add the splitted part as next line
-      self.lines.insert(at.line_idx.saturating_add(1), new);
+    // The line break added to an empty document comes after the inserted text
+    if was_empty {
+      Self::end_of_text(at, text)
+    } else {
+      end
+    }
+  }
+
+  /// Delete text and record the change in the history
+  fn delete_recorded(&mut self, from: Location, to: Location) {
+    // The line break of the last line is kept
+    let to = min(to, self.end_location());
+    let text = self.remove_text(from, to);
+    if !text.is_empty() {
+      self.history.record(Change::Delete { at: from, text });
       self.dirty = true;
     }
   }
 
-  pub fn search_forward(&self, query: &str, from: Location) -> Option<Location> {
+  // endregion
+
+  // region: raw text manipulation
+
+  /// Get the location right after the given text, if it were inserted at `at`
+  fn end_of_text(at: Location, text: &str) -> Location {
+    let mut end = at;
+    for (idx, segment) in text.split('\n').enumerate() {
+      let grapheme_count = segment.graphemes(true).count();
+      if idx == 0 {
+        end.grapheme_idx = end.grapheme_idx.saturating_add(grapheme_count);
+      } else {
+        end.line_idx = end.line_idx.saturating_add(1);
+        end.grapheme_idx = grapheme_count;
+      }
+    }
+    end
+  }
+
+  /// Index of the char at the given location within the text.
+  /// Locations below the last line map to the end of the text.
+  fn char_idx(&self, at: Location) -> usize {
+    let Some(line) = self.line(at.line_idx) else {
+      return self.text.len_chars();
+    };
+    let byte_idx = line.grapheme_idx_to_byte_idx(min(at.grapheme_idx, line.grapheme_count()));
+    self
+      .text
+      .byte_to_char(self.text.line_to_byte(at.line_idx).saturating_add(byte_idx))
+  }
+
+  /// Location of the char at the given index within the text.
+  /// A char within a grapheme maps to the end of that grapheme.
+  fn location_of_char(&self, char_idx: usize) -> Location {
+    let line_idx = self.text.char_to_line(char_idx);
+    let grapheme_idx = self.line(line_idx).map_or(0, |line| {
+      let byte_idx = self
+        .text
+        .char_to_byte(char_idx)
+        .saturating_sub(self.text.line_to_byte(line_idx));
+      line.byte_idx_to_grapheme_idx(min(byte_idx, line.len()))
+    });
+    Location {
+      line_idx,
+      grapheme_idx,
+    }
+  }
+
+  /// Insert a (possibly multi line) text at the given location.
+  /// Returns the location right after the inserted text.
+  fn insert_text(&mut self, text: &str, at: Location) -> Location {
+    debug_assert!(at.line_idx <= self.height());
+    self.revision = self.revision.wrapping_add(1);
+
+    let inserted_lines = text.matches('\n').count().saturating_add(1);
+    let char_idx = self.char_idx(at);
+    self.invalidate_syntax_highlighting(at.line_idx, 1, inserted_lines);
+    self.text.insert(char_idx, text);
+    let mut line_cache = self.line_cache.borrow_mut();
+    if inserted_lines == 1 {
+      line_cache.edit_line(at.line_idx, |line| {
+        line.insert_str(text, min(at.grapheme_idx, line.grapheme_count()));
+      });
+    } else {
+      line_cache.replace_lines(at.line_idx, 1, inserted_lines);
+    }
+    drop(line_cache);
+    self.location_of_char(char_idx.saturating_add(text.chars().count()))
+  }
+
+  /// Remove the text between the two locations. Returns the removed text.
+  fn remove_text(&mut self, from: Location, to: Location) -> String {
+    debug_assert!(from <= to);
+    self.revision = self.revision.wrapping_add(1);
+
+    if from >= to {
+      return String::new();
+    }
+
+    let removed_lines = to.line_idx.saturating_sub(from.line_idx).saturating_add(1);
+    self.invalidate_syntax_highlighting(from.line_idx, removed_lines, 1);
+
+    let range = self.char_idx(from)..self.char_idx(to);
+    let removed = self.text.slice(range.clone()).to_string();
+    self.text.remove(range);
+    let mut line_cache = self.line_cache.borrow_mut();
+    if removed_lines == 1 {
+      line_cache.edit_line(from.line_idx, |line| {
+        line.delete_range(from.grapheme_idx..to.grapheme_idx);
+      });
+    } else {
+      line_cache.replace_lines(from.line_idx, removed_lines, 1);
+    }
+    drop(line_cache);
+    removed
+  }
+
+  /// Location right after the last grapheme of the document
+  fn end_location(&self) -> Location {
+    let line_idx = self.height().saturating_sub(1);
+    Location {
+      line_idx,
+      grapheme_idx: self.grapheme_count(line_idx),
+    }
+  }
+
+  // endregion
+
+  /// Returns the lines which contain matches of the query, along with the number of matches in each
+  pub fn count_matches(&self, query: &SearchQuery) -> Vec<(LineIdx, usize)> {
+    self
+      .text
+      .lines()
+      .take(self.height())
+      .enumerate()
+      .filter_map(|(idx, line)| {
+        let count = Self::count_line_matches(query, &Self::without_line_break(line));
+        (count > 0).then_some((idx, count))
+      })
+      .collect()
+  }
+
+  pub fn count_matches_in_line(&self, query: &SearchQuery, idx: LineIdx) -> usize {
+    self
+      .line_str(idx)
+      .map_or(0, |line| Self::count_line_matches(query, &line))
+  }
+
+  fn count_line_matches(query: &SearchQuery, line: &str) -> usize {
+    if !query.is_match(line) {
+      return 0;
+    }
+    let line = Line::from(line);
+    line.find_all(query, 0..line.len()).len()
+  }
+
+  /// Returns the number of matches within the line at `at` which start before it,
+  /// and whether a match starts exactly at `at`
+  pub fn matches_before(&self, query: &SearchQuery, at: Location) -> (usize, bool) {
+    self
+      .matching_line(query, at.line_idx)
+      .map_or((0, false), |line| {
+        let matches = line.find_all(query, 0..line.len());
+        let before = matches
+          .iter()
+          .take_while(|(_, grapheme_idx)| *grapheme_idx < at.grapheme_idx)
+          .count();
+        let is_at = matches
+          .get(before)
+          .is_some_and(|(_, grapheme_idx)| *grapheme_idx == at.grapheme_idx);
+        (before, is_at)
+      })
+  }
+
+  pub fn search_forward(&self, query: &SearchQuery, from: Location) -> Option<Location> {
     if query.is_empty() {
       return None;
     }
 
     let mut is_first = true;
 
-    for (line_idx, line) in self
-      .lines
-      .iter()
-      .enumerate()
-      .cycle()
-      .skip(from.line_idx)
-      .take(self.lines.len().saturating_add(1))
-    //taking one more, to search the current line twice (once from the middle, once from the start)
-    {
+    // The current line is searched twice (once from the middle, once from the start)
+    for line_idx in (from.line_idx..self.height()).chain(0..=from.line_idx) {
       let from_grapheme_idx = if is_first {
         is_first = false;
         from.grapheme_idx
@@ -191,49 +743,108 @@ impl Buffer {
         0
       };
 
-      if let Some(grapheme_idx) = line.search_forward(query, from_grapheme_idx) {
+      if let Some(line) = self.matching_line(query, line_idx)
+        && let Some(grapheme_idx) = line.search_forward(query, from_grapheme_idx)
+      {
         return Some(Location {
-          grapheme_idx,
           line_idx,
+          grapheme_idx,
         });
       }
     }
     None
   }
 
-  pub fn search_backward(&self, query: &str, from: Location) -> Option<Location> {
+  pub fn search_backward(&self, query: &SearchQuery, from: Location) -> Option<Location> {
     if query.is_empty() {
       return None;
     }
     let mut is_first = true;
-    for (line_idx, line) in self
-      .lines
-      .iter()
-      .enumerate()
+    for line_idx in (0..=from.line_idx)
       .rev()
-      .cycle()
-      .skip(
-        self
-          .lines
-          .len()
-          .saturating_sub(from.line_idx)
-          .saturating_sub(1),
-      )
-      .take(self.lines.len().saturating_add(1))
+      .chain((from.line_idx..self.height()).rev())
     {
-      let from_grapheme_idx = if is_first {
-        is_first = false;
-        from.grapheme_idx
-      } else {
-        line.grapheme_count()
+      let first_grapheme_idx = is_first.then_some(from.grapheme_idx);
+      is_first = false;
+
+      let Some(line) = self.matching_line(query, line_idx) else {
+        continue;
       };
+      let from_grapheme_idx = first_grapheme_idx.unwrap_or_else(|| line.grapheme_count());
       if let Some(grapheme_idx) = line.search_backward(query, from_grapheme_idx) {
         return Some(Location {
-          grapheme_idx,
           line_idx,
+          grapheme_idx,
         });
       }
     }
     None
   }
 }
+
+impl SwapSource for Buffer {
+  fn unsaved_text(&self) -> Option<(usize, String)> {
+    self.is_dirty().then(|| (self.revision, self.contents()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A buffer with the text, recording the edits made to it as a single change
+  fn buffer(text: &str) -> Buffer {
+    let mut buffer = Buffer::default();
+    buffer.replace_contents(text, Location::default());
+    buffer.begin_transaction(TransactionKind::Other, Location::default());
+    buffer
+  }
+
+  const fn at(line_idx: LineIdx, grapheme_idx: GraphemeIdx) -> Location {
+    Location {
+      line_idx,
+      grapheme_idx,
+    }
+  }
+
+  /// Checks that every line, cached or not, has the text of the rope
+  fn assert_lines_match_text(buffer: &Buffer) {
+    for idx in 0..buffer.height() {
+      let line = buffer.line(idx).expect("line within the buffer");
+      assert_eq!(
+        &**line,
+        buffer.line_str(idx).expect("line within the buffer")
+      );
+    }
+  }
+
+  #[test]
+  fn cached_lines_follow_edits() {
+    let mut buffer = buffer("one\ntwo\nthree\n");
+    assert_lines_match_text(&buffer);
+
+    buffer.insert_char('x', at(1, 1));
+    assert_lines_match_text(&buffer);
+    buffer.delete(at(0, 0));
+    assert_lines_match_text(&buffer);
+    buffer.insert_newline(at(1, 2));
+    assert_lines_match_text(&buffer);
+    buffer.delete(at(0, 2));
+    assert_lines_match_text(&buffer);
+    buffer.insert_text_at("a\nb", at(2, 3));
+    assert_lines_match_text(&buffer);
+    buffer.delete_range(at(0, 1), at(2, 1));
+    assert_lines_match_text(&buffer);
+    assert_eq!(buffer.contents(), "nhra\nbee\n");
+  }
+
+  #[test]
+  fn char_indices_and_locations_agree() {
+    let buffer = buffer("añb\ne\u{301}f\n");
+    assert_eq!(buffer.char_idx(at(0, 2)), 2);
+    assert_eq!(buffer.char_idx(at(1, 1)), 6);
+    assert_eq!(buffer.location_of_char(6), at(1, 1));
+    // A char within a grapheme maps to the end of that grapheme
+    assert_eq!(buffer.location_of_char(5), at(1, 1));
+  }
+}
@@ -3,30 +3,62 @@ use std::{
   path::{Path, PathBuf},
 };
 
-use crate::editor::filetype::FileType;
+use crate::editor::{
+  encoding::Encoding, filetype::FileType, indentation::Indentation, lineending::LineEnding,
+  uicomponents::view::filestamp::FileStamp,
+};
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct FileInfo {
   path: Option<PathBuf>,
   file_type: FileType,
+  indentation: Indentation,
+  encoding: Encoding,
+  line_ending: LineEnding,
+  /// Whether the last line is terminated by a line ending
+  has_final_newline: bool,
+  /// Whether the file starts with the byte order mark of its encoding
+  has_bom: bool,
+  /// Version of the file on disk that was last loaded or saved
+  stamp: Option<FileStamp>,
+}
+
+impl Default for FileInfo {
+  fn default() -> Self {
+    Self {
+      path: None,
+      file_type: FileType::default(),
+      indentation: Indentation::default(),
+      encoding: Encoding::default(),
+      line_ending: LineEnding::default(),
+      has_final_newline: true,
+      has_bom: false,
+      stamp: None,
+    }
+  }
 }
 
 impl FileInfo {
   pub fn from(file_name: &str) -> Self {
     let path = PathBuf::from(file_name);
-
-    let file_type = if path
-      .extension()
-      .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"))
-    {
-      FileType::Rust
-    } else {
-      FileType::Text
-    };
+    let file_type = FileType::detect(&path);
 
     Self {
       path: Some(path),
       file_type,
+      indentation: Indentation::for_file_type(file_type),
+      ..Self::default()
+    }
+  }
+
+  /// Info for saving the same text under another name, keeping its format
+  pub fn with_path(&self, file_name: &str) -> Self {
+    Self {
+      encoding: self.encoding,
+      line_ending: self.line_ending,
+      has_final_newline: self.has_final_newline,
+      has_bom: self.has_bom,
+      ..Self::from(file_name)
     }
   }
 
@@ -41,6 +73,54 @@ impl FileInfo {
   pub const fn get_file_type(&self) -> FileType {
     self.file_type
   }
+
+  pub const fn get_indentation(&self) -> Indentation {
+    self.indentation
+  }
+
+  pub const fn set_indentation(&mut self, indentation: Indentation) {
+    self.indentation = indentation;
+  }
+
+  pub const fn get_encoding(&self) -> Encoding {
+    self.encoding
+  }
+
+  pub const fn set_encoding(&mut self, encoding: Encoding) {
+    self.encoding = encoding;
+  }
+
+  pub const fn get_line_ending(&self) -> LineEnding {
+    self.line_ending
+  }
+
+  pub const fn set_line_ending(&mut self, line_ending: LineEnding) {
+    self.line_ending = line_ending;
+  }
+
+  pub const fn has_final_newline(&self) -> bool {
+    self.has_final_newline
+  }
+
+  pub const fn set_final_newline(&mut self, has_final_newline: bool) {
+    self.has_final_newline = has_final_newline;
+  }
+
+  pub const fn get_stamp(&self) -> Option<FileStamp> {
+    self.stamp
+  }
+
+  pub const fn set_stamp(&mut self, stamp: Option<FileStamp>) {
+    self.stamp = stamp;
+  }
+
+  pub const fn has_bom(&self) -> bool {
+    self.has_bom
+  }
+
+  pub const fn set_bom(&mut self, has_bom: bool) {
+    self.has_bom = has_bom;
+  }
 }
 
 impl Display for FileInfo {
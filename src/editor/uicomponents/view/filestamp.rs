@@ -0,0 +1,112 @@
+use std::{
+  fs::{Metadata, metadata, read},
+  hash::{DefaultHasher, Hash, Hasher},
+  path::Path,
+  time::SystemTime,
+};
+
+/// Identifies a version of a file on disk, to notice when someone else changes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+  modified: Option<SystemTime>,
+  len: u64,
+  hash: u64,
+}
+
+impl FileStamp {
+  /// Stamp of a file whose contents were just read or written
+  pub fn from(contents: &[u8], metadata: &Metadata) -> Self {
+    Self {
+      modified: metadata.modified().ok(),
+      len: metadata.len(),
+      hash: hash(contents),
+    }
+  }
+
+  /// Whether the file at `path` is still the version this stamp was taken from.
+  /// Only when modification time or size differ is the file read, so that merely touching it doesn't count as a change.
+  pub fn matches(&self, path: &Path) -> bool {
+    let Ok(metadata) = metadata(path) else {
+      // A file that was deleted can't be overwritten by accident
+      return true;
+    };
+    if metadata.modified().ok() == self.modified && metadata.len() == self.len {
+      return true;
+    }
+    self.matches_contents(path)
+  }
+
+  /// Whether the file at `path` still has the contents this stamp was taken from.
+  /// Unlike `matches`, this always reads the file, to also notice a change within the same second which kept the size,
+  /// before it is overwritten.
+  pub fn matches_contents(&self, path: &Path) -> bool {
+    if !path.exists() {
+      return true;
+    }
+    read(path).is_ok_and(|contents| hash(&contents) == self.hash)
+  }
+}
+
+fn hash(contents: &[u8]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  contents.hash(&mut hasher);
+  hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::{
+    env,
+    fs::{File, remove_file, write},
+    path::PathBuf,
+    process,
+    time::Duration,
+  };
+
+  /// A file of its own for each test, along with the stamp of its contents
+  fn stamped_file(name: &str, contents: &str) -> (PathBuf, FileStamp) {
+    let path = env::temp_dir().join(format!("hecto-filestamp-{}-{name}", process::id()));
+    write(&path, contents).expect("Could not write the test file");
+    let metadata = metadata(&path).expect("File exists");
+    (
+      path.clone(),
+      FileStamp::from(contents.as_bytes(), &metadata),
+    )
+  }
+
+  #[test]
+  fn notices_changed_contents() {
+    let (path, stamp) = stamped_file("changed", "old contents");
+    write(&path, "new contents").expect("Could not write");
+    // Within the same second the modification time and size may not tell
+    assert!(!stamp.matches_contents(&path));
+    write(&path, "longer new contents").expect("Could not write");
+    assert!(!stamp.matches(&path));
+    let _ = remove_file(&path);
+  }
+
+  #[test]
+  fn touching_is_no_change() {
+    let (path, stamp) = stamped_file("touched", "contents");
+    let file = File::options()
+      .write(true)
+      .open(&path)
+      .expect("File exists");
+    file
+      .set_modified(SystemTime::now() + Duration::from_mins(1))
+      .expect("Could not touch the file");
+    assert!(stamp.matches(&path));
+    assert!(stamp.matches_contents(&path));
+    let _ = remove_file(&path);
+  }
+
+  #[test]
+  fn deleting_is_no_change() {
+    let (path, stamp) = stamped_file("deleted", "contents");
+    remove_file(&path).expect("Could not delete");
+    assert!(stamp.matches(&path));
+    assert!(stamp.matches_contents(&path));
+  }
+}
@@ -1,5 +1,6 @@
 mod rustsyntaxhighlighter;
 mod searchresulthighlighter;
+mod selectionhighlighter;
 mod syntaxhighlighter;
 
 use crate::{
@@ -7,46 +8,57 @@ use crate::{
     annotation::Annotation,
     filetype::FileType,
     line::Line,
+    searchquery::SearchQuery,
     uicomponents::view::highlighter::{
       rustsyntaxhighlighter::RustSyntaxHighlighter,
-      searchresulthighlighter::SearchResultHighlighter, syntaxhighlighter::SyntaxHighlighter,
+      searchresulthighlighter::SearchResultHighlighter, selectionhighlighter::SelectionHighlighter,
     },
   },
   prelude::{LineIdx, Location},
 };
 
-fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
+use std::ops::Range;
+
+pub use syntaxhighlighter::SyntaxHighlighter;
+
+pub fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
   match file_type {
     FileType::Rust => Some(Box::<RustSyntaxHighlighter>::default()),
-    FileType::Text => None,
+    FileType::Go | FileType::Makefile | FileType::Text => None,
   }
 }
 
+// clippy::struct_field_names: each field holds a highlighter of a different kind, naming them after it is the clearest option.
 #[derive(Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Highlighter<'a> {
-  syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
+  /// Owned by the buffer, so that it survives between frames
+  syntax_highlighter: Option<&'a dyn SyntaxHighlighter>,
   search_result_highlighter: Option<SearchResultHighlighter<'a>>,
+  selection_highlighter: Option<SelectionHighlighter>,
 }
 
 impl<'a> Highlighter<'a> {
   pub fn new(
-    matched_word: Option<&'a str>,
+    query: Option<&'a SearchQuery>,
     selected_match: Option<Location>,
-    file_type: FileType,
+    selection: Option<Range<Location>>,
+    syntax_highlighter: Option<&'a dyn SyntaxHighlighter>,
   ) -> Self {
     let search_result_highlighter =
-      matched_word.map(|matched_word| SearchResultHighlighter::new(matched_word, selected_match));
+      query.map(|query| SearchResultHighlighter::new(query, selected_match));
 
     Self {
       search_result_highlighter,
-      syntax_highlighter: create_syntax_highlighter(file_type),
+      selection_highlighter: selection.map(SelectionHighlighter::new),
+      syntax_highlighter,
     }
   }
 
   pub fn get_annotations(&self, idx: LineIdx) -> Vec<Annotation> {
     let mut result = Vec::new();
 
-    if let Some(syntax_highlighter) = &self.syntax_highlighter
+    if let Some(syntax_highlighter) = self.syntax_highlighter
       && let Some(annotation) = syntax_highlighter.get_annotations(idx)
     {
       result.extend(annotation.iter().copied());
@@ -57,16 +69,24 @@ impl<'a> Highlighter<'a> {
     {
       result.extend(annotations.iter().copied());
     }
+
+    // Selection is added last, so it takes precedence over all other annotations
+    if let Some(selection_highlighter) = &self.selection_highlighter
+      && let Some(annotations) = selection_highlighter.get_annotations(idx)
+    {
+      result.extend(annotations.iter().copied());
+    }
     result
   }
 
+  /// Highlights search results and the selection, syntax highlighting is kept up to date by the buffer
   pub fn highlight(&mut self, idx: LineIdx, line: &Line) {
-    if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
-      syntax_highlighter.highlight(idx, line);
-    }
-
     if let Some(search_result_highlighter) = &mut self.search_result_highlighter {
       search_result_highlighter.highlight(idx, line);
     }
+
+    if let Some(selection_highlighter) = &mut self.selection_highlighter {
+      selection_highlighter.highlight(idx, line);
+    }
   }
 }
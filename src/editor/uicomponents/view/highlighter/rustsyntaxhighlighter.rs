@@ -6,6 +6,8 @@ use crate::{
   prelude::*,
 };
 
+use std::{cmp::min, iter::repeat_n};
+
 use unicode_segmentation::UnicodeSegmentation;
 
 const KEYWORDS: [&str; 52] = [
@@ -69,14 +71,48 @@ const TYPES: [&str; 22] = [
 
 const KNOWN_VALUES: [&str; 6] = ["Some", "None", "true", "false", "Ok", "Err"];
 
+/// What is carried over from one line to the next
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+struct State {
+  ml_comment_balance: usize,
+  in_ml_string: bool,
+}
+
+#[derive(Clone)]
+struct HighlightedLine {
+  annotations: Vec<Annotation>,
+  /// State the line was highlighted in
+  start: State,
+  /// State after the line, which the next line was highlighted in
+  end: State,
+}
+
 #[derive(Default)]
 pub struct RustSyntaxHighlighter {
-  highlights: Vec<Vec<Annotation>>,
+  /// Highlighting of each line, `None` for lines which changed since they were highlighted
+  highlights: Vec<Option<HighlightedLine>>,
+  /// Number of lines at the start whose highlighting is up to date
+  highlighted_until: LineIdx,
   ml_comment_balance: usize,
   in_ml_string: bool,
 }
 
 impl RustSyntaxHighlighter {
+  fn state_before(&self, idx: LineIdx) -> State {
+    idx
+      .checked_sub(1)
+      .and_then(|previous| self.highlights.get(previous))
+      .and_then(Option::as_ref)
+      .map_or_else(State::default, |highlighted| highlighted.end)
+  }
+
+  const fn state(&self) -> State {
+    State {
+      ml_comment_balance: self.ml_comment_balance,
+      in_ml_string: self.in_ml_string,
+    }
+  }
+
   fn annotate_string(&mut self, string: &str) -> Option<Annotation> {
     let mut chars = string.char_indices();
     while let Some((idx, char)) = chars.next() {
@@ -171,7 +207,11 @@ impl RustSyntaxHighlighter {
 
 impl SyntaxHighlighter for RustSyntaxHighlighter {
   fn highlight(&mut self, idx: LineIdx, line: &Line) {
-    debug_assert_eq!(idx, self.highlights.len());
+    debug_assert_eq!(idx, self.highlighted_until);
+
+    let start = self.state_before(idx);
+    self.ml_comment_balance = start.ml_comment_balance;
+    self.in_ml_string = start.in_ml_string;
 
     let mut result = Vec::new();
 
@@ -206,11 +246,59 @@ impl SyntaxHighlighter for RustSyntaxHighlighter {
         }
       }
     }
-    self.highlights.push(result);
+
+    let highlighted = Some(HighlightedLine {
+      annotations: result,
+      start,
+      end: self.state(),
+    });
+    if let Some(entry) = self.highlights.get_mut(idx) {
+      *entry = highlighted;
+    } else {
+      self.highlights.push(highlighted);
+    }
+    self.highlighted_until = idx.saturating_add(1);
   }
 
   fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
-    self.highlights.get(idx)
+    if idx >= self.highlighted_until {
+      return None;
+    }
+    self
+      .highlights
+      .get(idx)
+      .and_then(Option::as_ref)
+      .map(|highlighted| &highlighted.annotations)
+  }
+
+  fn highlighted_until(&self) -> LineIdx {
+    self.highlighted_until
+  }
+
+  fn reuse(&mut self, idx: LineIdx) -> bool {
+    debug_assert_eq!(idx, self.highlighted_until);
+
+    let start = self.state_before(idx);
+    let is_up_to_date = self
+      .highlights
+      .get(idx)
+      .and_then(Option::as_ref)
+      .is_some_and(|highlighted| highlighted.start == start);
+    if is_up_to_date {
+      self.highlighted_until = idx.saturating_add(1);
+    }
+    is_up_to_date
+  }
+
+  fn invalidate(&mut self, idx: LineIdx, removed: usize, inserted: usize) {
+    let end = idx.saturating_add(removed);
+    if end <= self.highlights.len() {
+      self.highlights.splice(idx..end, repeat_n(None, inserted));
+    } else {
+      // Nothing after the edit was highlighted yet
+      self.highlights.truncate(idx);
+    }
+    self.highlighted_until = min(self.highlighted_until, idx);
   }
 }
 
@@ -376,3 +464,75 @@ fn is_type(word: &str) -> bool {
 fn is_known_value(word: &str) -> bool {
   KNOWN_VALUES.contains(&word)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Brings the highlighting up to date like the buffer does, returns the lines which had to be highlighted again
+  fn update(highlighter: &mut RustSyntaxHighlighter, lines: &[&str]) -> Vec<LineIdx> {
+    let mut changed = Vec::new();
+    for (idx, line) in lines
+      .iter()
+      .enumerate()
+      .skip(highlighter.highlighted_until())
+    {
+      if !highlighter.reuse(idx) {
+        highlighter.highlight(idx, &Line::from(line));
+        changed.push(idx);
+      }
+    }
+    changed
+  }
+
+  fn is_comment(highlighter: &RustSyntaxHighlighter, idx: LineIdx) -> bool {
+    highlighter
+      .get_annotations(idx)
+      .expect("line is highlighted")
+      .iter()
+      .any(|annotation| annotation.annotation_type == AnnotationType::Comment)
+  }
+
+  const LINES: [&str; 5] = ["let a = 1;", "let b = 2;", "let c = 3;", "*/", "let d = 4;"];
+
+  fn highlighted() -> RustSyntaxHighlighter {
+    let mut highlighter = RustSyntaxHighlighter::default();
+    assert_eq!(update(&mut highlighter, &LINES), vec![0, 1, 2, 3, 4]);
+    highlighter
+  }
+
+  #[test]
+  fn unchanged_state_stops_after_the_edited_line() {
+    let mut highlighter = highlighted();
+    let mut lines = LINES;
+    lines[1] = "let b = 22;";
+    highlighter.invalidate(1, 1, 1);
+    assert_eq!(update(&mut highlighter, &lines), vec![1]);
+  }
+
+  #[test]
+  fn opening_a_comment_highlights_down_to_its_end() {
+    let mut highlighter = highlighted();
+    let mut lines = LINES;
+    lines[1] = "/* let b = 2;";
+    highlighter.invalidate(1, 1, 1);
+    assert_eq!(update(&mut highlighter, &lines), vec![1, 2, 3]);
+    assert!(is_comment(&highlighter, 2));
+    assert!(!is_comment(&highlighter, 4));
+
+    lines[1] = "let b = 2;";
+    highlighter.invalidate(1, 1, 1);
+    assert_eq!(update(&mut highlighter, &lines), vec![1, 2, 3]);
+    assert!(!is_comment(&highlighter, 2));
+  }
+
+  #[test]
+  fn inserted_lines_move_the_highlighting_after_them() {
+    let mut highlighter = highlighted();
+    highlighter.invalidate(1, 0, 1);
+    let lines = [LINES[0], "/* */", LINES[1], LINES[2], LINES[3], LINES[4]];
+    assert_eq!(update(&mut highlighter, &lines), vec![1]);
+    assert!(is_comment(&highlighter, 1));
+    assert!(!is_comment(&highlighter, 3));
+  }
+}
@@ -1,6 +1,6 @@
 use crate::{
   editor::{
-    annotation::Annotation, annotationtype::AnnotationType, line::Line,
+    annotation::Annotation, annotationtype::AnnotationType, line::Line, searchquery::SearchQuery,
     uicomponents::view::highlighter::syntaxhighlighter::SyntaxHighlighter,
   },
   prelude::{LineIdx, Location},
@@ -8,47 +8,42 @@ use crate::{
 
 use std::collections::HashMap;
 
-#[derive(Default)]
 pub struct SearchResultHighlighter<'a> {
-  matched_word: &'a str,
+  query: &'a SearchQuery,
   selected_match: Option<Location>,
   highlights: HashMap<LineIdx, Vec<Annotation>>,
 }
 
 impl<'a> SearchResultHighlighter<'a> {
-  pub fn new(matched_word: &'a str, selected_match: Option<Location>) -> Self {
+  pub fn new(query: &'a SearchQuery, selected_match: Option<Location>) -> Self {
     Self {
-      matched_word,
+      query,
       selected_match,
       highlights: HashMap::new(),
     }
   }
 
-  fn highlight_matched_words(&self, line: &Line, result: &mut Vec<Annotation>) {
-    if !self.matched_word.is_empty() {
-      line
-        .find_all(self.matched_word, 0..line.len())
-        .iter()
-        .for_each(|(start, _)| {
-          result.push(Annotation {
-            annotation_type: AnnotationType::Match,
-            start: *start,
-            end: start.saturating_add(self.matched_word.len()),
-          });
-        });
+  fn highlight_matched_words(&self, idx: LineIdx, line: &Line, result: &mut Vec<Annotation>) {
+    if self.query.is_empty() {
+      return;
     }
-  }
 
-  fn highlight_selected_match(&self, result: &mut Vec<Annotation>) {
-    if let Some(selected_match) = self.selected_match
-      && !self.matched_word.is_empty()
-    {
-      let start = selected_match.grapheme_idx;
+    for (range, grapheme_idx) in line.find_all(self.query, 0..line.len()) {
+      // The match at the caret is the currently selected one
+      let is_selected = self.selected_match
+        == Some(Location {
+          line_idx: idx,
+          grapheme_idx,
+        });
 
       result.push(Annotation {
-        annotation_type: AnnotationType::SelectedMatch,
-        start,
-        end: start.saturating_add(self.matched_word.len()),
+        annotation_type: if is_selected {
+          AnnotationType::SelectedMatch
+        } else {
+          AnnotationType::Match
+        },
+        start: range.start,
+        end: range.end,
       });
     }
   }
@@ -57,12 +52,7 @@ impl<'a> SearchResultHighlighter<'a> {
 impl SyntaxHighlighter for SearchResultHighlighter<'_> {
   fn highlight(&mut self, idx: LineIdx, line: &Line) {
     let mut result = Vec::new();
-    self.highlight_matched_words(line, &mut result);
-    if let Some(selected_match) = self.selected_match
-      && selected_match.line_idx == idx
-    {
-      self.highlight_selected_match(&mut result);
-    }
+    self.highlight_matched_words(idx, line, &mut result);
     self.highlights.insert(idx, result);
   }
 
@@ -0,0 +1,61 @@
+use crate::{
+  editor::{
+    annotation::Annotation, annotationtype::AnnotationType, line::Line,
+    uicomponents::view::highlighter::syntaxhighlighter::SyntaxHighlighter,
+  },
+  prelude::{LineIdx, Location},
+};
+
+use std::{collections::HashMap, ops::Range};
+
+#[derive(Default)]
+pub struct SelectionHighlighter {
+  selection: Range<Location>,
+  highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl SelectionHighlighter {
+  pub fn new(selection: Range<Location>) -> Self {
+    Self {
+      selection,
+      highlights: HashMap::new(),
+    }
+  }
+}
+
+impl SyntaxHighlighter for SelectionHighlighter {
+  fn highlight(&mut self, idx: LineIdx, line: &Line) {
+    let Range { start, end } = self.selection;
+    if idx < start.line_idx || idx > end.line_idx {
+      return;
+    }
+
+    // Lines between start and end of the selection are selected entirely.
+    // A selected line break is annotated as one byte past the end of the line.
+    let start_byte = if idx == start.line_idx {
+      line.grapheme_idx_to_byte_idx(start.grapheme_idx)
+    } else {
+      0
+    };
+    let end_byte = if idx == end.line_idx {
+      line.grapheme_idx_to_byte_idx(end.grapheme_idx)
+    } else {
+      line.len().saturating_add(1)
+    };
+
+    if start_byte < end_byte {
+      self.highlights.insert(
+        idx,
+        vec![Annotation {
+          annotation_type: AnnotationType::Selection,
+          start: start_byte,
+          end: end_byte,
+        }],
+      );
+    }
+  }
+
+  fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+    self.highlights.get(&idx)
+  }
+}
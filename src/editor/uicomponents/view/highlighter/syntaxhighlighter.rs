@@ -3,7 +3,23 @@ use crate::{
   prelude::LineIdx,
 };
 
+/// Highlights lines in order, from the first line of the document on.
+/// Highlighters which keep their highlighting across edits override the caching methods.
 pub trait SyntaxHighlighter {
   fn highlight(&mut self, idx: LineIdx, line: &Line);
   fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>>;
+
+  /// Number of lines at the start of the document whose highlighting is up to date
+  fn highlighted_until(&self) -> LineIdx {
+    0
+  }
+
+  /// Keeps the highlighting the line had before an edit, if the lines before it still end in the same state.
+  /// Returns false if the line has to be highlighted again.
+  fn reuse(&mut self, _idx: LineIdx) -> bool {
+    false
+  }
+
+  /// Forgets the highlighting of the `removed` lines starting at `idx`, which an edit replaced with `inserted` lines
+  fn invalidate(&mut self, _idx: LineIdx, _removed: usize, _inserted: usize) {}
 }
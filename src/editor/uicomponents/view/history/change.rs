@@ -0,0 +1,9 @@
+use crate::prelude::Location;
+
+/// A single primitive modification of the buffer.
+/// `text` may span multiple lines, separated by `\n`.
+#[derive(Debug, Clone)]
+pub enum Change {
+  Insert { at: Location, text: String },
+  Delete { at: Location, text: String },
+}
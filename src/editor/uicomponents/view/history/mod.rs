@@ -0,0 +1,244 @@
+mod change;
+mod transaction;
+mod transactionkind;
+
+pub use change::Change;
+pub use transaction::Transaction;
+pub use transactionkind::TransactionKind;
+
+use crate::prelude::Location;
+
+/// Keeps track of all changes applied to a buffer, to allow undo and redo
+#[derive(Default)]
+pub struct History {
+  undo_stack: Vec<Transaction>,
+  redo_stack: Vec<Transaction>,
+  /// Transaction currently collecting changes
+  pending: Option<Transaction>,
+  /// Prevents the transaction on top of the undo stack from absorbing further edits
+  sealed: bool,
+  next_id: usize,
+  /// Id of the transaction on top of the undo stack when the buffer was last saved.
+  /// `None` means the buffer was saved (or loaded) with an empty undo stack.
+  saved_id: Option<usize>,
+}
+
+impl History {
+  /// Start collecting changes. Continues the last transaction if it's of the same kind
+  /// and the caret didn't move in between, so that typing runs become a single undo step.
+  pub fn begin_transaction(&mut self, kind: TransactionKind, caret: Location) {
+    debug_assert!(self.pending.is_none(), "Transaction already in progress");
+
+    let can_merge = !self.sealed
+      && kind != TransactionKind::Other
+      && self.redo_stack.is_empty()
+      && self
+        .undo_stack
+        .last()
+        .is_some_and(|last| last.kind == kind && last.caret_after == caret);
+
+    self.pending = if can_merge {
+      self.undo_stack.pop()
+    } else {
+      let id = self.next_id;
+      self.next_id = self.next_id.saturating_add(1);
+      Some(Transaction {
+        id,
+        kind,
+        changes: Vec::new(),
+        caret_before: caret,
+        caret_after: caret,
+      })
+    };
+    self.sealed = false;
+  }
+
+  /// Records a change into the pending transaction
+  pub fn record(&mut self, change: Change) {
+    debug_assert!(self.pending.is_some(), "Recording without a transaction");
+
+    if let Some(transaction) = &mut self.pending {
+      transaction.changes.push(change);
+      // A new change invalidates everything which has been undone before
+      self.redo_stack.clear();
+    }
+  }
+
+  /// Finish collecting changes. Transactions without any change are dropped.
+  pub fn end_transaction(&mut self, caret: Location) {
+    if let Some(mut transaction) = self.pending.take()
+      && !transaction.changes.is_empty()
+    {
+      transaction.caret_after = caret;
+      self.undo_stack.push(transaction);
+    }
+  }
+
+  /// Prevents further edits from being merged into the last transaction
+  pub const fn seal(&mut self) {
+    self.sealed = true;
+  }
+
+  pub fn pop_undo(&mut self) -> Option<Transaction> {
+    self.seal();
+    self.undo_stack.pop()
+  }
+
+  pub fn push_undo(&mut self, transaction: Transaction) {
+    self.undo_stack.push(transaction);
+  }
+
+  pub fn pop_redo(&mut self) -> Option<Transaction> {
+    self.seal();
+    self.redo_stack.pop()
+  }
+
+  pub fn push_redo(&mut self, transaction: Transaction) {
+    self.redo_stack.push(transaction);
+  }
+
+  /// Remember the current state as the saved one
+  pub fn mark_saved(&mut self) {
+    self.seal();
+    self.saved_id = self.current_id();
+  }
+
+  /// Returns true if the buffer is in the same state as when it was last saved
+  pub fn is_at_saved_state(&self) -> bool {
+    self.current_id() == self.saved_id
+  }
+
+  fn current_id(&self) -> Option<usize> {
+    self.undo_stack.last().map(|transaction| transaction.id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn at(grapheme_idx: usize) -> Location {
+    Location {
+      line_idx: 0,
+      grapheme_idx,
+    }
+  }
+
+  /// Records typing a character at the given column, leaving the caret after it
+  fn type_char(history: &mut History, col: usize) {
+    history.begin_transaction(TransactionKind::Typing, at(col));
+    history.record(Change::Insert {
+      at: at(col),
+      text: String::from("x"),
+    });
+    history.end_transaction(at(col.saturating_add(1)));
+  }
+
+  #[test]
+  fn typing_run_is_a_single_step() {
+    let mut history = History::default();
+    for col in 0..3 {
+      type_char(&mut history, col);
+    }
+
+    let transaction = history.pop_undo().expect("typing was recorded");
+    assert_eq!(transaction.changes.len(), 3);
+    assert_eq!(transaction.caret_before, at(0));
+    assert_eq!(transaction.caret_after, at(3));
+    assert!(history.pop_undo().is_none());
+  }
+
+  #[test]
+  fn moving_the_caret_ends_a_typing_run() {
+    let mut history = History::default();
+    type_char(&mut history, 0);
+    type_char(&mut history, 5);
+
+    assert_eq!(history.pop_undo().map(|t| t.changes.len()), Some(1));
+    assert_eq!(history.pop_undo().map(|t| t.changes.len()), Some(1));
+  }
+
+  #[test]
+  fn other_kinds_are_never_merged() {
+    let mut history = History::default();
+    type_char(&mut history, 0);
+    history.begin_transaction(TransactionKind::Deleting, at(1));
+    history.record(Change::Delete {
+      at: at(0),
+      text: String::from("x"),
+    });
+    history.end_transaction(at(0));
+    for _ in 0..2 {
+      history.begin_transaction(TransactionKind::Other, at(0));
+      history.record(Change::Insert {
+        at: at(0),
+        text: String::from("\n"),
+      });
+      history.end_transaction(at(0));
+    }
+
+    let kinds: Vec<_> = std::iter::from_fn(|| history.pop_undo())
+      .map(|transaction| transaction.kind)
+      .collect();
+    assert_eq!(
+      kinds,
+      [
+        TransactionKind::Other,
+        TransactionKind::Other,
+        TransactionKind::Deleting,
+        TransactionKind::Typing
+      ]
+    );
+  }
+
+  #[test]
+  fn sealing_ends_a_typing_run() {
+    let mut history = History::default();
+    type_char(&mut history, 0);
+    history.seal();
+    type_char(&mut history, 1);
+
+    assert_eq!(history.pop_undo().map(|t| t.changes.len()), Some(1));
+  }
+
+  #[test]
+  fn transaction_without_changes_is_dropped() {
+    let mut history = History::default();
+    history.begin_transaction(TransactionKind::Other, at(0));
+    history.end_transaction(at(0));
+
+    assert!(history.pop_undo().is_none());
+    assert!(history.is_at_saved_state());
+  }
+
+  #[test]
+  fn undoing_back_to_the_saved_state() {
+    let mut history = History::default();
+    type_char(&mut history, 0);
+    history.mark_saved();
+    assert!(history.is_at_saved_state());
+
+    type_char(&mut history, 1);
+    assert!(!history.is_at_saved_state());
+
+    let transaction = history.pop_undo().expect("typing was recorded");
+    history.push_redo(transaction);
+    assert!(history.is_at_saved_state());
+
+    let transaction = history.pop_redo().expect("undone typing can be redone");
+    history.push_undo(transaction);
+    assert!(!history.is_at_saved_state());
+  }
+
+  #[test]
+  fn undoing_past_the_saved_state() {
+    let mut history = History::default();
+    assert!(history.is_at_saved_state());
+    type_char(&mut history, 0);
+    history.mark_saved();
+
+    let transaction = history.pop_undo().expect("typing was recorded");
+    history.push_redo(transaction);
+    assert!(!history.is_at_saved_state());
+  }
+}
@@ -0,0 +1,17 @@
+use crate::{
+  editor::uicomponents::view::history::{change::Change, transactionkind::TransactionKind},
+  prelude::Location,
+};
+
+/// A group of changes which is undone and redone as a single step
+#[derive(Debug)]
+pub struct Transaction {
+  /// Unique id, used to track the saved state
+  pub id: usize,
+  pub kind: TransactionKind,
+  pub changes: Vec<Change>,
+  /// Caret location before the first change, restored on undo
+  pub caret_before: Location,
+  /// Caret location after the last change, restored on redo
+  pub caret_after: Location,
+}
@@ -0,0 +1,10 @@
+/// Determines whether consecutive edits may be grouped into a single undo step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+  /// Consecutive typed characters
+  Typing,
+  /// Consecutive deletions
+  Deleting,
+  /// Anything else, never grouped
+  Other,
+}
@@ -0,0 +1,62 @@
+use crate::{editor::line::Line, prelude::*};
+
+use std::{collections::VecDeque, rc::Rc};
+
+/// How many lines are kept, enough for every line on screen
+const MAX_CACHED_LINES: usize = 256;
+
+/// The lines of a buffer last turned into a `Line`, so that their graphemes and wrapping
+/// are not computed again on every caret move and drawn row.
+/// Edits patch the lines they touch, so that typing into a long line doesn't build it again.
+/// All lines are dropped once the tab width changes.
+#[derive(Default)]
+pub struct LineCache {
+  tab_width: ColIdx,
+  lines: VecDeque<(LineIdx, Rc<Line>)>,
+}
+
+impl LineCache {
+  /// The cached line, or the one made by `make`, which is then kept in place of the oldest line
+  pub fn get_or_insert_with(
+    &mut self,
+    idx: LineIdx,
+    tab_width: ColIdx,
+    make: impl FnOnce() -> Option<Line>,
+  ) -> Option<Rc<Line>> {
+    if self.tab_width != tab_width {
+      self.lines.clear();
+      self.tab_width = tab_width;
+    }
+    if let Some((_, line)) = self.lines.iter().find(|(line_idx, _)| *line_idx == idx) {
+      return Some(Rc::clone(line));
+    }
+
+    let line = Rc::new(make()?);
+    if self.lines.len() >= MAX_CACHED_LINES {
+      self.lines.pop_front();
+    }
+    self.lines.push_back((idx, Rc::clone(&line)));
+    Some(line)
+  }
+
+  /// Applies an edit of the text to the cached line, if it is cached
+  pub fn edit_line(&mut self, idx: LineIdx, edit: impl FnOnce(&mut Line)) {
+    if let Some((_, line)) = self.lines.iter_mut().find(|(line_idx, _)| *line_idx == idx) {
+      edit(Rc::make_mut(line));
+    }
+  }
+
+  /// Drops the lines from the index on which an edit replaced, and moves the ones after them
+  /// to where they are now
+  pub fn replace_lines(&mut self, idx: LineIdx, removed: usize, inserted: usize) {
+    let end = idx.saturating_add(removed);
+    self
+      .lines
+      .retain(|(line_idx, _)| *line_idx < idx || *line_idx >= end);
+    for (line_idx, _) in &mut self.lines {
+      if *line_idx >= end {
+        *line_idx = line_idx.saturating_sub(removed).saturating_add(inserted);
+      }
+    }
+  }
+}
@@ -0,0 +1,129 @@
+use crate::prelude::LineIdx;
+
+use std::mem;
+
+/// Number of matches of a search query in the lines which contain any.
+/// Keeps prefix sums in a Fenwick tree, so that numbering the match at the caret
+/// and updating a line after a replacement don't go through all lines.
+#[derive(Clone, Default)]
+pub struct MatchCounts {
+  /// Lines with matches, in ascending order, along with their count
+  lines: Vec<(LineIdx, usize)>,
+  /// Fenwick tree over the counts in `lines`, starting at index 1
+  tree: Vec<usize>,
+  total: usize,
+}
+
+impl MatchCounts {
+  pub fn from_lines(lines: Vec<(LineIdx, usize)>) -> Self {
+    debug_assert!(lines.is_sorted_by_key(|(line_idx, _)| *line_idx));
+    let mut result = Self {
+      lines,
+      ..Self::default()
+    };
+    result.rebuild();
+    result
+  }
+
+  pub const fn total(&self) -> usize {
+    self.total
+  }
+
+  /// Number of matches in the lines before the given one
+  pub fn before(&self, line_idx: LineIdx) -> usize {
+    let mut pos = self.lines.partition_point(|(idx, _)| *idx < line_idx);
+    let mut sum: usize = 0;
+    while pos > 0 {
+      sum = sum.saturating_add(self.tree.get(pos).copied().unwrap_or(0));
+      pos &= pos.saturating_sub(1);
+    }
+    sum
+  }
+
+  /// Changes the number of matches in the line, e.g. after a replacement
+  pub fn set(&mut self, line_idx: LineIdx, count: usize) {
+    let pos = self.lines.partition_point(|(idx, _)| *idx < line_idx);
+    match self.lines.get_mut(pos) {
+      Some((idx, old_count)) if *idx == line_idx => {
+        let old_count = mem::replace(old_count, count);
+        self.total = self.total.saturating_sub(old_count).saturating_add(count);
+        // Every node covering the line holds its old count, so subtracting it can't underflow
+        let mut node = pos.saturating_add(1);
+        while let Some(sum) = self.tree.get_mut(node) {
+          *sum = sum.saturating_sub(old_count).saturating_add(count);
+          node = node.saturating_add(node & node.wrapping_neg());
+        }
+      }
+      _ if count > 0 => {
+        // Lines without matches are not stored, so this takes building the tree again
+        self.lines.insert(pos, (line_idx, count));
+        self.rebuild();
+      }
+      _ => {}
+    }
+  }
+
+  fn rebuild(&mut self) {
+    self.tree = vec![0; self.lines.len().saturating_add(1)];
+    self.total = 0;
+    for (pos, (_, count)) in self.lines.iter().enumerate() {
+      self.total = self.total.saturating_add(*count);
+      let node = pos.saturating_add(1);
+      if let Some(sum) = self.tree.get_mut(node) {
+        *sum = sum.saturating_add(*count);
+        let sum = *sum;
+        let parent = node.saturating_add(node & node.wrapping_neg());
+        if let Some(parent_sum) = self.tree.get_mut(parent) {
+          *parent_sum = parent_sum.saturating_add(sum);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Checks `before` for every line against summing up the counts one by one
+  fn assert_sums(counts: &MatchCounts, lines: &[(LineIdx, usize)]) {
+    for line_idx in 0..=lines.last().map_or(0, |(idx, _)| idx.saturating_add(1)) {
+      let expected: usize = lines
+        .iter()
+        .filter(|(idx, _)| *idx < line_idx)
+        .map(|(_, count)| count)
+        .sum();
+      assert_eq!(counts.before(line_idx), expected, "before line {line_idx}");
+    }
+    assert_eq!(counts.total(), lines.iter().map(|(_, count)| count).sum());
+  }
+
+  #[test]
+  fn counts_matches_before_a_line() {
+    let lines = vec![(1, 2), (3, 1), (4, 5), (8, 1), (9, 3)];
+    assert_sums(&MatchCounts::from_lines(lines.clone()), &lines);
+    assert_sums(&MatchCounts::from_lines(Vec::new()), &[]);
+  }
+
+  #[test]
+  fn changes_the_count_of_a_line() {
+    let mut counts = MatchCounts::from_lines(vec![(1, 2), (3, 1), (4, 5), (8, 1), (9, 3)]);
+    counts.set(4, 2);
+    assert_sums(&counts, &[(1, 2), (3, 1), (4, 2), (8, 1), (9, 3)]);
+    // A line left without matches counts as 0
+    counts.set(1, 0);
+    assert_sums(&counts, &[(1, 0), (3, 1), (4, 2), (8, 1), (9, 3)]);
+  }
+
+  #[test]
+  fn adds_lines_which_had_no_matches() {
+    let mut counts = MatchCounts::from_lines(vec![(3, 1), (8, 1)]);
+    counts.set(5, 4);
+    counts.set(0, 2);
+    counts.set(10, 1);
+    assert_sums(&counts, &[(0, 2), (3, 1), (5, 4), (8, 1), (10, 1)]);
+    // Still without matches, nothing to store
+    counts.set(6, 0);
+    assert_sums(&counts, &[(0, 2), (3, 1), (5, 4), (8, 1), (10, 1)]);
+  }
+}
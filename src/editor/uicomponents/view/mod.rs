@@ -1,69 +1,228 @@
+mod atomicwrite;
 mod buffer;
 mod fileinfo;
+mod filestamp;
 mod highlighter;
+mod history;
+mod linecache;
+mod matchcounts;
+mod replaceinfo;
 mod searchdirection;
 mod searchinfo;
+mod visualrow;
 
 use crate::{
   editor::{
     NAME, VERSION,
+    annotatedstring::AnnotatedString,
+    annotationtype::AnnotationType,
     command::{Edit, Move},
+    displayoptions::DisplayOptions,
     documentstatus::DocumentStatus,
-    line::Line,
+    encoding::Encoding,
+    indentation::Indentation,
+    lineending::LineEnding,
+    linenumbers::LineNumbers,
+    register::Register,
+    searchoptions::SearchOptions,
+    searchquery::SearchQuery,
+    swapfile::SwapSource,
     terminal::Terminal,
     uicomponents::{
       UIComponent,
       view::{
-        buffer::Buffer, highlighter::Highlighter, searchdirection::SearchDirection,
-        searchinfo::SearchInfo,
+        buffer::Buffer, highlighter::Highlighter, history::TransactionKind,
+        matchcounts::MatchCounts, replaceinfo::ReplaceInfo, searchdirection::SearchDirection,
+        searchinfo::SearchInfo, visualrow::VisualRow,
       },
     },
   },
   prelude::*,
 };
 
-use std::{cmp::min, io::Error};
+use std::{
+  cell::RefCell,
+  cmp::{Ordering, min},
+  io::{Error, ErrorKind},
+  ops::Range,
+  path::PathBuf,
+  rc::{Rc, Weak},
+};
 
-#[derive(Default)]
+/// Cloning a view gives another view of the same buffer, so that edits made in one show up in the other
+#[derive(Default, Clone)]
 pub struct View {
-  buffer: Buffer,
+  buffer: Rc<RefCell<Buffer>>,
+  /// Revision of the buffer this view was last brought in line with
+  seen_revision: usize,
   needs_redraw: bool,
   // The view always starts at `(0/0)`. The `size` property determines the visible area.
   size: Size,
   text_location: Location,
+  /// The other end of the selection, the caret being the first one
+  selection_anchor: Option<Location>,
+  /// Line index of the first visible row, and the column the visible text starts at
   scroll_offset: Position,
+  /// With soft wrap, which of the rows of the line at `scroll_offset.row` is the first visible one
+  scroll_sub_row: usize,
+  display: DisplayOptions,
   search_info: Option<SearchInfo>,
+  replace_info: Option<ReplaceInfo>,
 }
 
 impl View {
-  pub fn get_status(&self) -> DocumentStatus {
-    let file_info = self.buffer.get_file_info();
+  /// Whether both views show the same buffer
+  pub(crate) fn shows_same_buffer(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.buffer, &other.buffer)
+  }
+
+  /// Moves the caret back into the text after the buffer was changed through another view.
+  /// The selection may not fit the changed text anymore, so it is dropped.
+  pub fn sync_with_buffer(&mut self) {
+    let revision = self.buffer.borrow().revision();
+    if revision == self.seen_revision {
+      return;
+    }
+    self.seen_revision = revision;
+    self.clear_selection();
+    self.snap_to_valid_line();
+    self.snap_to_valid_grapheme();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  pub(crate) fn get_status(&self) -> DocumentStatus {
+    let buffer = self.buffer.borrow();
+    let file_info = buffer.get_file_info();
     DocumentStatus {
-      total_lines: self.buffer.height(),
+      total_lines: buffer.height(),
       current_line_idx: self.text_location.line_idx,
       file_name: format!("{file_info}"),
-      is_modified: self.buffer.is_dirty(),
+      is_modified: buffer.is_dirty(),
       file_type: file_info.get_file_type(),
+      encoding: file_info.get_encoding(),
+      line_ending: file_info.get_line_ending(),
+      current_match: self.current_match(),
+      total_matches: self
+        .search_info
+        .as_ref()
+        .and_then(|search_info| search_info.match_counts.as_ref())
+        .map(MatchCounts::total),
+      counting_matches: self.is_counting_matches(),
+      search_wrapped: self
+        .search_info
+        .as_ref()
+        .is_some_and(|search_info| search_info.wrapped),
+    }
+  }
+
+  pub fn set_display_options(&mut self, display: DisplayOptions) {
+    if self.display != display {
+      self.display = display;
+      if display.soft_wrap {
+        self.scroll_offset.col = 0;
+      }
+      self.scroll_text_location_into_view();
+      self.set_needs_redraw(true);
     }
   }
 
-  pub const fn is_file_loaded(&self) -> bool {
-    self.buffer.is_file_loaded()
+  pub(crate) fn is_file_loaded(&self) -> bool {
+    self.buffer.borrow().is_file_loaded()
+  }
+
+  /// Switches the line endings used when saving between LF and CRLF.
+  /// The carriage returns kept in the lines of a mixed file are removed, which can be undone.
+  pub fn toggle_line_ending(&mut self) -> LineEnding {
+    let (current, has_final_newline) = {
+      let buffer = self.buffer.borrow();
+      let file_info = buffer.get_file_info();
+      (file_info.get_line_ending(), file_info.has_final_newline())
+    };
+    if current == LineEnding::Mixed {
+      let mut contents = self.buffer.borrow().contents().replace("\r\n", "\n");
+      // The contents leave out the line break of the last line
+      if has_final_newline && contents.ends_with('\r') {
+        contents.pop();
+      }
+      self.replace_contents(&contents);
+    }
+    let line_ending = current.toggled();
+    self.buffer.borrow_mut().set_line_ending(line_ending);
+    line_ending
+  }
+
+  pub fn set_indentation(&mut self, indentation: Indentation) {
+    self.buffer.borrow_mut().set_indentation(indentation);
+    self.set_needs_redraw(true);
+  }
+
+  /// Switches between inserting spaces and a tab character when Tab is pressed.
+  /// Returns whether spaces are inserted now.
+  pub fn toggle_insert_spaces(&mut self) -> bool {
+    let mut indentation = self.buffer.borrow().get_file_info().get_indentation();
+    indentation.insert_spaces = !indentation.insert_spaces;
+    self.buffer.borrow_mut().set_indentation(indentation);
+    indentation.insert_spaces
   }
 
   // region: command handling
 
   pub fn handle_edit_command(&mut self, command: Edit) {
+    let selection = self.selection();
+    let has_selection = selection.is_some();
+    self.clear_selection();
+
+    let kind = match &command {
+      Edit::Undo => return self.undo(),
+      Edit::Redo => return self.redo(),
+      // Handled by the editor, which owns the clipboard
+      Edit::Cut | Edit::Copy | Edit::Paste => return,
+      // Replacing a selection is never merged with other edits
+      _ if has_selection => TransactionKind::Other,
+      Edit::Insert(_) => TransactionKind::Typing,
+      Edit::Delete | Edit::DeleteBackward | Edit::DeleteWordBackward => TransactionKind::Deleting,
+      Edit::InsertNewline | Edit::InsertText(_) => TransactionKind::Other,
+    };
+
+    self
+      .buffer
+      .borrow_mut()
+      .begin_transaction(kind, self.text_location);
+    if let Some(selection) = selection {
+      self.delete_selection(selection);
+    }
     match command {
+      Edit::Insert('\t') => self.insert_tab(),
       Edit::Insert(character) => self.insert_char(character),
+      Edit::InsertText(text) => self.insert_text(&text),
+      // Deleting a selection is all Delete and Backspace do when there is one
+      Edit::Delete | Edit::DeleteBackward | Edit::DeleteWordBackward if has_selection => {}
       Edit::Delete => self.delete(),
       Edit::DeleteBackward => self.delete_backward(),
+      Edit::DeleteWordBackward => self.delete_word_backward(),
       Edit::InsertNewline => self.insert_newline(),
+      Edit::Undo | Edit::Redo | Edit::Cut | Edit::Copy | Edit::Paste => {}
     }
+    self.buffer.borrow_mut().end_transaction(self.text_location);
   }
 
   pub fn handle_move_command(&mut self, command: Move) {
+    self.clear_selection();
+    self.move_caret(command);
+  }
+
+  pub fn handle_select_command(&mut self, command: Move) {
+    if self.selection_anchor.is_none() {
+      self.selection_anchor = Some(self.text_location);
+    }
+    self.move_caret(command);
+    self.set_needs_redraw(true);
+  }
+
+  fn move_caret(&mut self, command: Move) {
     let Size { height, .. } = self.size;
+    let line_idx = self.text_location.line_idx;
     match command {
       Move::Up => self.move_up(1),
       Move::Down => self.move_down(1),
@@ -74,6 +233,9 @@ impl View {
       Move::StartOfLine => self.move_to_start_of_line(),
       Move::EndOfLine => self.move_to_end_of_line(),
     }
+    if self.display.line_numbers.follows_caret() && self.text_location.line_idx != line_idx {
+      self.set_needs_redraw(true);
+    }
     self.scroll_text_location_into_view();
   }
 
@@ -81,21 +243,113 @@ impl View {
 
   // region: file i/o
 
+  /// Reads the file into the buffer.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the file exists but can't be read or decoded.
   pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
-    let buffer = Buffer::load(file_name)?;
-    self.buffer = buffer;
+    let buffer = Buffer::load(file_name, None)?;
+    self.buffer.borrow_mut().replace_with(buffer);
     self.set_needs_redraw(true);
     Ok(())
   }
 
+  /// Reads the file again, decoding it with the given encoding. Unsaved changes are lost.
+  pub(crate) fn reopen_with_encoding(&mut self, encoding: Encoding) -> Result<(), Error> {
+    let Some(path) = self.file_path() else {
+      return Err(Error::new(ErrorKind::NotFound, "No file to reopen"));
+    };
+    let file_name = path.to_string_lossy().to_string();
+    let mut buffer = Buffer::load(&file_name, Some(encoding))?;
+    // Keep the configured or toggled indentation, the file type is still the same
+    buffer.set_indentation(self.buffer.borrow().get_file_info().get_indentation());
+    self.buffer.borrow_mut().replace_with(buffer);
+    self.clear_selection();
+    self.snap_to_valid_line();
+    self.snap_to_valid_grapheme();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+    Ok(())
+  }
+
+  /// Reads the file again in its current encoding. Unsaved changes are lost.
+  pub(crate) fn reload(&mut self) -> Result<(), Error> {
+    let encoding = self.buffer.borrow().get_file_info().get_encoding();
+    self.reopen_with_encoding(encoding)
+  }
+
+  /// Whether someone else changed the file since it was last loaded or saved
+  pub(crate) fn is_changed_on_disk(&self) -> bool {
+    self.buffer.borrow().is_changed_on_disk()
+  }
+
+  /// Whether saving would overwrite a change someone else made, found by reading the whole file
+  pub(crate) fn is_changed_on_disk_before_save(&self) -> bool {
+    self.buffer.borrow().is_changed_on_disk_before_save()
+  }
+
+  /// Accepts the version on disk as the one being edited, so that saving overwrites it without asking
+  pub fn acknowledge_disk_version(&mut self) {
+    self.buffer.borrow_mut().acknowledge_disk_version();
+  }
+
+  /// Changes the encoding used when saving
+  pub fn set_encoding(&mut self, encoding: Encoding) {
+    self.buffer.borrow_mut().set_encoding(encoding);
+  }
+
+  pub fn file_path(&self) -> Option<PathBuf> {
+    self
+      .buffer
+      .borrow()
+      .get_file_info()
+      .get_path()
+      .map(ToOwned::to_owned)
+  }
+
+  /// Changes with every edit, to tell whether the text is still the same
+  pub(crate) fn revision(&self) -> usize {
+    self.buffer.borrow().revision()
+  }
+
+  /// The buffer, for the panic hook to write its unsaved changes to the swap file
+  pub(crate) fn swap_source(&self) -> Weak<RefCell<dyn SwapSource>> {
+    let buffer: Rc<RefCell<dyn SwapSource>> = self.buffer.clone();
+    Rc::downgrade(&buffer)
+  }
+
+  /// Whole text of the buffer, lines separated by `\n`
+  pub(crate) fn contents(&self) -> String {
+    self.buffer.borrow().contents()
+  }
+
+  /// Replaces the whole text, e.g. with a recovered version of it. This can be undone.
+  pub fn replace_contents(&mut self, text: &str) {
+    self.clear_selection();
+    self
+      .buffer
+      .borrow_mut()
+      .replace_contents(text, self.text_location);
+    self.snap_to_valid_line();
+    self.snap_to_valid_grapheme();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  /// Writes the buffer to its file.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the file can't be written, or the text can't be encoded in the file's encoding.
   pub fn save(&mut self) -> Result<(), Error> {
-    self.buffer.save()?;
+    self.buffer.borrow_mut().save()?;
     self.set_needs_redraw(true);
     Ok(())
   }
 
-  pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-    self.buffer.save_as(file_name)?;
+  pub(crate) fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+    self.buffer.borrow_mut().save_as(file_name)?;
     self.set_needs_redraw(true);
     Ok(())
   }
@@ -105,12 +359,21 @@ impl View {
   // region: Text editing
 
   fn insert_char(&mut self, character: char) {
-    let old_len = self.buffer.grapheme_count(self.text_location.line_idx);
+    let old_len = self
+      .buffer
+      .borrow()
+      .grapheme_count(self.text_location.line_idx);
 
     // Insert new char in the buffer at text_location
-    self.buffer.insert_char(character, self.text_location);
+    self
+      .buffer
+      .borrow_mut()
+      .insert_char(character, self.text_location);
 
-    let new_len = self.buffer.grapheme_count(self.text_location.line_idx);
+    let new_len = self
+      .buffer
+      .borrow()
+      .grapheme_count(self.text_location.line_idx);
 
     let grapheme_delta = new_len.saturating_sub(old_len);
     if grapheme_delta > 0 {
@@ -120,8 +383,36 @@ impl View {
     self.set_needs_redraw(true);
   }
 
+  /// Inserts a tab, or spaces up to the next tab stop if the buffer is indented with spaces
+  fn insert_tab(&mut self) {
+    let indentation = self.buffer.borrow().get_file_info().get_indentation();
+    if !indentation.insert_spaces {
+      self.insert_char('\t');
+      return;
+    }
+    let col = self
+      .buffer
+      .borrow()
+      .width_until(self.text_location.line_idx, self.text_location.grapheme_idx);
+    let spaces = col
+      .checked_rem(indentation.tab_width)
+      .map_or(1, |past_stop| {
+        indentation.tab_width.saturating_sub(past_stop)
+      });
+    self.insert_text(&" ".repeat(spaces));
+  }
+
+  fn insert_text(&mut self, text: &str) {
+    self.text_location = self
+      .buffer
+      .borrow_mut()
+      .insert_text_at(text, self.text_location);
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
   fn delete(&mut self) {
-    self.buffer.delete(self.text_location);
+    self.buffer.borrow_mut().delete(self.text_location);
     self.set_needs_redraw(true);
   }
 
@@ -132,18 +423,220 @@ impl View {
     }
   }
 
+  fn delete_word_backward(&mut self) {
+    // At the start of a line there is no word to delete, so join with the previous line instead
+    if self.text_location.grapheme_idx == 0 {
+      self.delete_backward();
+      return;
+    }
+    let start = self.buffer.borrow().previous_word_start(self.text_location);
+    self.delete_selection(start..self.text_location);
+  }
+
   fn insert_newline(&mut self) {
-    self.buffer.insert_newline(self.text_location);
+    self.buffer.borrow_mut().insert_newline(self.text_location);
     self.handle_move_command(Move::Right);
     self.set_needs_redraw(true);
   }
 
   // endregion
 
+  // region: selection
+
+  /// Returns the selected range, ordered from start to end, if anything is selected
+  fn selection(&self) -> Option<Range<Location>> {
+    let anchor = self.selection_anchor?;
+    let caret = self.text_location;
+    match anchor.cmp(&caret) {
+      Ordering::Less => Some(anchor..caret),
+      Ordering::Greater => Some(caret..anchor),
+      Ordering::Equal => None,
+    }
+  }
+
+  fn clear_selection(&mut self) {
+    if self.selection_anchor.take().is_some() {
+      self.set_needs_redraw(true);
+    }
+  }
+
+  fn delete_selection(&mut self, selection: Range<Location>) {
+    self
+      .buffer
+      .borrow_mut()
+      .delete_range(selection.start, selection.end);
+    self.text_location = selection.start;
+    self.snap_to_valid_line();
+    self.snap_to_valid_grapheme();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  // endregion
+
+  // region: clipboard
+
+  /// Returns the selected text, or the whole caret line if nothing is selected
+  pub(crate) fn copy(&self) -> Option<Register> {
+    if let Some(selection) = self.selection() {
+      return Some(Register {
+        text: self
+          .buffer
+          .borrow()
+          .get_text(selection.start, selection.end),
+        is_line: false,
+      });
+    }
+
+    let line_idx = self.text_location.line_idx;
+    (line_idx < self.buffer.borrow().height()).then(|| {
+      let line = self.buffer.borrow().get_text(
+        Location {
+          line_idx,
+          grapheme_idx: 0,
+        },
+        Location {
+          line_idx,
+          grapheme_idx: self.buffer.borrow().grapheme_count(line_idx),
+        },
+      );
+      Register {
+        text: format!("{line}\n"),
+        is_line: true,
+      }
+    })
+  }
+
+  /// Like `copy`, but also removes the copied text
+  pub fn cut(&mut self) -> Option<Register> {
+    let register = self.copy()?;
+    let selection = self.selection();
+    self.clear_selection();
+
+    self
+      .buffer
+      .borrow_mut()
+      .begin_transaction(TransactionKind::Other, self.text_location);
+    if let Some(selection) = selection {
+      self.delete_selection(selection);
+    } else {
+      self.delete_caret_line();
+    }
+    self.buffer.borrow_mut().end_transaction(self.text_location);
+    Some(register)
+  }
+
+  /// Inserts the register in a single edit, replacing the selection if there is one
+  pub fn paste(&mut self, register: &Register) {
+    let selection = self.selection();
+    let has_selection = selection.is_some();
+    self.clear_selection();
+
+    self
+      .buffer
+      .borrow_mut()
+      .begin_transaction(TransactionKind::Other, self.text_location);
+    if let Some(selection) = selection {
+      self.delete_selection(selection);
+    }
+
+    if register.is_line && !has_selection {
+      // Whole lines go above the caret line, the caret stays in its column
+      let line_idx = self.text_location.line_idx;
+      // Below the document, the line break is implied by the new line itself
+      let text = if line_idx == self.buffer.borrow().height() {
+        register.text.strip_suffix('\n').unwrap_or(&register.text)
+      } else {
+        &register.text
+      };
+      self.buffer.borrow_mut().insert_text_at(
+        text,
+        Location {
+          line_idx,
+          grapheme_idx: 0,
+        },
+      );
+      self.text_location.line_idx = line_idx.saturating_add(register.text.matches('\n').count());
+      self.scroll_text_location_into_view();
+      self.set_needs_redraw(true);
+    } else {
+      self.insert_text(&register.text);
+    }
+    self.buffer.borrow_mut().end_transaction(self.text_location);
+  }
+
+  fn delete_caret_line(&mut self) {
+    let line_idx = self.text_location.line_idx;
+    let line_start = Location {
+      line_idx,
+      grapheme_idx: 0,
+    };
+    let line_end = Location {
+      line_idx,
+      grapheme_idx: self.buffer.borrow().grapheme_count(line_idx),
+    };
+
+    if line_idx.saturating_add(1) < self.buffer.borrow().height() {
+      // Remove the line including its line break
+      self.buffer.borrow_mut().delete_range(
+        line_start,
+        Location {
+          line_idx: line_idx.saturating_add(1),
+          grapheme_idx: 0,
+        },
+      );
+    } else if let Some(prev_line_idx) = line_idx.checked_sub(1) {
+      // The last line has no line break of its own, remove the one before it instead
+      let prev_line_end = Location {
+        line_idx: prev_line_idx,
+        grapheme_idx: self.buffer.borrow().grapheme_count(prev_line_idx),
+      };
+      self
+        .buffer
+        .borrow_mut()
+        .delete_range(prev_line_end, line_end);
+    } else {
+      self.buffer.borrow_mut().delete_range(line_start, line_end);
+    }
+
+    self.text_location = line_start;
+    self.snap_to_valid_line();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  // endregion
+
+  // region: history
+
+  fn undo(&mut self) {
+    let location = self.buffer.borrow_mut().undo();
+    if let Some(location) = location {
+      self.move_to_location(location);
+    }
+  }
+
+  fn redo(&mut self) {
+    let location = self.buffer.borrow_mut().redo();
+    if let Some(location) = location {
+      self.move_to_location(location);
+    }
+  }
+
+  fn move_to_location(&mut self, location: Location) {
+    self.text_location = location;
+    self.snap_to_valid_line();
+    self.snap_to_valid_grapheme();
+    self.scroll_text_location_into_view();
+    self.set_needs_redraw(true);
+  }
+
+  // endregion
+
   // region: Rendering
 
-  fn render_line(at: RowIdx, line_text: &str) -> Result<(), Error> {
-    Terminal::print_row(at, line_text)
+  fn render_line(at: Position, width: usize, line_text: &str) -> Result<(), Error> {
+    Terminal::print_at(at, width, line_text)
   }
 
   fn build_welcome_message(width: usize) -> String {
@@ -160,28 +653,60 @@ impl View {
     format!("{:<1}{:^remaining_width$}", "~", welcome_message)
   }
 
+  /// Columns taken by the line numbers and the space after them.
+  /// The gutter grows with the number of lines, and is left out if it doesn't leave room for the text.
+  fn gutter_width(&self) -> usize {
+    if self.display.line_numbers == LineNumbers::Off {
+      return 0;
+    }
+    let digits = self.buffer.borrow().height().max(1).to_string().len();
+    let width = digits.saturating_add(1);
+    if width < self.size.width { width } else { 0 }
+  }
+
+  /// Columns left for the text next to the gutter
+  fn text_width(&self) -> usize {
+    self.size.width.saturating_sub(self.gutter_width())
+  }
+
+  fn build_gutter(&self, line_idx: LineIdx, width: usize) -> AnnotatedString {
+    let caret_line_idx = self.text_location.line_idx;
+    let number = self
+      .display
+      .line_numbers
+      .number(line_idx, caret_line_idx)
+      .map_or_else(String::new, |number| number.to_string());
+    let digits = width.saturating_sub(1);
+    let mut gutter = AnnotatedString::from(&format!("{number:>digits$} "));
+    if line_idx != caret_line_idx {
+      gutter.add_annotation(AnnotationType::LineNumber, 0, digits);
+    }
+    gutter
+  }
+
   // endregion
 
   // region: Scrolling
 
-  fn scroll_vertically(&mut self, to: RowIdx) {
-    let Size { height, .. } = self.size;
-    let offset_changed = if to < self.scroll_offset.row {
-      self.scroll_offset.row = to;
-      true
-    } else if to >= self.scroll_offset.row.saturating_add(height) {
-      self.scroll_offset.row = to.saturating_sub(height).saturating_add(1);
-      true
+  /// Scrolls as little as possible to bring the row into view
+  fn scroll_vertically(&mut self, to: VisualRow) {
+    let height = self.size.height;
+    let top = self.top_row();
+    let new_top = if to < top {
+      Some(to)
+    } else if self.visual_distance(top, to, height).is_none() {
+      Some(self.visual_row_above(to, height.saturating_sub(1)))
     } else {
-      false
+      None
     };
-    if offset_changed {
+    if let Some(new_top) = new_top {
+      self.set_top_row(new_top);
       self.set_needs_redraw(true);
     }
   }
 
   fn scroll_horizontally(&mut self, to: ColIdx) {
-    let Size { width, .. } = self.size;
+    let width = self.text_width();
     let offset_changed = if to < self.scroll_offset.col {
       self.scroll_offset.col = to;
       true
@@ -197,18 +722,21 @@ impl View {
   }
 
   fn scroll_text_location_into_view(&mut self) {
-    let Position { col, row } = self.text_location_to_position();
-    self.scroll_vertically(row);
+    self.scroll_vertically(self.caret_visual_row());
+    // Wrapped lines always fit the width
+    let col = if self.display.soft_wrap {
+      0
+    } else {
+      self.text_location_to_position().col
+    };
     self.scroll_horizontally(col);
   }
 
   /// Center current location in center of view
   fn center_text_location(&mut self) {
-    // Get current size of the view
-    let Size { height, width } = self.size;
-
-    // Get position of current text location
-    let Position { row, col } = self.text_location_to_position();
+    // Get current size of the view, without the gutter
+    let height = self.size.height;
+    let width = self.text_width();
 
     // Get the middle of the view
     let vertical_mid = height.div_ceil(2);
@@ -216,29 +744,203 @@ impl View {
 
     // Change the offset of the view
     // Which in turn move the view only not the current edit position
-    self.scroll_offset.row = row.saturating_sub(vertical_mid);
-    self.scroll_offset.col = col.saturating_sub(horizontal_mid);
+    let top = self.visual_row_above(self.caret_visual_row(), vertical_mid);
+    self.set_top_row(top);
+    self.scroll_offset.col = if self.display.soft_wrap {
+      0
+    } else {
+      self
+        .text_location_to_position()
+        .col
+        .saturating_sub(horizontal_mid)
+    };
 
     // Ask view to redraw
     self.set_needs_redraw(true);
   }
 
+  /// The first visible row
+  fn top_row(&self) -> VisualRow {
+    let line_idx = self.scroll_offset.row;
+    // The line may have been wrapped into fewer rows since, e.g. after the view got wider
+    let last_sub_row = self.row_starts(line_idx).len().saturating_sub(1);
+    VisualRow {
+      line_idx,
+      sub_row: min(self.scroll_sub_row, last_sub_row),
+    }
+  }
+
+  const fn set_top_row(&mut self, row: VisualRow) {
+    self.scroll_offset.row = row.line_idx;
+    self.scroll_sub_row = row.sub_row;
+  }
+
+  // endregion
+
+  // region: soft wrap
+
+  /// Where the rows of the line start. Without soft wrap, every line is a single row.
+  fn row_starts(&self, line_idx: LineIdx) -> Rc<[GraphemeIdx]> {
+    if self.display.soft_wrap {
+      self.buffer.borrow().wrap(line_idx, self.text_width())
+    } else {
+      Rc::from([0])
+    }
+  }
+
+  fn visual_row_of(&self, location: Location) -> VisualRow {
+    let sub_row = self
+      .row_starts(location.line_idx)
+      .iter()
+      .rposition(|start| *start <= location.grapheme_idx)
+      .unwrap_or(0);
+    VisualRow {
+      line_idx: location.line_idx,
+      sub_row,
+    }
+  }
+
+  fn caret_visual_row(&self) -> VisualRow {
+    self.visual_row_of(self.text_location)
+  }
+
+  /// The row below, `None` at the end of the document
+  fn next_visual_row(&self, row: VisualRow) -> Option<VisualRow> {
+    if row.sub_row.saturating_add(1) < self.row_starts(row.line_idx).len() {
+      Some(VisualRow {
+        sub_row: row.sub_row.saturating_add(1),
+        ..row
+      })
+    } else if row.line_idx < self.buffer.borrow().height() {
+      Some(VisualRow {
+        line_idx: row.line_idx.saturating_add(1),
+        sub_row: 0,
+      })
+    } else {
+      None
+    }
+  }
+
+  /// The row above, `None` at the start of the document
+  fn previous_visual_row(&self, row: VisualRow) -> Option<VisualRow> {
+    if let Some(sub_row) = row.sub_row.checked_sub(1) {
+      return Some(VisualRow { sub_row, ..row });
+    }
+    let line_idx = row.line_idx.checked_sub(1)?;
+    Some(VisualRow {
+      line_idx,
+      sub_row: self.row_starts(line_idx).len().saturating_sub(1),
+    })
+  }
+
+  /// The row `step` rows above, or the first row of the document
+  fn visual_row_above(&self, row: VisualRow, step: usize) -> VisualRow {
+    let mut row = row;
+    for _ in 0..step {
+      let Some(previous) = self.previous_visual_row(row) else {
+        break;
+      };
+      row = previous;
+    }
+    row
+  }
+
+  /// The row `step` rows below, or the last row of the document
+  fn visual_row_below(&self, row: VisualRow, step: usize) -> VisualRow {
+    let mut row = row;
+    for _ in 0..step {
+      let Some(next) = self.next_visual_row(row) else {
+        break;
+      };
+      row = next;
+    }
+    row
+  }
+
+  /// Number of rows from one row down to the other, `None` if it is above or at least `limit` rows below
+  fn visual_distance(&self, from: VisualRow, to: VisualRow, limit: usize) -> Option<usize> {
+    let mut row = from;
+    for distance in 0..limit {
+      if row == to {
+        return Some(distance);
+      }
+      row = self.next_visual_row(row)?;
+    }
+    None
+  }
+
+  /// Moves the caret to the row, keeping its column within the row as far as possible
+  fn move_to_visual_row(&mut self, row: VisualRow) {
+    let current = self.caret_visual_row();
+    let current_starts = self.row_starts(current.line_idx);
+    let buffer = self.buffer.borrow();
+    let current_row_start = current_starts.get(current.sub_row).copied().unwrap_or(0);
+    let col = buffer
+      .width_until(current.line_idx, self.text_location.grapheme_idx)
+      .saturating_sub(buffer.width_until(current.line_idx, current_row_start));
+    drop(buffer);
+
+    let starts = self.row_starts(row.line_idx);
+    let buffer = self.buffer.borrow();
+    let row_start = starts.get(row.sub_row).copied().unwrap_or(0);
+    // The grapheme starting the next row belongs to that row
+    let row_end = starts.get(row.sub_row.saturating_add(1)).map_or_else(
+      || buffer.grapheme_count(row.line_idx),
+      |next_start| next_start.saturating_sub(1),
+    );
+    let grapheme_idx = buffer.grapheme_idx_at(
+      row.line_idx,
+      buffer
+        .width_until(row.line_idx, row_start)
+        .saturating_add(col),
+    );
+    drop(buffer);
+
+    self.text_location = Location {
+      line_idx: row.line_idx,
+      grapheme_idx: grapheme_idx.clamp(row_start, row_end.max(row_start)),
+    };
+  }
+
   // endregion
 
   // region: Location and Position Handling
 
-  pub fn caret_position(&self) -> Position {
-    self
-      .text_location_to_position()
-      .saturating_sub(self.scroll_offset)
+  /// Caret position relative to the view, right of the gutter
+  pub(crate) fn caret_position(&self) -> Position {
+    let caret_row = self.caret_visual_row();
+    let row = self
+      .visual_distance(self.top_row(), caret_row, self.size.height)
+      .unwrap_or(0);
+    let col = if self.display.soft_wrap {
+      let row_start = self
+        .row_starts(caret_row.line_idx)
+        .get(caret_row.sub_row)
+        .copied()
+        .unwrap_or(0);
+      let buffer = self.buffer.borrow();
+      buffer
+        .width_until(caret_row.line_idx, self.text_location.grapheme_idx)
+        .saturating_sub(buffer.width_until(caret_row.line_idx, row_start))
+    } else {
+      self
+        .text_location_to_position()
+        .col
+        .saturating_sub(self.scroll_offset.col)
+    };
+    Position {
+      row,
+      col: col.saturating_add(self.gutter_width()),
+    }
   }
 
   fn text_location_to_position(&self) -> Position {
     let row = self.text_location.line_idx;
-    debug_assert!(row.saturating_sub(1) <= self.buffer.height());
+    debug_assert!(row.saturating_sub(1) <= self.buffer.borrow().height());
 
     let col = self
       .buffer
+      .borrow()
       .width_until(row, self.text_location.grapheme_idx);
 
     Position { col, row }
@@ -253,22 +955,37 @@ impl View {
   fn snap_to_valid_grapheme(&mut self) {
     self.text_location.grapheme_idx = min(
       self.text_location.grapheme_idx,
-      self.buffer.grapheme_count(self.text_location.line_idx),
+      self
+        .buffer
+        .borrow()
+        .grapheme_count(self.text_location.line_idx),
     );
   }
 
   // Ensures self.location.idx points to a valid line index by snapping it to the bottom most line if appropriate.
   // Doesn't trigger scrolling.
   fn snap_to_valid_line(&mut self) {
-    self.text_location.line_idx = min(self.text_location.line_idx, self.buffer.height());
+    self.text_location.line_idx = min(self.text_location.line_idx, self.buffer.borrow().height());
   }
 
+  /// Moves up by lines, or by rows with soft wrap
   fn move_up(&mut self, step: usize) {
+    if self.display.soft_wrap {
+      let row = self.visual_row_above(self.caret_visual_row(), step);
+      self.move_to_visual_row(row);
+      return;
+    }
     self.text_location.line_idx = self.text_location.line_idx.saturating_sub(step);
     self.snap_to_valid_grapheme();
   }
 
+  /// Moves down by lines, or by rows with soft wrap
   fn move_down(&mut self, step: usize) {
+    if self.display.soft_wrap {
+      let row = self.visual_row_below(self.caret_visual_row(), step);
+      self.move_to_visual_row(row);
+      return;
+    }
     self.text_location.line_idx = self.text_location.line_idx.saturating_add(step);
     self.snap_to_valid_grapheme();
     self.snap_to_valid_line();
@@ -279,14 +996,20 @@ impl View {
   }
 
   fn move_to_end_of_line(&mut self) {
-    self.text_location.grapheme_idx = self.buffer.grapheme_count(self.text_location.line_idx);
+    self.text_location.grapheme_idx = self
+      .buffer
+      .borrow()
+      .grapheme_count(self.text_location.line_idx);
   }
 
   // clippy::arithmetic_side_effects: This function performs arithmetic calculations
   // after explicitly checking that the target value will be within bounds.
   #[allow(clippy::arithmetic_side_effects)]
   fn move_right(&mut self) {
-    let grapheme_count = self.buffer.grapheme_count(self.text_location.line_idx);
+    let grapheme_count = self
+      .buffer
+      .borrow()
+      .grapheme_count(self.text_location.line_idx);
     if self.text_location.grapheme_idx < grapheme_count {
       self.text_location.grapheme_idx += 1;
     } else {
@@ -312,10 +1035,14 @@ impl View {
   // region: search
 
   pub fn enter_search(&mut self) {
+    self.clear_selection();
     self.search_info = Some(SearchInfo {
       prev_location: self.text_location,
       prev_scroll_offset: self.scroll_offset,
+      prev_scroll_sub_row: self.scroll_sub_row,
       query: None,
+      match_counts: None,
+      wrapped: false,
     });
   }
 
@@ -328,6 +1055,7 @@ impl View {
     if let Some(search_info) = &self.search_info {
       self.text_location = search_info.prev_location;
       self.scroll_offset = search_info.prev_scroll_offset;
+      self.scroll_sub_row = search_info.prev_scroll_sub_row;
       self.set_needs_redraw(true);
 
       // ensure the previous location is still visible even if the terminal has been resized during search.
@@ -336,28 +1064,38 @@ impl View {
     self.exit_search();
   }
 
-  pub fn search(&mut self, query: &str) {
+  /// Search for the query, starting at the current location.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error without searching if the query is not a valid regex.
+  pub fn search(&mut self, query: &str, options: SearchOptions) -> Result<(), regex::Error> {
+    let query = SearchQuery::new(query, options);
     if let Some(search_info) = &mut self.search_info {
-      search_info.query = Some(Line::from(query));
+      search_info.query = query.clone().ok();
+      // Counting goes through the whole document, so it waits until typing pauses
+      search_info.match_counts = search_info
+        .query
+        .as_ref()
+        .is_none_or(SearchQuery::is_empty)
+        .then(MatchCounts::default);
+      search_info.wrapped = false;
     }
+    // Make sure highlights of a previous query disappear, even if we don't search
+    self.set_needs_redraw(true);
+
+    query?;
     self.search_in_direction(self.text_location, SearchDirection::default());
+    Ok(())
   }
 
-  // Attempts to get the current search query - for scenarios where the search query absolutely must be there.
-  // Panics if not present in debug, or if search info is not present in debug
-  // Returns None on release.
-  fn get_search_query(&self) -> Option<&Line> {
-    let query = self
+  // Attempts to get the current search query.
+  // Returns None if there's no search in progress, or the query is invalid.
+  fn get_search_query(&self) -> Option<&SearchQuery> {
+    self
       .search_info
       .as_ref()
-      .and_then(|search_info| search_info.query.as_ref());
-
-    debug_assert!(
-      query.is_some(),
-      "Attempting to search with malformed searchinfo present"
-    );
-
-    query
+      .and_then(|search_info| search_info.query.as_ref())
   }
 
   /// Start the search from the given location
@@ -366,21 +1104,80 @@ impl View {
       if query.is_empty() {
         None
       } else if direction == SearchDirection::Forward {
-        self.buffer.search_forward(query, from)
+        self.buffer.borrow().search_forward(query, from)
       } else {
-        self.buffer.search_backward(query, from)
+        self.buffer.borrow().search_backward(query, from)
       }
     }) {
+      let wrapped = match direction {
+        SearchDirection::Forward => location < from,
+        SearchDirection::Backward => location > from,
+      };
+      if let Some(search_info) = &mut self.search_info {
+        search_info.wrapped = wrapped;
+      }
       self.text_location = location;
       self.center_text_location();
     }
     self.set_needs_redraw(true);
   }
 
+  /// Returns the index of the match at the caret, starting at 1
+  fn current_match(&self) -> Option<usize> {
+    let search_info = self.search_info.as_ref()?;
+    let query = search_info.query.as_ref()?;
+
+    let (before_in_line, is_at_match) = self
+      .buffer
+      .borrow()
+      .matches_before(query, self.text_location);
+    if !is_at_match {
+      return None;
+    }
+
+    let before_line = search_info
+      .match_counts
+      .as_ref()?
+      .before(self.text_location.line_idx);
+    Some(before_line.saturating_add(before_in_line).saturating_add(1))
+  }
+
+  /// Whether the matches of the current query are yet to be counted
+  pub(crate) fn is_counting_matches(&self) -> bool {
+    self
+      .search_info
+      .as_ref()
+      .is_some_and(|search_info| search_info.match_counts.is_none())
+  }
+
+  /// Counts the matches of the current query, unless they are counted already.
+  /// Meant to be called once typing pauses, as it goes through the whole document.
+  pub fn count_matches(&mut self) {
+    if let Some(search_info) = &mut self.search_info
+      && search_info.match_counts.is_none()
+      && let Some(query) = &search_info.query
+    {
+      let lines = self.buffer.borrow().count_matches(query);
+      search_info.match_counts = Some(MatchCounts::from_lines(lines));
+      self.set_needs_redraw(true);
+    }
+  }
+
+  /// Keeps the match count up to date after the line has been changed
+  fn update_match_count(&mut self, idx: LineIdx) {
+    if let Some(search_info) = &mut self.search_info
+      && let Some(query) = &search_info.query
+      && let Some(match_counts) = &mut search_info.match_counts
+    {
+      let count = self.buffer.borrow().count_matches_in_line(query, idx);
+      match_counts.set(idx, count);
+    }
+  }
+
   pub fn search_next(&mut self) {
     let step_right = self
       .get_search_query()
-      .map_or(1, |query| min(query.grapheme_count(), 1));
+      .map_or(1, |query| usize::from(!query.is_empty()));
 
     let location = Location {
       line_idx: self.text_location.line_idx,
@@ -394,6 +1191,118 @@ impl View {
   }
 
   // endregion
+
+  // region: replace
+
+  /// Starts replacing the matches of the current search query, one by one.
+  /// All replacements form a single undo step.
+  /// Returns false if there is nothing to replace.
+  pub fn start_replace(&mut self, replacement: String) -> bool {
+    let origin = self.text_location;
+    self.replace_info = Some(ReplaceInfo {
+      replacement,
+      origin,
+      wrapped: false,
+      count: 0,
+    });
+    self
+      .buffer
+      .borrow_mut()
+      .begin_transaction(TransactionKind::Other, self.text_location);
+    self.move_to_next_replace_match(origin)
+  }
+
+  /// Replaces the match at the caret and moves on to the next one.
+  /// Returns false if there are no further matches.
+  pub fn replace_match(&mut self) -> bool {
+    let at = self.text_location;
+    let old_len = self.buffer.borrow().grapheme_count(at.line_idx);
+
+    let end = self
+      .search_info
+      .as_ref()
+      .and_then(|search_info| search_info.query.as_ref())
+      .zip(self.replace_info.as_ref())
+      .and_then(|(query, replace_info)| {
+        self
+          .buffer
+          .borrow_mut()
+          .replace_match(query, at, &replace_info.replacement)
+      });
+
+    let Some(end) = end else {
+      return self.skip_match();
+    };
+
+    self.update_match_count(at.line_idx);
+
+    let new_len = self.buffer.borrow().grapheme_count(at.line_idx);
+    if let Some(replace_info) = &mut self.replace_info {
+      replace_info.count = replace_info.count.saturating_add(1);
+      // After wrapping, replacing in front of the origin moves the origin
+      if replace_info.wrapped && at.line_idx == replace_info.origin.line_idx {
+        replace_info.origin.grapheme_idx = replace_info
+          .origin
+          .grapheme_idx
+          .saturating_add(new_len)
+          .saturating_sub(old_len);
+      }
+    }
+    self.set_needs_redraw(true);
+    self.move_to_next_replace_match(end)
+  }
+
+  /// Leaves the match at the caret as it is and moves on to the next one.
+  /// Returns false if there are no further matches.
+  pub fn skip_match(&mut self) -> bool {
+    let from = Location {
+      line_idx: self.text_location.line_idx,
+      grapheme_idx: self.text_location.grapheme_idx.saturating_add(1),
+    };
+    self.move_to_next_replace_match(from)
+  }
+
+  /// Replaces the match at the caret and all remaining ones
+  pub fn replace_all(&mut self) {
+    while self.replace_match() {}
+  }
+
+  /// Ends the replace, returns the number of replaced matches
+  pub fn finish_replace(&mut self) -> usize {
+    self.buffer.borrow_mut().end_transaction(self.text_location);
+    self.exit_search();
+    self
+      .replace_info
+      .take()
+      .map_or(0, |replace_info| replace_info.count)
+  }
+
+  /// Moves to the next match which hasn't been visited during this replace yet
+  fn move_to_next_replace_match(&mut self, from: Location) -> bool {
+    let Some(found) = self
+      .get_search_query()
+      .filter(|query| !query.is_empty())
+      .and_then(|query| self.buffer.borrow().search_forward(query, from))
+    else {
+      return false;
+    };
+
+    let Some(replace_info) = &mut self.replace_info else {
+      return false;
+    };
+    if found < from {
+      replace_info.wrapped = true;
+    }
+    if replace_info.wrapped && found >= replace_info.origin {
+      return false;
+    }
+
+    self.text_location = found;
+    self.center_text_location();
+    true
+  }
+
+  // endregion
 }
 
 impl UIComponent for View {
@@ -410,54 +1319,268 @@ impl UIComponent for View {
     self.scroll_text_location_into_view();
   }
 
-  fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
     let Size { height, width } = self.size;
-    let end_y = origin_row.saturating_add(height);
+    let gutter_width = self.gutter_width();
+    let text_width = width.saturating_sub(gutter_width);
     let top_third = height.div_ceil(3);
 
+    // Rows of wrapped lines are counted as lines here, which covers at least all visible lines
     let scroll_top = self.scroll_offset.row;
+    let scroll_bottom = scroll_top.saturating_add(height);
+
+    // Syntax highlighting depends on the lines above, so it is brought up to date first
+    self
+      .buffer
+      .borrow_mut()
+      .update_syntax_highlighting(scroll_bottom);
+    let buffer = self.buffer.borrow();
 
     let query = self
       .search_info
       .as_ref()
-      .and_then(|search_info| search_info.query.as_deref());
+      .and_then(|search_info| search_info.query.as_ref());
 
     let selected_match = query.is_some().then_some(self.text_location);
 
     let mut highlighter = Highlighter::new(
       query,
       selected_match,
-      self.buffer.get_file_info().get_file_type(),
+      self.selection(),
+      buffer.syntax_highlighter(),
     );
 
-    // Highlight from the start of the document to the end of the visible area, to ensure all annotations are up to date.
-    for current_row in 0..end_y.saturating_add(scroll_top) {
-      self.buffer.highlight(current_row, &mut highlighter);
+    for line_idx in scroll_top..scroll_bottom {
+      buffer.highlight(line_idx, &mut highlighter);
     }
 
-    for current_row in origin_row..end_y {
-      // to get the correct line index, we have to take current_row (the absolute row on screen),
-      // subtract origin_row to get the current row relative to the view (ranging from 0 to self.size.height)
-      // and add the scroll offset.
-      let line_idx = current_row
-        .saturating_sub(origin_row)
-        .saturating_add(scroll_top);
-
-      let left = self.scroll_offset.col;
-      let right = self.scroll_offset.col.saturating_add(width);
+    // Rows of the line the current row belongs to, only wrapped again for the next line
+    let mut row_starts: Option<(LineIdx, Rc<[GraphemeIdx]>)> = None;
+    let mut visual_row = Some(self.top_row());
+
+    for row in 0..height {
+      // row is relative to the view (ranging from 0 to self.size.height),
+      // adding the origin gives the row on screen.
+      let current_origin = Position {
+        row: origin.row.saturating_add(row),
+        col: origin.col,
+      };
+      let Some(VisualRow { line_idx, sub_row }) = visual_row else {
+        Self::render_line(current_origin, width, "~")?;
+        continue;
+      };
+      visual_row = self.next_visual_row(VisualRow { line_idx, sub_row });
+
+      let (left, right) = if self.display.soft_wrap {
+        if row_starts.as_ref().is_none_or(|(idx, _)| *idx != line_idx) {
+          row_starts = Some((line_idx, self.row_starts(line_idx)));
+        }
+        let starts = row_starts
+          .as_ref()
+          .map(|(_, starts)| &**starts)
+          .unwrap_or_default();
+        let left = buffer.width_until(line_idx, starts.get(sub_row).copied().unwrap_or(0));
+        // A row broken after whitespace ends before the full width
+        let right = starts.get(sub_row.saturating_add(1)).map_or_else(
+          || left.saturating_add(text_width),
+          |next_start| buffer.width_until(line_idx, *next_start),
+        );
+        (left, right)
+      } else {
+        let left = self.scroll_offset.col;
+        (left, left.saturating_add(text_width))
+      };
 
       if let Some(annotated_string) =
-        self
-          .buffer
-          .get_highlighted_substring(line_idx, left..right, &highlighter)
+        buffer.get_highlighted_substring(line_idx, left..right, &highlighter)
       {
-        Terminal::print_annotated_row(current_row, &annotated_string)?;
-      } else if current_row == top_third && self.buffer.is_empty() {
-        Self::render_line(current_row, &Self::build_welcome_message(width))?;
+        if gutter_width > 0 {
+          // Only the first row of a wrapped line is numbered
+          if sub_row == 0 {
+            Terminal::print_annotated_row(
+              current_origin,
+              gutter_width,
+              &self.build_gutter(line_idx, gutter_width),
+              &self.display.theme,
+            )?;
+          } else {
+            Self::render_line(current_origin, gutter_width, "")?;
+          }
+        }
+        let text_origin = Position {
+          col: current_origin.col.saturating_add(gutter_width),
+          ..current_origin
+        };
+        Terminal::print_annotated_row(
+          text_origin,
+          text_width,
+          &annotated_string,
+          &self.display.theme,
+        )?;
+      } else if row == top_third && buffer.is_empty() {
+        Self::render_line(current_origin, width, &Self::build_welcome_message(width))?;
       } else {
-        Self::render_line(current_row, "~")?;
+        Self::render_line(current_origin, width, "~")?;
       }
     }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn view(text: &str) -> View {
+    let mut view = View::default();
+    view.resize(Size {
+      height: 10,
+      width: 40,
+    });
+    view.replace_contents(text);
+    view
+  }
+
+  const fn at(line_idx: LineIdx, grapheme_idx: GraphemeIdx) -> Location {
+    Location {
+      line_idx,
+      grapheme_idx,
+    }
+  }
+
+  #[test]
+  fn selects_from_where_the_caret_started() {
+    let mut view = view("one\ntwo\n");
+    assert_eq!(view.selection(), None);
+    view.handle_select_command(Move::Right);
+    view.handle_select_command(Move::Down);
+    assert_eq!(view.selection(), Some(at(0, 0)..at(1, 1)));
+    view.handle_select_command(Move::Up);
+    view.handle_select_command(Move::Left);
+    assert_eq!(view.selection(), None);
+  }
+
+  #[test]
+  fn selection_is_ordered_when_selecting_backwards() {
+    let mut view = view("one\ntwo\n");
+    view.handle_move_command(Move::Down);
+    view.handle_select_command(Move::Up);
+    assert_eq!(view.selection(), Some(at(0, 0)..at(1, 0)));
+  }
+
+  #[test]
+  fn moving_clears_the_selection() {
+    let mut view = view("one\n");
+    view.handle_select_command(Move::Right);
+    view.handle_move_command(Move::Right);
+    assert_eq!(view.selection(), None);
+  }
+
+  #[test]
+  fn deleting_removes_only_the_selection() {
+    let mut view = view("one\ntwo\n");
+    view.handle_move_command(Move::Right);
+    view.handle_select_command(Move::Down);
+    view.handle_edit_command(Edit::DeleteBackward);
+    assert_eq!(view.contents(), "owo\n");
+    assert_eq!(view.text_location, at(0, 1));
+    assert_eq!(view.selection(), None);
+  }
+
+  #[test]
+  fn typing_replaces_the_selection() {
+    let mut view = view("one two\n");
+    view.handle_select_command(Move::EndOfLine);
+    view.handle_select_command(Move::Left);
+    view.handle_select_command(Move::Left);
+    view.handle_select_command(Move::Left);
+    view.handle_select_command(Move::Left);
+    view.handle_edit_command(Edit::Insert('1'));
+    assert_eq!(view.contents(), "1 two\n");
+    view.handle_edit_command(Edit::Undo);
+    assert_eq!(view.contents(), "one two\n");
+  }
+
+  #[test]
+  fn tab_inserts_spaces_up_to_the_next_tab_stop() {
+    let mut view = view("ab\n");
+    view.set_indentation(Indentation {
+      tab_width: 4,
+      insert_spaces: true,
+    });
+    view.handle_move_command(Move::Right);
+    view.handle_edit_command(Edit::Insert('\t'));
+    assert_eq!(view.contents(), "a   b\n");
+    view.handle_edit_command(Edit::Insert('\t'));
+    assert_eq!(view.contents(), "a       b\n");
+
+    view.toggle_insert_spaces();
+    view.handle_edit_command(Edit::Insert('\t'));
+    assert_eq!(view.contents(), "a       \tb\n");
+    assert_eq!(view.text_location, at(0, 9));
+  }
+
+  fn start_replace(view: &mut View, query: &str, replacement: &str) -> bool {
+    view.enter_search();
+    view
+      .search(query, SearchOptions::default())
+      .expect("query is valid");
+    view.start_replace(replacement.to_string())
+  }
+
+  #[test]
+  fn replacing_wraps_around_and_stops_at_the_origin() {
+    let mut view = view("a a\na a\n");
+    view.handle_move_command(Move::Down);
+    assert!(start_replace(&mut view, "a", "bb"));
+    assert_eq!(view.text_location, at(1, 0));
+    view.replace_all();
+    assert_eq!(view.finish_replace(), 4);
+    assert_eq!(view.contents(), "bb bb\nbb bb\n");
+  }
+
+  #[test]
+  fn skipping_every_match_stops_at_the_origin() {
+    let mut view = view("a a\na a\n");
+    view.handle_move_command(Move::Down);
+    assert!(start_replace(&mut view, "a", "a"));
+    assert!(view.skip_match());
+    assert!(view.skip_match());
+    assert!(view.skip_match());
+    assert!(!view.skip_match());
+    assert_eq!(view.finish_replace(), 0);
+  }
+
+  #[test]
+  fn replacing_in_front_of_the_origin_moves_it() {
+    let mut view = view("a a a\n");
+    view.handle_move_command(Move::EndOfLine);
+    view.handle_move_command(Move::Left);
+    assert!(start_replace(&mut view, "a", "bbb"));
+    assert_eq!(view.text_location, at(0, 4));
+    view.replace_all();
+    assert_eq!(view.finish_replace(), 3);
+    assert_eq!(view.contents(), "bbb bbb bbb\n");
+  }
+
+  #[test]
+  fn nothing_to_replace() {
+    let mut view = view("one\n");
+    assert!(!start_replace(&mut view, "two", "three"));
+    assert_eq!(view.finish_replace(), 0);
+    assert_eq!(view.contents(), "one\n");
+  }
+
+  #[test]
+  fn replacements_are_undone_at_once() {
+    let mut view = view("a a\na\n");
+    assert!(start_replace(&mut view, "a", "b"));
+    assert!(view.replace_match());
+    assert!(view.skip_match());
+    assert!(!view.replace_match());
+    assert_eq!(view.finish_replace(), 2);
+    assert_eq!(view.contents(), "b a\nb\n");
+    view.handle_edit_command(Edit::Undo);
+    assert_eq!(view.contents(), "a a\na\n");
+  }
+}
@@ -0,0 +1,13 @@
+use crate::prelude::Location;
+
+/// State of an interactive replace, which steps through all matches once
+#[derive(Clone)]
+pub struct ReplaceInfo {
+  pub replacement: String,
+  /// Location where the replace started, it ends once we get back here
+  pub origin: Location,
+  /// True once the search wrapped around the end of the document
+  pub wrapped: bool,
+  /// Number of replaced matches so far
+  pub count: usize,
+}
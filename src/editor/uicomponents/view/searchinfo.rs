@@ -1,10 +1,17 @@
 use crate::{
-  editor::line::Line,
+  editor::{searchquery::SearchQuery, uicomponents::view::matchcounts::MatchCounts},
   prelude::{Location, Position},
 };
 
+#[derive(Clone)]
 pub struct SearchInfo {
   pub prev_location: Location,
   pub prev_scroll_offset: Position,
-  pub query: Option<Line>,
+  pub prev_scroll_sub_row: usize,
+  /// `None` while the query is invalid
+  pub query: Option<SearchQuery>,
+  /// Counted once typing pauses, as that goes through the whole document. `None` until then.
+  pub match_counts: Option<MatchCounts>,
+  /// True if the last search went past the end or the beginning of the document
+  pub wrapped: bool,
 }
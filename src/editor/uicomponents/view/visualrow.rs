@@ -0,0 +1,10 @@
+use crate::prelude::LineIdx;
+
+/// A row on screen as seen from the text: one of the rows a line is wrapped into.
+/// Without soft wrap, every line has exactly one row.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VisualRow {
+  pub line_idx: LineIdx,
+  /// Index of the row within its line
+  pub sub_row: usize,
+}
@@ -0,0 +1,83 @@
+use crate::{
+  editor::uicomponents::{StatusBar, UIComponent, View},
+  prelude::{Position, Size},
+};
+
+use std::{io::Error, mem};
+
+/// One of the areas the screen is split into: a view with its own status bar below it
+#[derive(Default)]
+pub struct Window {
+  pub view: View,
+  status_bar: StatusBar,
+  size: Size,
+}
+
+impl Window {
+  pub fn new(view: View) -> Self {
+    Self {
+      view,
+      ..Self::default()
+    }
+  }
+
+  /// Shows another view in this window, returns the one shown until now
+  pub fn replace_view(&mut self, view: View) -> View {
+    let previous = mem::replace(&mut self.view, view);
+    self.view.resize(self.view_size());
+    self.set_needs_redraw(true);
+    previous
+  }
+
+  /// Brings the view in line with edits made through other windows and updates the status bar
+  pub fn refresh_status(&mut self) {
+    self.view.sync_with_buffer();
+    self.status_bar.update_status(self.view.get_status());
+  }
+
+  /// Caret position relative to the window
+  pub fn caret_position(&self) -> Position {
+    self.view.caret_position()
+  }
+
+  /// The status bar takes the last row
+  const fn view_size(&self) -> Size {
+    Size {
+      height: self.size.height.saturating_sub(1),
+      width: self.size.width,
+    }
+  }
+}
+
+impl UIComponent for Window {
+  fn set_needs_redraw(&mut self, value: bool) {
+    self.view.set_needs_redraw(value);
+    self.status_bar.set_needs_redraw(value);
+  }
+
+  fn needs_redraw(&self) -> bool {
+    self.view.needs_redraw() || self.status_bar.needs_redraw()
+  }
+
+  fn set_size(&mut self, size: Size) {
+    self.size = size;
+    self.view.resize(self.view_size());
+    self.status_bar.resize(Size {
+      height: 1,
+      width: size.width,
+    });
+  }
+
+  fn draw(&mut self, origin: Position) -> Result<(), Error> {
+    if self.size.height == 0 {
+      return Ok(());
+    }
+    let view_height = self.view_size().height;
+    self.view.render(origin);
+    self.status_bar.render(Position {
+      row: origin.row.saturating_add(view_height),
+      col: origin.col,
+    });
+    Ok(())
+  }
+}
@@ -0,0 +1,18 @@
+#![warn(
+  clippy::all,
+  clippy::pedantic,
+  clippy::print_stdout,
+  clippy::arithmetic_side_effects,
+  clippy::as_conversions,
+  clippy::integer_division
+)]
+
+//! The editor is started by the `hecto` binary.
+//! It lives in a library so that the benchmarks in `benches/` can use it as well.
+
+mod editor;
+mod prelude;
+
+pub use editor::Editor;
+#[doc(hidden)]
+pub use editor::bench;
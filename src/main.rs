@@ -7,9 +7,7 @@
   clippy::integer_division
 )]
 
-mod editor;
-mod prelude;
-use editor::Editor;
+use hecto::Editor;
 
 fn main() {
   Editor::new().unwrap().run();
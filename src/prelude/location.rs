@@ -1,7 +1,8 @@
 use crate::prelude::{GraphemeIdx, LineIdx};
 
-#[derive(Copy, Clone, Default)]
+// Fields are ordered so that the derived ordering compares lines first, then graphemes.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Location {
-  pub grapheme_idx: GraphemeIdx,
   pub line_idx: LineIdx,
+  pub grapheme_idx: GraphemeIdx,
 }
@@ -1,6 +1,6 @@
 use crate::prelude::{ColIdx, RowIdx};
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
 pub struct Position {
   pub col: ColIdx,
   pub row: RowIdx,